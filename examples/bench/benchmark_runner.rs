@@ -17,12 +17,17 @@
 //! Provides a framework for running benchmarks with different TableDataProvider implementations.
 //! This module handles benchmark execution, configuration, and results.
 
-use super::table_data_provider::{ApiDataProvider, TableDataProvider};
+use super::table_data_provider::{ApiDataProvider, Batches, TableDataProvider};
 use greptimedb_ingester::{
     api::v1::{RowInsertRequest, RowInsertRequests, Rows as ApiRows},
     database::Database,
-    BulkInserter, BulkWriteOptions, CompressionType, Result,
+    flight::do_put::DoPutResponse,
+    table::iter_with_columns,
+    BulkInserter, BulkStreamWriter, BulkWriteOptions, CompressionType, Result, Row, Rows,
+    TableSchema, Value,
 };
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Configuration for benchmark runs
@@ -34,6 +39,10 @@ pub struct BenchmarkConfig {
     pub batch_size: usize,
     pub parallelism: usize,
     pub compression: String,
+    /// Let the bulk writer adapt the batch size, with `batch_size` as the maximum
+    pub adaptive_batching: bool,
+    /// Run even if the buffered batches are estimated not to fit into memory
+    pub allow_large_batches: bool,
 }
 
 impl Default for BenchmarkConfig {
@@ -45,32 +54,224 @@ impl Default for BenchmarkConfig {
             batch_size: 64 * 1024,
             parallelism: 4,
             compression: "lz4".to_string(),
+            adaptive_batching: false,
+            allow_large_batches: false,
+        }
+    }
+}
+
+/// Named combinations of batch size, parallelism and compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Large batches, many in-flight requests and zstd: maximize rows per second
+    Throughput,
+    /// Small batches sent one at a time without compression: minimize per-batch latency
+    Latency,
+    /// Middle ground between the two
+    Balanced,
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "throughput" => Ok(Preset::Throughput),
+            "latency" => Ok(Preset::Latency),
+            "balanced" => Ok(Preset::Balanced),
+            _ => Err(format!("unknown benchmark preset '{s}'")),
         }
     }
 }
 
 impl BenchmarkConfig {
+    /// Create configuration from a preset
+    ///
+    /// The preset only sets batch size, parallelism and compression; everything else
+    /// is the same as in a run without a preset, see [`BenchmarkConfig::from_env`].
+    ///
+    /// | Preset     | Batch size | Parallelism | Compression |
+    /// |------------|------------|-------------|-------------|
+    /// | Throughput | 100,000    | 16          | zstd        |
+    /// | Latency    | 1,000      | 1           | none        |
+    /// | Balanced   | 20,000     | 4           | lz4         |
+    pub fn preset(preset: Preset) -> Self {
+        let (batch_size, parallelism, compression) = match preset {
+            Preset::Throughput => (100_000, 16, "zstd"),
+            Preset::Latency => (1_000, 1, "none"),
+            Preset::Balanced => (20_000, 4, "lz4"),
+        };
+        Self {
+            batch_size,
+            parallelism,
+            compression: compression.to_string(),
+            ..Self::env_defaults()
+        }
+    }
+
+    /// Configuration of [`BenchmarkConfig::from_env`] when neither a preset nor any
+    /// of the variables are set
+    fn env_defaults() -> Self {
+        Self {
+            table_row_count: 2_000_000,
+            batch_size: 100_000,
+            parallelism: 8,
+            ..Self::default()
+        }
+    }
+
     /// Create configuration from environment variables
+    ///
+    /// `BENCHMARK_PRESET` selects a [`Preset`] as the base; the individual variables
+    /// still override it.
     pub fn from_env() -> Self {
+        let preset = std::env::var("BENCHMARK_PRESET")
+            .ok()
+            .and_then(|name| match name.parse() {
+                Ok(preset) => Some(preset),
+                Err(e) => {
+                    println!("Warning: {e}, ignoring it");
+                    None
+                }
+            });
+        let base = match preset {
+            Some(preset) => Self::preset(preset),
+            None => Self::env_defaults(),
+        };
+
         Self {
-            endpoint: std::env::var("GREPTIME_ENDPOINT")
-                .unwrap_or_else(|_| "localhost:4001".to_string()),
-            dbname: std::env::var("GREPTIMEDB_DBNAME").unwrap_or_else(|_| "public".to_string()),
+            endpoint: std::env::var("GREPTIME_ENDPOINT").unwrap_or(base.endpoint),
+            dbname: std::env::var("GREPTIMEDB_DBNAME").unwrap_or(base.dbname),
             table_row_count: std::env::var("TABLE_ROW_COUNT")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(2_000_000),
+                .unwrap_or(base.table_row_count),
             batch_size: std::env::var("BATCH_SIZE")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(100_000),
+                .unwrap_or(base.batch_size),
             parallelism: std::env::var("PARALLELISM")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(8),
-            compression: std::env::var("COMPRESSION").unwrap_or_else(|_| "lz4".to_string()),
+                .unwrap_or(base.parallelism),
+            compression: std::env::var("COMPRESSION").unwrap_or(base.compression),
+            adaptive_batching: std::env::var("ADAPTIVE_BATCHING")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(base.adaptive_batching),
+            allow_large_batches: std::env::var("ALLOW_LARGE_BATCHES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(base.allow_large_batches),
         }
     }
+
+    /// Check that the batches buffered by a run fit into `available_bytes` of memory
+    ///
+    /// An estimate above [`MAX_MEMORY_FRACTION`] of the available memory prints a
+    /// warning and fails, unless `allow_large_batches` is set. Without a figure for
+    /// the available memory, as on platforms other than Linux, the check passes.
+    #[allow(dead_code)] // May be unused depending on which examples are being compiled
+    pub fn check_memory(
+        &self,
+        estimate: &MemoryEstimate,
+        available_bytes: Option<u64>,
+    ) -> std::result::Result<(), String> {
+        let Some(available_bytes) = available_bytes else {
+            return Ok(());
+        };
+        if !estimate.exceeds(available_bytes) {
+            return Ok(());
+        }
+
+        println!(
+            "Warning: {} rows per batch × ~{} bytes per row × {} batches in flight need ~{} MiB, \
+             more than {:.0}% of the {} MiB of available memory",
+            estimate.batch_size,
+            estimate.row_bytes,
+            estimate.in_flight,
+            estimate.total_bytes() >> 20,
+            MAX_MEMORY_FRACTION * 100.0,
+            available_bytes >> 20
+        );
+        if self.allow_large_batches {
+            return Ok(());
+        }
+        Err(
+            "batches may not fit into memory, lower BATCH_SIZE or PARALLELISM, \
+             or set ALLOW_LARGE_BATCHES=true to run anyway"
+                .to_string(),
+        )
+    }
+}
+
+/// Share of the available memory the buffered batches may take before a run is refused
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+pub const MAX_MEMORY_FRACTION: f64 = 0.5;
+
+/// Estimated memory a run holds in batches being buffered or in flight
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    pub row_bytes: usize,
+    pub batch_size: usize,
+    pub in_flight: usize,
+}
+
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+impl MemoryEstimate {
+    /// Estimate a bulk run writing rows like `sample_row` with `config`
+    ///
+    /// Besides the `parallelism` batches in flight, one more batch is being filled.
+    pub fn for_bulk(config: &BenchmarkConfig, schema: &TableSchema, sample_row: &Row) -> Self {
+        Self {
+            row_bytes: estimate_row_bytes(schema, sample_row),
+            batch_size: config.batch_size,
+            in_flight: config.parallelism + 1,
+        }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        (self.row_bytes as u64)
+            .saturating_mul(self.batch_size as u64)
+            .saturating_mul(self.in_flight as u64)
+    }
+
+    /// Whether the estimate takes more than [`MAX_MEMORY_FRACTION`] of `available_bytes`
+    pub fn exceeds(&self, available_bytes: u64) -> bool {
+        self.total_bytes() as f64 > available_bytes as f64 * MAX_MEMORY_FRACTION
+    }
+}
+
+/// Approximate size of `row` once buffered in a columnar batch
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+fn estimate_row_bytes(schema: &TableSchema, row: &Row) -> usize {
+    iter_with_columns(row, schema)
+        .map(|(_, value)| match value {
+            // Variable length values also take an offset
+            Value::String(v) | Value::Json(v) => v.len() + 4,
+            Value::Binary(v) => v.len() + 4,
+            Value::BinaryChunks(chunks) => chunks.iter().map(|c| c.len()).sum::<usize>() + 4,
+            Value::Decimal128(_) => 16,
+            _ => 8,
+        })
+        .sum()
+}
+
+/// Memory available for new allocations, `MemAvailable` of `/proc/meminfo` on Linux
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+pub fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_mem_available(&meminfo)
+}
+
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
 }
 
 /// Results from a benchmark run
@@ -83,6 +284,8 @@ pub struct BenchmarkResult {
     pub rows_per_second: f64,
     pub success: bool,
     pub error_message: Option<String>,
+    /// Ratio of uncompressed to compressed batch bytes, for bulk runs
+    pub compression_ratio: Option<f64>,
 }
 
 impl BenchmarkResult {
@@ -95,13 +298,22 @@ impl BenchmarkResult {
             rows_per_second: 0.0,
             success: false,
             error_message: None,
+            compression_ratio: None,
         }
     }
 
-    pub fn success(mut self, duration_ms: u64) -> Self {
-        self.duration_ms = duration_ms;
-        self.rows_per_second = if duration_ms > 0 {
-            (self.total_rows as f64) / (duration_ms as f64 / 1000.0)
+    /// Mark the benchmark as successful after running for `duration`
+    ///
+    /// Throughput is computed from the full-precision duration, so sub-millisecond runs
+    /// still report a meaningful rate. A run whose elapsed time is exactly zero reports
+    /// `f64::INFINITY` if it wrote any rows, and `0.0` otherwise.
+    pub fn success(mut self, duration: Duration) -> Self {
+        self.duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        let seconds = duration.as_secs_f64();
+        self.rows_per_second = if seconds > 0.0 {
+            self.total_rows as f64 / seconds
+        } else if self.total_rows > 0 {
+            f64::INFINITY
         } else {
             0.0
         };
@@ -109,6 +321,36 @@ impl BenchmarkResult {
         self
     }
 
+    /// Record the compression ratio reported by the writer's stats
+    pub fn with_compression_ratio(mut self, compression_ratio: Option<f64>) -> Self {
+        self.compression_ratio = compression_ratio;
+        self
+    }
+
+    /// Relative change of throughput against `baseline`, e.g. `-0.25` for 25% fewer
+    /// rows per second, or `None` unless both runs succeeded with a positive baseline
+    #[allow(dead_code)] // May be unused depending on which examples are being compiled
+    pub fn throughput_change(&self, baseline: &BenchmarkResult) -> Option<f64> {
+        (self.success && baseline.success && baseline.rows_per_second > 0.0)
+            .then(|| self.rows_per_second / baseline.rows_per_second - 1.0)
+    }
+
+    /// Whether throughput dropped by more than `max_regression` against `baseline`
+    ///
+    /// `max_regression` is a fraction, so `0.1` tolerates up to 10% fewer rows per
+    /// second. A failed run regressed against a successful baseline, while a failed
+    /// baseline gives nothing to compare against and never reports a regression.
+    #[allow(dead_code)] // May be unused depending on which examples are being compiled
+    pub fn regressed_against(&self, baseline: &BenchmarkResult, max_regression: f64) -> bool {
+        if !baseline.success {
+            return false;
+        }
+        match self.throughput_change(baseline) {
+            Some(change) => change < -max_regression,
+            None => !self.success,
+        }
+    }
+
     pub fn error(mut self, error: String) -> Self {
         self.error_message = Some(error);
         self.success = false;
@@ -125,6 +367,9 @@ impl BenchmarkResult {
             println!("Total rows: {}", self.total_rows);
             println!("Duration: {}ms", self.duration_ms);
             println!("Throughput: {:.0} rows/sec", self.rows_per_second);
+            if let Some(ratio) = self.compression_ratio {
+                println!("Compression ratio: {ratio:.2}x");
+            }
         } else {
             println!("FAILED");
             if let Some(ref error) = self.error_message {
@@ -135,17 +380,84 @@ impl BenchmarkResult {
     }
 }
 
+/// Rows written by the current run of a runner, shared by both runners
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+#[derive(Debug, Clone, Default)]
+struct Progress(Arc<AtomicUsize>);
+
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+impl Progress {
+    fn handle(&self) -> Arc<AtomicUsize> {
+        self.0.clone()
+    }
+
+    fn reset(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+
+    fn advance(&self, rows: usize) {
+        self.0.fetch_add(rows, Ordering::Relaxed);
+    }
+}
+
+/// The calls of the bulk benchmark loop into its writer, so that the loop can be
+/// driven without a server
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+trait BulkBatchWriter {
+    fn recommended_batch_size(&self) -> Option<usize>;
+
+    fn alloc_rows_buffer(&self, capacity: usize, row_buffer_size: usize) -> Result<Rows>;
+
+    async fn write_rows_async(&mut self, rows: Rows) -> Result<()>;
+
+    fn flush_completed_responses(&mut self) -> Vec<DoPutResponse>;
+}
+
+impl BulkBatchWriter for BulkStreamWriter {
+    fn recommended_batch_size(&self) -> Option<usize> {
+        BulkStreamWriter::recommended_batch_size(self)
+    }
+
+    fn alloc_rows_buffer(&self, capacity: usize, row_buffer_size: usize) -> Result<Rows> {
+        BulkStreamWriter::alloc_rows_buffer(self, capacity, row_buffer_size)
+    }
+
+    async fn write_rows_async(&mut self, rows: Rows) -> Result<()> {
+        // Responses are collected from the writer's cache, not through the ack
+        BulkStreamWriter::write_rows_async(self, rows)
+            .await
+            .map(|_ack| ())
+    }
+
+    fn flush_completed_responses(&mut self) -> Vec<DoPutResponse> {
+        BulkStreamWriter::flush_completed_responses(self)
+    }
+}
+
 /// Bulk API Benchmark runner that can execute tests with any TableDataProvider
 #[allow(dead_code)] // May be unused depending on which examples are being compiled
 pub struct BulkApiBenchmarkRunner {
     config: BenchmarkConfig,
+    progress: Progress,
 }
 
 #[allow(dead_code)] // May be unused depending on which examples are being compiled
 impl BulkApiBenchmarkRunner {
     /// Create a new benchmark runner with configuration
     pub fn new(config: BenchmarkConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Counter of the rows written by the current run, e.g. to render a progress bar
+    /// or serve a status endpoint from another thread or task
+    ///
+    /// Take it before the run; it is reset to zero when a run starts and advanced
+    /// after every batch handed to the writer.
+    pub fn progress_handle(&self) -> Arc<AtomicUsize> {
+        self.progress.handle()
     }
 
     /// Run a bulk API benchmark with the given provider using the zero-cost Rows API
@@ -164,6 +476,15 @@ impl BulkApiBenchmarkRunner {
         if let Err(e) = provider.init() {
             return result.error(format!("Failed to initialize provider: {e:?}"));
         }
+        if let Err(e) = provider.self_check() {
+            return result.error(format!("Provider rows do not match its schema: {e:?}"));
+        }
+        if let Some(row) = provider.sample_row() {
+            let estimate = MemoryEstimate::for_bulk(&self.config, &table_schema, &row);
+            if let Err(e) = self.config.check_memory(&estimate, available_memory()) {
+                return result.error(e);
+            }
+        }
 
         // Create client and database
         let client = match self.create_client().await {
@@ -176,7 +497,11 @@ impl BulkApiBenchmarkRunner {
         // Create bulk stream writer
         println!("Setting up bulk stream writer...");
         let mut bulk_writer = match bulk_inserter
-            .create_bulk_stream_writer(&table_schema, Some(self.create_bulk_options()))
+            .create_bulk_stream_writer_auto(
+                &table_schema,
+                &[("append_mode", "true"), ("skip_wal", "true")],
+                Some(self.create_bulk_options()),
+            )
             .await
         {
             Ok(writer) => writer,
@@ -195,44 +520,99 @@ impl BulkApiBenchmarkRunner {
         println!();
 
         let start_time = Instant::now();
+        let (rows_written, batch_count) = self
+            .write_batches(&mut provider, &mut bulk_writer, total_rows, start_time)
+            .await;
+
+        // Finish writing
+        println!("Finishing bulk writer and waiting for all responses...");
+        if let Err(e) = bulk_writer.wait_for_all_pending().await {
+            return result.error(format!("Failed to finish bulk writer: {e:?}"));
+        }
+        let metrics = bulk_writer.metrics();
+        let stats = bulk_writer.stats();
+        if let Err(e) = bulk_writer.finish_with_responses().await {
+            return result.error(format!("Failed to finish bulk writer: {e:?}"));
+        }
+        println!("All bulk writes completed successfully");
+
+        // Cleanup provider
+        println!("Cleaning up data provider...");
+        if let Err(e) = provider.close() {
+            return result.error(format!("Failed to close provider: {e:?}"));
+        }
+
+        let duration = start_time.elapsed();
+        println!("Bulk API benchmark completed successfully!");
+        println!("Final Result:");
+        println!("  • Total rows: {rows_written}");
+        println!("  • Total batches: {batch_count}");
+        println!("  • Duration: {:.2}s", duration.as_secs_f64());
+        println!(
+            "  • Buffering time: {:.2}s, network time: {:.2}s",
+            metrics.buffering_time.as_secs_f64(),
+            metrics.network_time.as_secs_f64()
+        );
+        println!(
+            "  • Throughput: {:.0} rows/sec",
+            rows_written as f64 / duration.as_secs_f64()
+        );
+        println!(
+            "  • Bytes sent: {} uncompressed, {} compressed",
+            stats.uncompressed_bytes, stats.compressed_bytes
+        );
+        println!("  • Affected rows: {}", stats.affected_rows);
+        println!();
+
+        result
+            .with_compression_ratio(stats.compression_ratio())
+            .success(duration)
+    }
+
+    /// Write the rows of `provider` in batches, returning the rows and batches
+    /// written; stops at the first batch that fails
+    async fn write_batches<P: TableDataProvider>(
+        &self,
+        provider: &mut P,
+        writer: &mut impl BulkBatchWriter,
+        total_rows: usize,
+        start_time: Instant,
+    ) -> (usize, usize) {
         let mut rows_written = 0;
         let mut batch_count = 0;
+        self.progress.reset();
 
-        let mut row_iter = provider.rows();
+        let batch_size = self.config.batch_size;
+        let mut batches = Batches::new(batch_size, total_rows);
 
         loop {
-            let mut break_out = false;
-            let batch_size = self.config.batch_size;
-            let mut rows_buf = bulk_writer.alloc_rows_buffer(batch_size, 1024).unwrap();
-            for _ in 0..batch_size {
-                if let Some(row) = row_iter.next() {
-                    rows_buf.add_row(row).unwrap();
-                } else {
-                    break_out = true;
-                    break;
-                }
-            }
-
-            let len = rows_buf.len();
-            if len == 0 {
+            let batch_size = writer.recommended_batch_size().unwrap_or(batch_size);
+            batches.set_batch_size(batch_size);
+            let Some(batch) = batches.next_rows(provider) else {
                 break;
+            };
+            let mut rows_buf = writer.alloc_rows_buffer(batch_size, 1024).unwrap();
+            for row in batch {
+                rows_buf.add_row(row).unwrap();
             }
 
-            rows_written += len;
+            rows_written += rows_buf.len();
             batch_count += 1;
 
-            let res = bulk_writer.write_rows_async(rows_buf).await;
+            let batch_len = rows_buf.len();
+            let res = writer.write_rows_async(rows_buf).await;
             if let Err(e) = res {
                 println!("Failed to write rows: {e:?}");
                 break;
             }
+            self.progress.advance(batch_len);
 
             let elapsed = start_time.elapsed();
             let rate = rows_written as f64 / elapsed.as_secs_f64();
             println!("→ Batch {batch_count}: {rows_written} rows processed ({rate:.0} rows/sec)");
 
             if batch_count % 10 == 0 {
-                let responses = bulk_writer.flush_completed_responses();
+                let responses = writer.flush_completed_responses();
                 if !responses.is_empty() {
                     let total_affected_rows: usize =
                         responses.iter().map(|r| r.affected_rows()).sum();
@@ -243,48 +623,18 @@ impl BulkApiBenchmarkRunner {
                     );
                 }
             }
-
-            if break_out {
-                break;
-            }
-        }
-
-        // Finish writing
-        println!("Finishing bulk writer and waiting for all responses...");
-        if let Err(e) = bulk_writer.finish_with_responses().await {
-            return result.error(format!("Failed to finish bulk writer: {e:?}"));
         }
-        println!("All bulk writes completed successfully");
 
-        // Drop the iterator to release the mutable borrow
-        drop(row_iter);
-
-        // Cleanup provider
-        println!("Cleaning up data provider...");
-        if let Err(e) = provider.close() {
-            return result.error(format!("Failed to close provider: {e:?}"));
-        }
-
-        let duration = start_time.elapsed();
-        println!("Bulk API benchmark completed successfully!");
-        println!("Final Result:");
-        println!("  • Total rows: {rows_written}");
-        println!("  • Total batches: {batch_count}");
-        println!("  • Duration: {:.2}s", duration.as_secs_f64());
-        println!(
-            "  • Throughput: {:.0} rows/sec",
-            rows_written as f64 / duration.as_secs_f64()
-        );
-        println!();
-
-        result.success(duration.as_millis() as u64)
+        (rows_written, batch_count)
     }
 
     /// Create GreptimeDB client
     async fn create_client(&self) -> Result<greptimedb_ingester::client::Client> {
+        // Survive server restarts during long runs
         let client = greptimedb_ingester::client::Client::with_urls(std::slice::from_ref(
             &self.config.endpoint,
-        ));
+        ))
+        .with_reconnect(true);
         Ok(client)
     }
 
@@ -303,10 +653,16 @@ impl BulkApiBenchmarkRunner {
             }
         };
 
-        BulkWriteOptions::default()
+        let options = BulkWriteOptions::default()
             .with_compression(compression)
             .with_parallelism(self.config.parallelism)
-            .with_timeout(Duration::from_secs(60))
+            .with_timeout(Duration::from_secs(60));
+        if self.config.adaptive_batching {
+            let max_rows = self.config.batch_size;
+            options.with_adaptive_batching(max_rows / 10, max_rows)
+        } else {
+            options
+        }
     }
 
     /// Display system information
@@ -318,6 +674,10 @@ impl BulkApiBenchmarkRunner {
         println!("Batch size: {}", self.config.batch_size);
         println!("Parallelism: {}", self.config.parallelism);
         println!("Compression: {}", self.config.compression);
+        println!("Adaptive batching: {}", self.config.adaptive_batching);
+        if let Some(available) = available_memory() {
+            println!("Available memory: {} MiB", available >> 20);
+        }
 
         if let Ok(hostname) = std::env::var("HOSTNAME") {
             println!("Hostname: {hostname}");
@@ -409,17 +769,43 @@ pub fn show_benchmark_result(results: &[BenchmarkResult]) {
     }
 }
 
+/// Describe the throughput change of `current` against `baseline`
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+pub fn compare_results(baseline: &BenchmarkResult, current: &BenchmarkResult) -> String {
+    let name = &current.provider_name;
+    match current.throughput_change(baseline) {
+        Some(change) => format!(
+            "{name}: {:.0} rows/sec vs baseline {:.0} rows/sec ({:+.1}%)",
+            current.rows_per_second,
+            baseline.rows_per_second,
+            change * 100.0
+        ),
+        None if !current.success => format!("{name}: FAILED, baseline has nothing to compare"),
+        None => format!("{name}: no successful baseline to compare against"),
+    }
+}
+
 /// Regular API Benchmark Runner
 /// Uses Database::insert() API for performance testing
 #[allow(dead_code)] // May be unused depending on which examples are being compiled
 pub struct RegularApiBenchmarkRunner {
     config: BenchmarkConfig,
+    progress: Progress,
 }
 
 #[allow(dead_code)] // May be unused depending on which examples are being compiled
 impl RegularApiBenchmarkRunner {
     pub fn new(config: BenchmarkConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Counter of the rows written by the current run, see
+    /// [`BulkApiBenchmarkRunner::progress_handle`]
+    pub fn progress_handle(&self) -> Arc<AtomicUsize> {
+        self.progress.handle()
     }
 
     /// Run regular API benchmark using a provider that implements ApiDataProvider
@@ -457,28 +843,13 @@ impl RegularApiBenchmarkRunner {
         let mut rows_written = 0;
         let mut batch_count = 0;
         let mut total_latency = Duration::new(0, 0);
+        self.progress.reset();
 
         // Use regular API to insert data in batches
-        let mut row_iter = provider.api_rows();
-        let mut batch_rows = Vec::new();
+        let mut batches = Batches::new(self.config.batch_size, total_rows);
 
-        loop {
-            // Collect batch from row iterator
-            batch_rows.clear();
-            let mut batch_complete = false;
-
-            for _ in 0..self.config.batch_size {
-                if let Some(api_row) = row_iter.next() {
-                    batch_rows.push(api_row);
-                } else {
-                    batch_complete = true;
-                    break;
-                }
-            }
-
-            if batch_rows.is_empty() {
-                break;
-            }
+        while let Some(batch_rows) = batches.next_api_rows(&mut provider) {
+            let batch_len = batch_rows.len();
 
             // Create insert request
             let insert_request = RowInsertRequests {
@@ -486,7 +857,7 @@ impl RegularApiBenchmarkRunner {
                     table_name: table_name.to_string(),
                     rows: Some(ApiRows {
                         schema: column_schema.clone(),
-                        rows: batch_rows.clone(),
+                        rows: batch_rows,
                     }),
                 }],
             };
@@ -498,7 +869,8 @@ impl RegularApiBenchmarkRunner {
                 Ok(affected_rows) => {
                     let batch_latency = batch_start.elapsed();
                     total_latency += batch_latency;
-                    rows_written += batch_rows.len();
+                    rows_written += batch_len;
+                    self.progress.advance(batch_len);
                     batch_count += 1;
 
                     let elapsed = start_time.elapsed();
@@ -506,7 +878,7 @@ impl RegularApiBenchmarkRunner {
                     println!(
                         "→ Batch {}: {} rows processed, {} affected ({:.0} rows/sec, {:.2}ms latency)",
                         batch_count,
-                        batch_rows.len(),
+                        batch_len,
                         affected_rows,
                         rate,
                         batch_latency.as_secs_f64() * 1000.0
@@ -516,10 +888,6 @@ impl RegularApiBenchmarkRunner {
                     return result.error(format!("Failed to insert batch {batch_count}: {e:?}"));
                 }
             }
-
-            if batch_complete {
-                break;
-            }
         }
 
         let duration = start_time.elapsed();
@@ -541,22 +909,21 @@ impl RegularApiBenchmarkRunner {
         println!("  • Average latency: {avg_latency:.2}ms");
         println!();
 
-        // Drop the iterator to release the mutable borrow
-        drop(row_iter);
-
         // Cleanup provider
         if let Err(e) = provider.close() {
             return result.error(format!("Failed to close provider: {e:?}"));
         }
 
-        result.success(duration.as_millis() as u64)
+        result.success(duration)
     }
 
     /// Create GreptimeDB client
     async fn create_client(&self) -> Result<greptimedb_ingester::client::Client> {
+        // Survive server restarts during long runs
         let client = greptimedb_ingester::client::Client::with_urls(std::slice::from_ref(
             &self.config.endpoint,
-        ));
+        ))
+        .with_reconnect(true);
         Ok(client)
     }
 
@@ -588,3 +955,178 @@ impl RegularApiBenchmarkRunner {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let cases = [
+            (Preset::Throughput, 100_000, 16, "zstd"),
+            (Preset::Latency, 1_000, 1, "none"),
+            (Preset::Balanced, 20_000, 4, "lz4"),
+        ];
+        for (preset, batch_size, parallelism, compression) in cases {
+            let config = BenchmarkConfig::preset(preset);
+            assert_eq!(config.batch_size, batch_size, "{preset:?}");
+            assert_eq!(config.parallelism, parallelism, "{preset:?}");
+            assert_eq!(config.compression, compression, "{preset:?}");
+            // Everything but the three settings is the same as without a preset
+            assert_eq!(config.table_row_count, 2_000_000, "{preset:?}");
+            assert_eq!(config.endpoint, BenchmarkConfig::default().endpoint);
+        }
+
+        assert_eq!("Throughput".parse::<Preset>(), Ok(Preset::Throughput));
+        assert!("fastest".parse::<Preset>().is_err());
+    }
+
+    #[test]
+    fn test_memory_guard() {
+        let estimate = MemoryEstimate {
+            row_bytes: 1_600,
+            batch_size: 100_000,
+            in_flight: 9,
+        };
+        // ~1.4 GB of batches on a machine with 2 GiB available
+        let available = Some(2 << 30);
+        assert!(estimate.exceeds(2 << 30));
+
+        let config = BenchmarkConfig::default();
+        let error = config.check_memory(&estimate, available).unwrap_err();
+        assert!(error.contains("ALLOW_LARGE_BATCHES"));
+
+        let config = BenchmarkConfig {
+            allow_large_batches: true,
+            ..BenchmarkConfig::default()
+        };
+        assert!(config.check_memory(&estimate, available).is_ok());
+
+        let small = MemoryEstimate {
+            batch_size: 1_000,
+            ..estimate
+        };
+        assert!(BenchmarkConfig::default()
+            .check_memory(&small, available)
+            .is_ok());
+        // Unknown available memory never blocks a run
+        assert!(BenchmarkConfig::default()
+            .check_memory(&estimate, None)
+            .is_ok());
+
+        let meminfo = "MemTotal:       16318412 kB\nMemAvailable:    8159206 kB\n";
+        assert_eq!(parse_mem_available(meminfo), Some(8_159_206 * 1024));
+        assert_eq!(parse_mem_available("MemTotal: 1 kB"), None);
+    }
+
+    /// A writer accepting every batch without a server, recording the batch sizes
+    struct StubWriter {
+        schema: TableSchema,
+        batch_sizes: Vec<usize>,
+    }
+
+    impl BulkBatchWriter for StubWriter {
+        fn recommended_batch_size(&self) -> Option<usize> {
+            None
+        }
+
+        fn alloc_rows_buffer(&self, capacity: usize, row_buffer_size: usize) -> Result<Rows> {
+            Rows::new(self.schema.columns(), capacity, row_buffer_size)
+        }
+
+        async fn write_rows_async(&mut self, rows: Rows) -> Result<()> {
+            self.batch_sizes.push(rows.len());
+            Ok(())
+        }
+
+        fn flush_completed_responses(&mut self) -> Vec<DoPutResponse> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_handle_tracks_written_rows() {
+        use crate::bench::LogTableDataProvider;
+
+        let config = BenchmarkConfig {
+            table_row_count: 1_000,
+            batch_size: 64,
+            ..BenchmarkConfig::default()
+        };
+        let mut provider = LogTableDataProvider::new("logs", &config);
+        let runner = BulkApiBenchmarkRunner::new(config);
+        let progress = runner.progress_handle();
+        let total_rows = provider.row_count();
+        assert_eq!(progress.load(Ordering::Relaxed), 0);
+
+        let observer = std::thread::spawn(move || {
+            let mut seen = vec![progress.load(Ordering::Relaxed)];
+            while *seen.last().unwrap() < total_rows {
+                let current = progress.load(Ordering::Relaxed);
+                if current != *seen.last().unwrap() {
+                    seen.push(current);
+                }
+                std::thread::yield_now();
+            }
+            seen
+        });
+
+        let mut writer = StubWriter {
+            schema: provider.table_schema(),
+            batch_sizes: Vec::new(),
+        };
+        let (rows_written, batch_count) = runner
+            .write_batches(&mut provider, &mut writer, total_rows, Instant::now())
+            .await;
+
+        let seen = observer.join().unwrap();
+        assert_eq!(seen.last(), Some(&total_rows));
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(rows_written, total_rows);
+        assert_eq!(batch_count, total_rows.div_ceil(64));
+        assert_eq!(writer.batch_sizes.iter().sum::<usize>(), total_rows);
+        assert!(writer.batch_sizes.iter().all(|&size| size <= 64));
+    }
+
+    #[test]
+    fn test_regression_against_baseline() {
+        let baseline =
+            BenchmarkResult::new("provider", "table", 1_000).success(Duration::from_secs(1));
+        let current =
+            BenchmarkResult::new("provider", "table", 1_000).success(Duration::from_millis(1250));
+
+        // 800 rows/sec is 20% below the baseline's 1000
+        assert!(current.regressed_against(&baseline, 0.1));
+        assert!(!current.regressed_against(&baseline, 0.25));
+        assert!(!baseline.regressed_against(&current, 0.0));
+        assert_eq!(
+            compare_results(&baseline, &current),
+            "provider: 800 rows/sec vs baseline 1000 rows/sec (-20.0%)"
+        );
+
+        let failed = BenchmarkResult::new("provider", "table", 1_000).error("timeout".to_string());
+        assert!(failed.regressed_against(&baseline, 0.5));
+        assert!(!current.regressed_against(&failed, 0.0));
+    }
+
+    #[test]
+    fn test_success_with_sub_millisecond_duration() {
+        let result =
+            BenchmarkResult::new("provider", "table", 100).success(Duration::from_micros(500));
+        assert_eq!(result.duration_ms, 0);
+        assert_eq!(result.rows_per_second, 200_000.0);
+
+        let result = BenchmarkResult::new("provider", "table", 100).success(Duration::from_secs(2));
+        assert_eq!(result.duration_ms, 2000);
+        assert_eq!(result.rows_per_second, 50.0);
+    }
+
+    #[test]
+    fn test_success_with_zero_duration() {
+        let result = BenchmarkResult::new("provider", "table", 100).success(Duration::ZERO);
+        assert_eq!(result.rows_per_second, f64::INFINITY);
+
+        let result = BenchmarkResult::new("provider", "table", 0).success(Duration::ZERO);
+        assert_eq!(result.rows_per_second, 0.0);
+    }
+}
@@ -25,10 +25,15 @@ use greptimedb_ingester::api::v1::{
     ColumnDataType as ApiColumnDataType, ColumnSchema, Row as ApiRow, SemanticType,
 };
 use greptimedb_ingester::helpers::values::*;
-use greptimedb_ingester::{ColumnDataType, Row, TableSchema, Value};
+use greptimedb_ingester::{
+    ColumnDataType, ColumnOptions, FulltextOptions, Row, TableSchema, Value,
+};
 use rand::RngCore;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Number of columns of the log table
+const COLUMN_COUNT: usize = 22;
+
 /// LogTableDataProvider that generates synthetic log data
 /// Following the Java implementation with 22 columns
 pub struct LogTableDataProvider {
@@ -210,8 +215,22 @@ impl LogTableDataProvider {
 
     /// Generate a single log row using ultra-fast pre-generated pools
     fn generate_row(&mut self) -> Option<Row> {
+        let mut row = Row::with_capacity(COLUMN_COUNT);
+        self.generate_row_into(&mut row).then_some(row)
+    }
+
+    /// Overwrite `row` with the next log row, returning `false` once all rows were
+    /// generated
+    ///
+    /// Strings are copied from the value pools into the buffers `row` already holds,
+    /// so refilling the same row allocates nothing after the first call. `Iterator`
+    /// cannot lend items that borrow from the iterator, which is why `rows()` still
+    /// hands out owned rows: use this directly where a row is only read before the
+    /// next one is generated, and clone it where it has to outlive that.
+    #[allow(dead_code)] // May be unused depending on which examples are being compiled
+    pub fn generate_row_into(&mut self, row: &mut Row) -> bool {
         if self.current_row >= self.row_count {
-            return None;
+            return false;
         }
 
         // Ultra-fast index calculation using bit operations for better performance
@@ -241,31 +260,30 @@ impl LogTableDataProvider {
 
         self.current_row += 1;
 
-        // Directly create values using references to avoid unnecessary clones
-        Some(Row::new().add_values(vec![
-            Value::TimestampMillisecond(timestamp),
-            Value::String(log_uid.clone()),
-            Value::String(log_message.clone()),
-            Value::String(log_level.clone()),
-            Value::String(self.host_ids[idx1].clone()),
-            Value::String(self.host_names[idx1].clone()),
-            Value::String(self.service_ids[idx2].clone()),
-            Value::String(self.service_names[idx2].clone()),
-            Value::String(self.container_ids[idx3].clone()),
-            Value::String(self.container_names[idx3].clone()),
-            Value::String(self.pod_ids[idx4].clone()),
-            Value::String(self.pod_names[idx4].clone()),
-            Value::String(self.cluster_ids[idx5].clone()),
-            Value::String(self.cluster_names[idx5].clone()),
-            Value::String(self.trace_ids[idx1].clone()),
-            Value::String(self.span_ids[idx2].clone()),
-            Value::String(self.user_ids[idx3].clone()),
-            Value::String(self.session_ids[idx4].clone()),
-            Value::String(self.request_ids[idx5].clone()),
-            Value::Int64(response_time_ms),
-            Value::String("application".to_string()),
-            Value::String("v1.0.0".to_string()),
-        ]))
+        // Values are written in place, in the same order as the schema
+        row.set_value(0, Value::TimestampMillisecond(timestamp));
+        row.set_string(1, log_uid);
+        row.set_string(2, log_message);
+        row.set_string(3, log_level);
+        row.set_string(4, &self.host_ids[idx1]);
+        row.set_string(5, &self.host_names[idx1]);
+        row.set_string(6, &self.service_ids[idx2]);
+        row.set_string(7, &self.service_names[idx2]);
+        row.set_string(8, &self.container_ids[idx3]);
+        row.set_string(9, &self.container_names[idx3]);
+        row.set_string(10, &self.pod_ids[idx4]);
+        row.set_string(11, &self.pod_names[idx4]);
+        row.set_string(12, &self.cluster_ids[idx5]);
+        row.set_string(13, &self.cluster_names[idx5]);
+        row.set_string(14, &self.trace_ids[idx1]);
+        row.set_string(15, &self.span_ids[idx2]);
+        row.set_string(16, &self.user_ids[idx3]);
+        row.set_string(17, &self.session_ids[idx4]);
+        row.set_string(18, &self.request_ids[idx5]);
+        row.set_value(19, Value::Int64(response_time_ms));
+        row.set_string(20, "application");
+        row.set_string(21, "v1.0.0");
+        true
     }
 
     /// Generate a single api::v1::Row for regular API
@@ -346,7 +364,11 @@ impl TableDataProvider for LogTableDataProvider {
             .unwrap()
             .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
             .add_field("log_uid", ColumnDataType::String)
-            .add_field("log_message", ColumnDataType::String)
+            .add_field_with_options(
+                "log_message",
+                ColumnDataType::String,
+                ColumnOptions::new().with_fulltext(FulltextOptions::new()),
+            )
             .add_field("log_level", ColumnDataType::String)
             .add_field("host_id", ColumnDataType::String)
             .add_field("host_name", ColumnDataType::String)
@@ -371,6 +393,20 @@ impl TableDataProvider for LogTableDataProvider {
     fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_> {
         Box::new(LogRowIterator { provider: self })
     }
+
+    fn rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = Row> + '_> {
+        // Rows are generated from `current_row`, so seeking is a matter of moving it
+        self.current_row = start;
+        self.rows()
+    }
+
+    fn sample_row(&mut self) -> Option<Row> {
+        // Generate the next row and rewind, so the run still starts from it
+        let current_row = self.current_row;
+        let row = self.generate_row();
+        self.current_row = current_row;
+        row
+    }
 }
 
 impl ApiDataProvider for LogTableDataProvider {
@@ -396,6 +432,9 @@ impl ApiDataProvider for LogTableDataProvider {
                 column_name: "log_message".to_string(),
                 datatype: ApiColumnDataType::String as i32,
                 semantic_type: SemanticType::Field as i32,
+                options: ColumnOptions::new()
+                    .with_fulltext(FulltextOptions::new())
+                    .to_api(),
                 ..Default::default()
             },
             ColumnSchema {
@@ -517,9 +556,13 @@ impl ApiDataProvider for LogTableDataProvider {
     }
 
     fn api_rows(&mut self) -> Box<dyn Iterator<Item = ApiRow> + '_> {
+        self.api_rows_from(0)
+    }
+
+    fn api_rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = ApiRow> + '_> {
         Box::new(ApiRowIterator {
             provider: self,
-            current_row: 0,
+            current_row: start,
         })
     }
 }
@@ -560,3 +603,81 @@ impl<'a> Iterator for LogRowIterator<'a> {
         self.provider.generate_row()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_schema_timestamp_column() {
+        let config = BenchmarkConfig {
+            table_row_count: 10,
+            ..BenchmarkConfig::default()
+        };
+        let provider = LogTableDataProvider::new("logs", &config);
+        let schema = provider.table_schema();
+
+        assert_eq!(schema.timestamp_index(), Some(0));
+        let column = schema.timestamp_column().unwrap();
+        assert_eq!(column.name, "ts");
+        assert_eq!(column.data_type, ColumnDataType::TimestampMillisecond);
+
+        let fields_only = TableSchema::builder()
+            .name("logs")
+            .build()
+            .unwrap()
+            .add_field("log_message", ColumnDataType::String);
+        assert!(fields_only.timestamp_column().is_none());
+        assert_eq!(fields_only.timestamp_index(), None);
+    }
+
+    #[test]
+    fn test_generate_row_into_reused_row() {
+        let config = BenchmarkConfig {
+            table_row_count: 5,
+            ..BenchmarkConfig::default()
+        };
+        let mut provider = LogTableDataProvider::new("logs", &config);
+        let expected: Vec<Row> = provider.rows_from(0).collect();
+        assert_eq!(expected.len(), 5);
+
+        provider.current_row = 0;
+        let schema = provider.table_schema();
+        let mut row = Row::new();
+        let mut generated = 0;
+        while provider.generate_row_into(&mut row) {
+            assert_eq!(row.len(), COLUMN_COUNT);
+            schema.validate_row(&row).unwrap();
+            let expected = &expected[generated];
+            assert_eq!(row.get_timestamp(0), expected.get_timestamp(0));
+            for index in 1..COLUMN_COUNT {
+                if index == 19 {
+                    assert_eq!(row.get_i64(index), expected.get_i64(index));
+                } else {
+                    assert_eq!(row.get_string(index), expected.get_string(index));
+                }
+            }
+            generated += 1;
+        }
+        assert_eq!(generated, 5);
+    }
+
+    #[test]
+    fn test_random_rows_match_log_schema() {
+        use rand::SeedableRng;
+
+        let config = BenchmarkConfig {
+            table_row_count: 10,
+            ..BenchmarkConfig::default()
+        };
+        let schema = LogTableDataProvider::new("logs", &config).table_schema();
+        assert!(schema.columns()[2].options.fulltext.is_some());
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for _ in 0..1000 {
+            let row = greptimedb_ingester::random_row(&schema, &mut rng);
+            assert_eq!(row.len(), COLUMN_COUNT);
+            schema.validate_row(&row).unwrap();
+        }
+    }
+}
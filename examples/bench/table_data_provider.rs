@@ -37,6 +37,15 @@ pub trait DataProvider {
     fn close(&mut self) -> Result<()> {
         Ok(())
     }
+
+    /// Called by the runners before the rows of batch `batch_index` are taken
+    ///
+    /// Use this and [`DataProvider::on_batch_end`] for per-batch work such as rotating
+    /// files or reseeding a generator. Both do nothing by default.
+    fn on_batch_start(&mut self, _batch_index: usize) {}
+
+    /// Called by the runners once the rows of batch `batch_index` were taken
+    fn on_batch_end(&mut self, _batch_index: usize) {}
 }
 
 /// Trait for providers that generate `Row` data for the bulk API
@@ -46,6 +55,36 @@ pub trait TableDataProvider: DataProvider {
 
     /// Get an iterator over the `Row` objects
     fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_>;
+
+    /// Get an iterator over the `Row` objects, starting at row `start`
+    ///
+    /// The runners take a new iterator for every batch, so the provider is free for
+    /// the batch hooks in between. The default skips `start` rows of a fresh `rows()`
+    /// iterator; providers that can seek, or whose iterator consumes shared state,
+    /// must override it.
+    fn rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = Row> + '_> {
+        Box::new(self.rows().skip(start))
+    }
+
+    /// Get a representative row without advancing the provider
+    ///
+    /// The default takes the first row of a fresh `rows()` iterator; providers whose
+    /// iterator consumes shared state must override it.
+    fn sample_row(&mut self) -> Option<Row> {
+        self.rows().next()
+    }
+
+    /// Check that a sample row matches `table_schema()` in arity and cell types
+    ///
+    /// Run this before a long benchmark so a provider whose rows drifted from its
+    /// schema fails immediately instead of after the first batch.
+    fn self_check(&mut self) -> Result<()> {
+        let schema = self.table_schema();
+        match self.sample_row() {
+            Some(row) => schema.validate_row(&row),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Trait for providers that generate `ApiRow` data for the regular API
@@ -58,4 +97,365 @@ pub trait ApiDataProvider: DataProvider {
 
     /// Get an iterator over the `ApiRow` objects
     fn api_rows(&mut self) -> Box<dyn Iterator<Item = ApiRow> + '_>;
+
+    /// Get an iterator over the `ApiRow` objects, starting at row `start`
+    ///
+    /// See [`TableDataProvider::rows_from`].
+    fn api_rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = ApiRow> + '_> {
+        Box::new(self.api_rows().skip(start))
+    }
+}
+
+/// Splits the rows of a provider into batches, calling its batch hooks around each one
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+pub struct Batches {
+    batch_size: usize,
+    total_rows: usize,
+    rows_taken: usize,
+    batch_count: usize,
+}
+
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+impl Batches {
+    /// Split `total_rows` rows into batches of at most `batch_size` rows
+    pub fn new(batch_size: usize, total_rows: usize) -> Self {
+        Self {
+            batch_size,
+            total_rows,
+            rows_taken: 0,
+            batch_count: 0,
+        }
+    }
+
+    /// Change the size of the batches taken from now on, e.g. to follow
+    /// `BulkStreamWriter::recommended_batch_size`
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size;
+    }
+
+    /// Number of batches taken so far
+    pub fn batch_count(&self) -> usize {
+        self.batch_count
+    }
+
+    /// Take the rows of the next batch, or `None` once the provider is exhausted
+    pub fn next_rows<P: TableDataProvider>(&mut self, provider: &mut P) -> Option<Vec<Row>> {
+        self.next_with(provider, |provider, start, len| {
+            provider.rows_from(start).take(len).collect()
+        })
+    }
+
+    /// Take the `ApiRow`s of the next batch, or `None` once the provider is exhausted
+    pub fn next_api_rows<P: ApiDataProvider>(&mut self, provider: &mut P) -> Option<Vec<ApiRow>> {
+        self.next_with(provider, |provider, start, len| {
+            provider.api_rows_from(start).take(len).collect()
+        })
+    }
+
+    fn next_with<P: DataProvider, T>(
+        &mut self,
+        provider: &mut P,
+        take: impl FnOnce(&mut P, usize, usize) -> Vec<T>,
+    ) -> Option<Vec<T>> {
+        let len = self.batch_size.min(self.total_rows - self.rows_taken);
+        if len == 0 {
+            return None;
+        }
+
+        let batch_index = self.batch_count;
+        provider.on_batch_start(batch_index);
+        let batch = take(provider, self.rows_taken, len);
+        provider.on_batch_end(batch_index);
+        if batch.is_empty() {
+            return None;
+        }
+
+        self.rows_taken += batch.len();
+        self.batch_count += 1;
+        Some(batch)
+    }
+}
+
+/// Adapter that skips the first `offset` rows of a provider and yields at most `limit` rows
+///
+/// This lets N workers of a sharded benchmark cover disjoint ranges of the same
+/// deterministic provider, e.g. worker `i` of `n` wraps it with
+/// `LimitProvider::new(provider, i * slice, slice)` where `slice = row_count / n`.
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+pub struct LimitProvider<P> {
+    inner: P,
+    offset: usize,
+    limit: usize,
+}
+
+#[allow(dead_code)] // May be unused depending on which examples are being compiled
+impl<P> LimitProvider<P> {
+    /// Wrap `inner`, skipping `offset` rows and yielding at most `limit` rows
+    pub fn new(inner: P, offset: usize, limit: usize) -> Self {
+        Self {
+            inner,
+            offset,
+            limit,
+        }
+    }
+
+    /// Get the wrapped provider back
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: DataProvider> DataProvider for LimitProvider<P> {
+    fn init(&mut self) -> Result<()> {
+        self.inner.init()
+    }
+
+    fn row_count(&self) -> usize {
+        self.inner
+            .row_count()
+            .saturating_sub(self.offset)
+            .min(self.limit)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.inner.close()
+    }
+}
+
+impl<P: TableDataProvider> TableDataProvider for LimitProvider<P> {
+    fn table_schema(&self) -> TableSchema {
+        self.inner.table_schema()
+    }
+
+    fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_> {
+        let (offset, limit) = (self.offset, self.limit);
+        Box::new(self.inner.rows().skip(offset).take(limit))
+    }
+
+    fn rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = Row> + '_> {
+        let (offset, limit) = (self.offset, self.limit);
+        Box::new(
+            self.inner
+                .rows_from(offset + start)
+                .take(limit.saturating_sub(start)),
+        )
+    }
+
+    fn sample_row(&mut self) -> Option<Row> {
+        self.inner.sample_row()
+    }
+}
+
+impl<P: ApiDataProvider> ApiDataProvider for LimitProvider<P> {
+    fn table_name(&self) -> &str {
+        self.inner.table_name()
+    }
+
+    fn api_schema(&self) -> Vec<ColumnSchema> {
+        self.inner.api_schema()
+    }
+
+    fn api_rows(&mut self) -> Box<dyn Iterator<Item = ApiRow> + '_> {
+        let (offset, limit) = (self.offset, self.limit);
+        Box::new(self.inner.api_rows().skip(offset).take(limit))
+    }
+
+    fn api_rows_from(&mut self, start: usize) -> Box<dyn Iterator<Item = ApiRow> + '_> {
+        let (offset, limit) = (self.offset, self.limit);
+        Box::new(
+            self.inner
+                .api_rows_from(offset + start)
+                .take(limit.saturating_sub(start)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use greptimedb_ingester::{ColumnDataType, Value};
+
+    /// Deterministic provider yielding rows `0..row_count`
+    struct SequenceProvider {
+        row_count: usize,
+    }
+
+    impl DataProvider for SequenceProvider {
+        fn row_count(&self) -> usize {
+            self.row_count
+        }
+    }
+
+    impl TableDataProvider for SequenceProvider {
+        fn table_schema(&self) -> TableSchema {
+            TableSchema::builder()
+                .name("sequence")
+                .build()
+                .unwrap()
+                .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+                .add_field("seq", ColumnDataType::Int64)
+        }
+
+        fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_> {
+            Box::new(
+                (0..self.row_count as i64).map(|i| {
+                    Row::from_values(vec![Value::TimestampMillisecond(i), Value::Int64(i)])
+                }),
+            )
+        }
+    }
+
+    /// Provider whose rows lost a column relative to its schema
+    struct MismatchedProvider {
+        wrong_type: bool,
+    }
+
+    impl DataProvider for MismatchedProvider {
+        fn row_count(&self) -> usize {
+            1
+        }
+    }
+
+    impl TableDataProvider for MismatchedProvider {
+        fn table_schema(&self) -> TableSchema {
+            SequenceProvider { row_count: 1 }
+                .table_schema()
+                .add_field("extra", ColumnDataType::Float64)
+        }
+
+        fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_> {
+            let row = if self.wrong_type {
+                Row::from_values(vec![
+                    Value::TimestampMillisecond(0),
+                    Value::Int64(0),
+                    Value::String("0.5".to_string()),
+                ])
+            } else {
+                Row::from_values(vec![Value::TimestampMillisecond(0), Value::Int64(0)])
+            };
+            Box::new(std::iter::once(row))
+        }
+    }
+
+    fn sequence_of(provider: &mut impl TableDataProvider) -> Vec<i64> {
+        provider.rows().map(|row| row.get_i64(1).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_limit_provider_yields_sub_sequence() {
+        let mut provider = LimitProvider::new(SequenceProvider { row_count: 10 }, 3, 4);
+        assert_eq!(provider.row_count(), 4);
+        assert_eq!(sequence_of(&mut provider), vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_limit_provider_workers_cover_disjoint_ranges() {
+        let workers = 3;
+        let slice = 4;
+        let mut all = Vec::new();
+        for worker in 0..workers {
+            let mut provider =
+                LimitProvider::new(SequenceProvider { row_count: 10 }, worker * slice, slice);
+            let expected_count = provider.row_count();
+            let rows = sequence_of(&mut provider);
+            assert_eq!(rows.len(), expected_count);
+            all.extend(rows);
+        }
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_limit_provider_offset_past_end() {
+        let mut provider = LimitProvider::new(SequenceProvider { row_count: 5 }, 10, 4);
+        assert_eq!(provider.row_count(), 0);
+        assert!(sequence_of(&mut provider).is_empty());
+    }
+
+    #[test]
+    fn test_self_check_accepts_consistent_provider() {
+        let mut provider = SequenceProvider { row_count: 3 };
+        provider.self_check().unwrap();
+        let mut provider = LimitProvider::new(provider, 1, 1);
+        provider.self_check().unwrap();
+    }
+
+    #[test]
+    fn test_self_check_rejects_mismatched_provider() {
+        let mut provider = MismatchedProvider { wrong_type: false };
+        assert!(matches!(
+            provider.self_check(),
+            Err(greptimedb_ingester::Error::InvalidColumnCount {
+                expected: 3,
+                actual: 2,
+                ..
+            })
+        ));
+
+        let mut provider = MismatchedProvider { wrong_type: true };
+        assert!(matches!(
+            provider.self_check(),
+            Err(greptimedb_ingester::Error::InvalidColumnValue { .. })
+        ));
+    }
+
+    /// Provider counting the calls of its batch hooks
+    struct HookCountingProvider {
+        inner: SequenceProvider,
+        started: Vec<usize>,
+        ended: Vec<usize>,
+    }
+
+    impl DataProvider for HookCountingProvider {
+        fn row_count(&self) -> usize {
+            self.inner.row_count()
+        }
+
+        fn on_batch_start(&mut self, batch_index: usize) {
+            assert_eq!(self.started.len(), self.ended.len(), "batch already open");
+            self.started.push(batch_index);
+        }
+
+        fn on_batch_end(&mut self, batch_index: usize) {
+            self.ended.push(batch_index);
+        }
+    }
+
+    impl TableDataProvider for HookCountingProvider {
+        fn table_schema(&self) -> TableSchema {
+            self.inner.table_schema()
+        }
+
+        fn rows(&mut self) -> Box<dyn Iterator<Item = Row> + '_> {
+            self.inner.rows()
+        }
+    }
+
+    #[test]
+    fn test_batch_hooks_called_around_each_batch() {
+        let mut provider = HookCountingProvider {
+            inner: SequenceProvider { row_count: 10 },
+            started: Vec::new(),
+            ended: Vec::new(),
+        };
+        let mut batches = Batches::new(4, provider.row_count());
+        let mut sequence = Vec::new();
+        while let Some(rows) = batches.next_rows(&mut provider) {
+            sequence.extend(rows.iter().map(|row| row.get_i64(1).unwrap()));
+        }
+
+        assert_eq!(batches.batch_count(), 3);
+        assert_eq!(provider.started, vec![0, 1, 2]);
+        assert_eq!(provider.ended, vec![0, 1, 2]);
+        assert_eq!(sequence, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_limit_provider_rows_from() {
+        let mut provider = LimitProvider::new(SequenceProvider { row_count: 10 }, 3, 4);
+        let rest: Vec<i64> = provider
+            .rows_from(2)
+            .map(|row| row.get_i64(1).unwrap())
+            .collect();
+        assert_eq!(rest, vec![5, 6]);
+    }
 }
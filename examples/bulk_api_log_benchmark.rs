@@ -17,8 +17,8 @@
 //! This benchmark demonstrates the LogTableDataProvider for generating synthetic log data
 //! and measuring GreptimeDB bulk API ingestion performance.
 //!
-//! Note: The bulk API does not automatically create tables. You must manually create the table
-//! before running this benchmark using the following schema:
+//! The benchmark creates the table if it does not exist, through
+//! `BulkInserter::create_bulk_stream_writer_auto`, with the following schema:
 //!
 //! ```sql
 //! CREATE TABLE IF NOT EXISTS `benchmark_logs` (
@@ -63,6 +63,7 @@
 //!   BATCH_SIZE        - Batch size for ingestion (default: 64 * 1024)
 //!   PARALLELISM       - Parallel requests (default: 8)
 //!   COMPRESSION       - Enable compression (default: lz4)
+//!   ALLOW_LARGE_BATCHES - Run even if the batches may not fit into memory (default: false)
 
 mod bench;
 
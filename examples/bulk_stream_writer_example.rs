@@ -273,8 +273,8 @@ async fn run_parallel_writes() -> Result<Duration> {
             _ => create_test_rows(batch_num, rows_per_batch, bulk_writer.column_schemas())?,
         };
         match bulk_writer.write_rows_async(rows).await {
-            Ok(request_id) => {
-                request_ids.push(request_id);
+            Ok(ack) => {
+                request_ids.push(ack.request_id());
                 if (batch_num + 1) % 25 == 0 {
                     println!("  Submitted: {}/{} batches", batch_num + 1, batch_count);
                 }
@@ -384,7 +384,7 @@ async fn main() -> Result<()> {
     println!();
     println!("=== BulkStreamWriter API Summary ===");
     println!("write_rows():                Submit batch and wait for completion (traditional)");
-    println!("write_rows_async():          Submit batch without waiting, returns request_id");
+    println!("write_rows_async():          Submit batch without waiting, returns an Ack future");
     println!("alloc_rows_buffer():         Allocate optimized buffer with shared schema");
     println!("new_row():                   Create schema-safe row builder with field names");
     println!("wait_for_response(id):       Wait for specific request by ID");
@@ -0,0 +1,105 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#![allow(clippy::print_stderr)]
+#![allow(clippy::print_stdout)]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[path = "util/mod.rs"]
+mod util;
+use util::DbConfig;
+
+use greptimedb_ingester::api::v1::*;
+use greptimedb_ingester::client::Client;
+use greptimedb_ingester::helpers::schema::*;
+use greptimedb_ingester::helpers::values::*;
+use greptimedb_ingester::{database::Database, ClientTlsOption, Result};
+
+/// Build the TLS settings from environment variables
+///
+/// - `GREPTIMEDB_TLS_CA`: PEM file with the CA certificate of the server (default: ca.pem)
+/// - `GREPTIMEDB_TLS_CERT` and `GREPTIMEDB_TLS_KEY`: client certificate and key, for mTLS
+/// - `GREPTIMEDB_TLS_DOMAIN`: name to verify the server certificate against
+/// - `GREPTIMEDB_TLS_INSECURE=true`: skip certificate verification (development only)
+fn tls_option_from_env() -> ClientTlsOption {
+    let ca_path = std::env::var("GREPTIMEDB_TLS_CA").unwrap_or_else(|_| "ca.pem".to_string());
+    let mut tls = ClientTlsOption::new(ca_path);
+
+    if let (Ok(cert), Ok(key)) = (
+        std::env::var("GREPTIMEDB_TLS_CERT"),
+        std::env::var("GREPTIMEDB_TLS_KEY"),
+    ) {
+        tls = tls.with_client_identity(cert, key);
+    }
+    if let Ok(domain_name) = std::env::var("GREPTIMEDB_TLS_DOMAIN") {
+        tls = tls.with_domain_name(domain_name);
+    }
+    let insecure = std::env::var("GREPTIMEDB_TLS_INSECURE").is_ok_and(|v| v == "true");
+    tls.with_danger_accept_invalid_certs(insecure)
+}
+
+/// Example of inserting rows into GreptimeDB behind a TLS endpoint
+pub async fn tls_ingest() -> Result<()> {
+    let config = DbConfig::from_env();
+    let tls = tls_option_from_env();
+
+    println!("=== TLS Insert Example ===");
+    config.display();
+    println!("Using CA certificate: {}", tls.server_ca_cert_path);
+    if tls.client_cert_path.is_some() {
+        println!("Using client certificate for mutual TLS");
+    }
+    if tls.danger_accept_invalid_certs {
+        println!("[WARNING] Server certificate verification is disabled");
+    }
+    println!();
+
+    let grpc_client = Client::with_tls_and_urls([&config.endpoint], tls)?;
+    grpc_client.health_check().await?;
+    println!("[SUCCESS] TLS handshake and health check succeeded");
+
+    let database = Database::new_with_dbname(&config.dbname, grpc_client);
+    let current_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    let insert_request = RowInsertRequests {
+        inserts: vec![RowInsertRequest {
+            table_name: "tls_sensor_readings".to_owned(),
+            rows: Some(Rows {
+                schema: vec![
+                    tag("device_id", ColumnDataType::String),
+                    timestamp("ts", ColumnDataType::TimestampMillisecond),
+                    field("temperature", ColumnDataType::Float64),
+                ],
+                rows: vec![Row {
+                    values: vec![
+                        string_value("device_001".to_string()),
+                        timestamp_millisecond_value(current_time),
+                        f64_value(23.5),
+                    ],
+                }],
+            }),
+        }],
+    };
+
+    let affected_rows = database.insert(insert_request).await?;
+    println!("[SUCCESS] Inserted {affected_rows} rows over TLS");
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tls_ingest().await
+}
@@ -17,9 +17,20 @@
 //! This module provides a user-friendly API for bulk inserting data into `GreptimeDB`,
 //! abstracting away the low-level Arrow Flight details.
 
+pub mod columnar;
+pub mod ingest;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+mod spill;
+
 use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 
 use greptime_proto::v1::SemanticType;
@@ -34,21 +45,32 @@ use arrow_array::builder::{
     TimestampNanosecondBuilder, TimestampSecondBuilder, UInt16Builder, UInt32Builder,
     UInt64Builder, UInt8Builder,
 };
-use arrow_array::{Array, RecordBatch};
+use arrow_array::{Array, Int64Array, RecordBatch};
 use arrow_flight::{FlightData, FlightDescriptor};
 use arrow_schema::{DataType, Field, Schema, TimeUnit};
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
+use futures::future::LocalBoxFuture;
 use futures::{FutureExt, SinkExt, Stream, StreamExt};
 
+use self::spill::Spill;
 use crate::api::v1::ColumnDataType;
 use crate::client::Client;
-use crate::database::Database;
-use crate::flight::do_put::{DoPutMetadata, DoPutResponse};
+use crate::database::{Database, WriteMode};
+use crate::error::ErrorKind;
+use crate::flight::do_put::{DoPutMetadata, DoPutResponse, ResponseError};
 use crate::flight::{FlightEncoder, FlightMessage};
-use crate::table::{Column, DataTypeExtension, Row, TableSchema, Value};
-use crate::{error, Result};
+use crate::retry::RetryBudget;
+use crate::table::{Column, DataTypeExtension, Row, SparseRow, TableSchema, TimestampRange, Value};
+use crate::{error, Error, Result};
 use snafu::{ensure, OptionExt, ResultExt};
 
+pub use self::columnar::ColumnarBatch;
+pub use self::ingest::{
+    CsvOptions, EmptyStringPolicy, FileCompression, FinishReport, JsonlOptions,
+};
+#[cfg(feature = "parquet")]
+pub use self::parquet::write_rows_parquet;
+
 /// Default channel buffer size for streaming FlightData
 /// This controls how many FlightData messages can be buffered in the channel
 /// before blocking the sender. A larger buffer allows for better throughput
@@ -57,6 +79,10 @@ use snafu::{ensure, OptionExt, ResultExt};
 /// Can be overridden by setting the GREPTIMEDB_CHANNEL_BUFFER_SIZE environment variable.
 const DEFAULT_CHANNEL_BUFFER_SIZE: usize = 1024;
 
+/// Upper bound of the row buffer of the batches built by
+/// [`BulkStreamWriter::write_stream`]
+const DEFAULT_ROW_BUFFER_SIZE: usize = 1024;
+
 /// Get configuration value from environment variable with fallback to default
 fn get_env_or_default<T>(env_var: &str, default: T) -> T
 where
@@ -70,33 +96,396 @@ where
 
 pub type RequestId = i64;
 
+/// Callback invoked with the request id and error of every batch that fails
+pub type OnErrorCallback = Arc<dyn Fn(RequestId, &Error) + Send + Sync>;
+
+/// Hook invoked with the `FlightData` of every batch just before it is sent
+pub type RequestInterceptor = Arc<dyn Fn(&mut FlightData) + Send + Sync>;
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<DoPutResponse>>>>;
+
+/// Opens a new bulk stream, returning its request sender and response stream
+type StreamOpener =
+    Box<dyn Fn() -> LocalBoxFuture<'static, Result<(mpsc::Sender<FlightData>, ResponseStream)>>>;
+
+type AckSenders = Arc<parking_lot::Mutex<HashMap<RequestId, oneshot::Sender<DoPutResponse>>>>;
+
+/// Acknowledgment of a batch submitted with [`BulkStreamWriter::write_rows_async`]
+///
+/// Resolves to the batch's response once the writer has read it from the server,
+/// or to an error if the batch can no longer be acknowledged because it timed out
+/// or the stream broke. A batch the server failed resolves to a response carrying
+/// the error, see [`DoPutResponse::error`].
+///
+/// The writer reads responses only while it is being driven: by later writes that
+/// wait for a free slot, by `wait_for_response`, `wait_for_all_pending` and by
+/// `finish`. An `Ack` is `Send`, so the usual pattern is to hand it to another task
+/// and keep writing; awaiting it on the task that owns an otherwise idle writer
+/// never completes.
+///
+/// A buffer spilled to disk is sent as several batches, see
+/// [`BulkWriteOptions::with_spill`]; its `Ack` covers all of them. It resolves to the
+/// response of the first failed batch, if any, and otherwise to the response of the
+/// last one, with the affected rows of every batch.
+#[must_use = "an Ack does nothing unless awaited"]
+pub struct Ack {
+    request_id: RequestId,
+    receiver: oneshot::Receiver<DoPutResponse>,
+    // Acks of the batches sent before the last one, oldest first
+    parts: Vec<Ack>,
+    // Rows affected by the parts acknowledged so far
+    parts_affected_rows: usize,
+}
+
+impl Ack {
+    /// The id of the acknowledged batch, the last one if the buffer was spilled
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    /// The ids of every batch covered by this ack, in the order they were sent
+    pub fn request_ids(&self) -> Vec<RequestId> {
+        self.parts
+            .iter()
+            .map(Ack::request_id)
+            .chain(std::iter::once(self.request_id))
+            .collect()
+    }
+}
+
+impl Future for Ack {
+    type Output = Result<DoPutResponse>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        while let Some(part) = self.parts.first_mut() {
+            let response = match part.poll_unpin(cx) {
+                Poll::Ready(Ok(response)) => response,
+                other => return other,
+            };
+            if !response.is_success() {
+                return Poll::Ready(Ok(response));
+            }
+            self.parts_affected_rows += response.affected_rows();
+            self.parts.remove(0);
+        }
+
+        let request_id = self.request_id;
+        let parts_affected_rows = self.parts_affected_rows;
+        self.receiver.poll_unpin(cx).map(|response| {
+            let response = response
+                .ok()
+                .context(error::AckDroppedSnafu { request_id })?;
+            Ok(response.with_earlier_affected_rows(parts_affected_rows))
+        })
+    }
+}
+
 /// High-level bulk inserter for `GreptimeDB`
 #[derive(Clone)]
 pub struct BulkInserter {
     database: Database,
+    default_options: BulkWriteOptions,
 }
 
 impl BulkInserter {
     /// Create a new bulk inserter
     #[must_use]
     pub fn new(client: Client, database_name: &str) -> Self {
+        Self::from_database(Database::new_with_dbname(database_name, client))
+    }
+
+    /// Create a bulk inserter writing through `database`, keeping its credentials
+    /// (see [`Database::with_auth`]) for every stream it opens
+    #[must_use]
+    pub fn from_database(database: Database) -> Self {
         Self {
-            database: Database::new_with_dbname(database_name, client),
+            database,
+            default_options: BulkWriteOptions::default(),
         }
     }
 
+    /// Start a [`BulkInserterBuilder`] to configure the options shared by every
+    /// writer of the inserter
+    #[must_use]
+    pub fn builder(client: Client, database_name: &str) -> BulkInserterBuilder {
+        BulkInserterBuilder::new(client, database_name)
+    }
+
+    /// The options of writers created without options of their own
+    pub fn default_options(&self) -> &BulkWriteOptions {
+        &self.default_options
+    }
+
+    /// The retry budget shared with the client this inserter was created from
+    pub fn retry_budget(&self) -> Option<&Arc<RetryBudget>> {
+        self.database.client().retry_budget()
+    }
+
     /// Create a bulk stream writer from a table template
     ///
     /// This is a convenience method that extracts the schema from a table
-    /// and creates a `BulkStreamWriter` bound to that schema.
+    /// and creates a `BulkStreamWriter` bound to that schema. The schema must
+    /// have exactly one timestamp column, which is checked before connecting.
+    ///
+    /// Without `options`, the writer uses the [`BulkInserter::default_options`].
+    /// Passed `options` replace them as a whole rather than being merged with them;
+    /// to change only some, start from a clone of the defaults, e.g.
+    /// `inserter.default_options().clone().with_parallelism(1)`.
     pub async fn create_bulk_stream_writer(
         &self,
         table_schema: &TableSchema,
         options: Option<BulkWriteOptions>,
     ) -> Result<BulkStreamWriter> {
-        let options = options.unwrap_or_default();
+        table_schema.validate_time_index()?;
+        let options = self.write_options(options);
         BulkStreamWriter::new(&self.database, table_schema, options).await
     }
+
+    fn write_options(&self, options: Option<BulkWriteOptions>) -> BulkWriteOptions {
+        options.unwrap_or_else(|| self.default_options.clone())
+    }
+
+    /// Like [`BulkInserter::create_bulk_stream_writer`], creating the table first if
+    /// it does not exist, see [`BulkInserter::create_table`]
+    pub async fn create_bulk_stream_writer_auto(
+        &self,
+        table_schema: &TableSchema,
+        table_options: &[(&str, &str)],
+        options: Option<BulkWriteOptions>,
+    ) -> Result<BulkStreamWriter> {
+        table_schema.validate_time_index()?;
+        self.create_table(table_schema, table_options).await?;
+        self.create_bulk_stream_writer(table_schema, options).await
+    }
+
+    /// Create the table of `table_schema` unless it already exists
+    ///
+    /// The statement is generated by [`TableSchema::to_create_table_sql`], with
+    /// `table_options` such as `("append_mode", "true")` in its `WITH` clause. An
+    /// existing table is left as it is, even if its schema or options differ; any
+    /// other failure is returned.
+    pub async fn create_table(
+        &self,
+        table_schema: &TableSchema,
+        table_options: &[(&str, &str)],
+    ) -> Result<()> {
+        let sql = table_schema.to_create_table_sql(table_options)?;
+        self.database.sql(sql).await?;
+        Ok(())
+    }
+
+    /// Check that `table_name` holds `expected` rows, e.g. at the end of an import
+    ///
+    /// Freshly written rows may take a moment to become visible to queries, so a
+    /// mismatching count is queried again a few times before failing with a
+    /// `RowCountMismatch` error.
+    pub async fn verify_row_count(&self, table_name: &str, expected: u64) -> Result<()> {
+        let sql = format!(
+            "SELECT COUNT(*) FROM \"{}\"",
+            table_name.replace('"', "\"\"")
+        );
+        verify_row_count_with(
+            table_name,
+            expected,
+            VERIFY_ROW_COUNT_ATTEMPTS,
+            VERIFY_ROW_COUNT_BACKOFF,
+            || self.count_rows(&sql),
+        )
+        .await
+    }
+
+    async fn count_rows(&self, sql: &str) -> Result<u64> {
+        let batches = self.database.sql(sql).await?;
+        count_from_batches(&batches)
+    }
+}
+
+/// Builder of a [`BulkInserter`] whose writers share one set of [`BulkWriteOptions`]
+///
+/// The options apply to writers created without options of their own; options given
+/// to [`BulkInserter::create_bulk_stream_writer`] replace all of them.
+///
+/// ```no_run
+/// # use greptimedb_ingester::{BulkInserter, CompressionType, Client};
+/// # fn example(client: Client) {
+/// let inserter = BulkInserter::builder(client, "public")
+///     .high_throughput()
+///     .with_compression(CompressionType::Zstd)
+///     .with_max_retries(3)
+///     .build();
+/// # }
+/// ```
+///
+/// Writers created with [`BulkInserter::create_bulk_stream_writer`] and no options
+/// of their own use these; passing options replaces them for that writer.
+#[derive(Clone)]
+pub struct BulkInserterBuilder {
+    database: Database,
+    options: BulkWriteOptions,
+}
+
+impl BulkInserterBuilder {
+    /// Start with the default [`BulkWriteOptions`]
+    #[must_use]
+    pub fn new(client: Client, database_name: &str) -> Self {
+        Self::from_database(Database::new_with_dbname(database_name, client))
+    }
+
+    /// Like [`BulkInserterBuilder::new`], writing through `database`, see
+    /// [`BulkInserter::from_database`]
+    #[must_use]
+    pub fn from_database(database: Database) -> Self {
+        Self {
+            database,
+            options: BulkWriteOptions::default(),
+        }
+    }
+
+    /// Preset for large imports: LZ4 compression and 16 batches in flight
+    ///
+    /// Later calls to the other methods override single options of the preset.
+    #[must_use]
+    pub fn high_throughput(mut self) -> Self {
+        self.options = self
+            .options
+            .with_compression(CompressionType::Lz4)
+            .with_parallelism(16);
+        self
+    }
+
+    /// Preset for small, frequent batches: no compression, 2 batches in flight and
+    /// a 10 second timeout
+    ///
+    /// Later calls to the other methods override single options of the preset.
+    #[must_use]
+    pub fn low_latency(mut self) -> Self {
+        self.options = self
+            .options
+            .with_compression(CompressionType::None)
+            .with_parallelism(2)
+            .with_timeout(Duration::from_secs(10));
+        self
+    }
+
+    /// Replace all options, e.g. to start from options built elsewhere
+    #[must_use]
+    pub fn with_options(mut self, options: BulkWriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_compression`]
+    #[must_use]
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.options = self.options.with_compression(compression);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_timeout`]
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.with_timeout(timeout);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_parallelism`]
+    #[must_use]
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.options = self.options.with_parallelism(parallelism);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_max_in_flight`]
+    #[must_use]
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.options = self.options.with_max_in_flight(max_in_flight);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_validation`]
+    #[must_use]
+    pub fn with_validation(mut self, enabled: bool) -> Self {
+        self.options = self.options.with_validation(enabled);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_max_retries`]
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.options = self.options.with_max_retries(max_retries);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_retry_backoff`]
+    #[must_use]
+    pub fn with_retry_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.options = self.options.with_retry_backoff(base, max);
+        self
+    }
+
+    /// See [`BulkWriteOptions::with_on_error`]
+    #[must_use]
+    pub fn with_on_error(mut self, on_error: OnErrorCallback) -> Self {
+        self.options = self.options.with_on_error(on_error);
+        self
+    }
+
+    /// Build the inserter
+    #[must_use]
+    pub fn build(self) -> BulkInserter {
+        BulkInserter {
+            database: self.database,
+            default_options: self.options,
+        }
+    }
+}
+
+const VERIFY_ROW_COUNT_ATTEMPTS: usize = 5;
+const VERIFY_ROW_COUNT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Compare the count returned by `count` against `expected`, retrying up to `attempts` times
+async fn verify_row_count_with<F, Fut>(
+    table_name: &str,
+    expected: u64,
+    attempts: usize,
+    backoff: Duration,
+    mut count: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<u64>>,
+{
+    let mut actual = count().await?;
+    for _ in 1..attempts {
+        if actual == expected {
+            return Ok(());
+        }
+        tokio::time::sleep(backoff).await;
+        actual = count().await?;
+    }
+
+    ensure!(
+        actual == expected,
+        error::RowCountMismatchSnafu {
+            table: table_name,
+            expected,
+            actual,
+        }
+    );
+    Ok(())
+}
+
+/// Extract the single value of a `SELECT COUNT(*)` result
+fn count_from_batches(batches: &[RecordBatch]) -> Result<u64> {
+    let count = batches
+        .iter()
+        .find(|batch| batch.num_rows() > 0 && batch.num_columns() > 0)
+        .and_then(|batch| batch.column(0).as_any().downcast_ref::<Int64Array>())
+        .map(|counts| counts.value(0))
+        .context(error::IllegalDatabaseResponseSnafu {
+            err_msg: "COUNT(*) returned no Int64 value",
+        })?;
+    Ok(u64::try_from(count).unwrap_or_default())
 }
 
 /// Compression algorithm options for bulk write operations
@@ -109,11 +498,60 @@ pub enum CompressionType {
 }
 
 /// Configuration options for bulk write operations
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct BulkWriteOptions {
     pub compression: CompressionType,
     pub timeout: Duration,
     pub parallelism: usize,
+    /// Bound on unacknowledged batches, see [`BulkWriteOptions::with_max_in_flight`]
+    pub max_in_flight: Option<usize>,
+    /// Optional plausibility check for timestamp values, see [`TimestampRange`]
+    pub timestamp_range: Option<TimestampRange>,
+    /// Optional callback fired as soon as a batch is known to have failed
+    pub on_error: Option<OnErrorCallback>,
+    /// Whether rows and batches are validated on the client, see [`BulkWriteOptions::with_validation`]
+    pub validation: bool,
+    /// When to recycle the underlying stream, see [`BulkWriteOptions::with_stream_reset_interval`]
+    pub stream_reset_interval: Option<StreamResetInterval>,
+    /// How often the batches of a failed stream are resent, see [`BulkWriteOptions::with_max_retries`]
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled for every further attempt
+    pub retry_backoff_base: Duration,
+    /// Upper bound of the delay between retries
+    pub retry_backoff_max: Duration,
+    /// Bounds of the adaptive batch size, see [`BulkWriteOptions::with_adaptive_batching`]
+    pub adaptive_batching: Option<(usize, usize)>,
+    /// Latency below which the adaptive batch size grows
+    pub batch_latency_target: Duration,
+    /// Declared write mode, see [`BulkWriteOptions::with_write_mode`]
+    pub write_mode: Option<WriteMode>,
+    /// Size threshold and directory of spilled rows, see [`BulkWriteOptions::with_spill`]
+    pub spill: Option<(usize, PathBuf)>,
+    /// Latency above which a batch is logged, see [`BulkWriteOptions::with_slow_batch_threshold`]
+    pub slow_batch_threshold: Option<Duration>,
+}
+
+impl std::fmt::Debug for BulkWriteOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BulkWriteOptions")
+            .field("compression", &self.compression)
+            .field("timeout", &self.timeout)
+            .field("parallelism", &self.parallelism)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("timestamp_range", &self.timestamp_range)
+            .field("on_error", &self.on_error.as_ref().map(|_| "Fn(..)"))
+            .field("validation", &self.validation)
+            .field("stream_reset_interval", &self.stream_reset_interval)
+            .field("max_retries", &self.max_retries)
+            .field("retry_backoff_base", &self.retry_backoff_base)
+            .field("retry_backoff_max", &self.retry_backoff_max)
+            .field("adaptive_batching", &self.adaptive_batching)
+            .field("batch_latency_target", &self.batch_latency_target)
+            .field("write_mode", &self.write_mode)
+            .field("spill", &self.spill)
+            .field("slow_batch_threshold", &self.slow_batch_threshold)
+            .finish()
+    }
 }
 
 impl Default for BulkWriteOptions {
@@ -122,6 +560,19 @@ impl Default for BulkWriteOptions {
             compression: CompressionType::default(),
             timeout: Duration::from_secs(60),
             parallelism: 4,
+            max_in_flight: None,
+            timestamp_range: None,
+            on_error: None,
+            validation: true,
+            stream_reset_interval: None,
+            max_retries: 0,
+            retry_backoff_base: Duration::from_millis(100),
+            retry_backoff_max: Duration::from_secs(5),
+            adaptive_batching: None,
+            batch_latency_target: Duration::from_secs(1),
+            write_mode: None,
+            spill: None,
+            slow_batch_threshold: None,
         }
     }
 }
@@ -142,18 +593,339 @@ impl BulkWriteOptions {
     }
 
     /// Set parallelism for concurrent requests
+    ///
+    /// Unless lowered by [`Self::with_max_in_flight`], this is also the bound on
+    /// batches in flight.
     #[must_use]
     pub fn with_parallelism(mut self, parallelism: usize) -> Self {
         self.parallelism = parallelism;
         self
     }
+
+    /// Bound the batches in flight to `max_in_flight`, capped by the parallelism,
+    /// which is the bound by default
+    ///
+    /// Once that many batches are unacknowledged, submitting another one, including
+    /// through [`BulkStreamWriter::write_rows_async`], waits for a response first.
+    /// Memory held by in-flight batches is therefore bounded by `max_in_flight` times
+    /// the batch size, however fast rows are produced. Responses read while waiting
+    /// are cached until [`BulkStreamWriter::flush_completed_responses`] reaps them.
+    /// A bound of zero is treated as one.
+    #[must_use]
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+
+    /// The effective bound on batches in flight, see [`Self::with_max_in_flight`]
+    fn in_flight_limit(&self) -> usize {
+        self.max_in_flight
+            .map_or(self.parallelism, |max| max.min(self.parallelism))
+    }
+
+    /// Reject timestamps outside of `range` when rows are added to buffers
+    /// allocated by the writer.
+    ///
+    /// This is a heuristic to catch values written in the wrong unit, e.g. seconds
+    /// into a millisecond column. It is disabled by default.
+    #[must_use]
+    pub fn with_timestamp_range(mut self, range: TimestampRange) -> Self {
+        self.timestamp_range = Some(range);
+        self
+    }
+
+    /// Set a callback fired the moment a batch fails, instead of only surfacing
+    /// the error from the call that observed it.
+    ///
+    /// The callback receives the id of the batch, see [`Ack::request_id`], and the error.
+    /// A timeout reports every timed out request. A broken response stream
+    /// loses every outstanding request, so each pending id is reported.
+    #[must_use]
+    pub fn with_on_error(mut self, on_error: OnErrorCallback) -> Self {
+        self.on_error = Some(on_error);
+        self
+    }
+
+    /// Enable or disable client-side validation, enabled by default
+    ///
+    /// Disabling it skips the schema comparison of every submitted batch against the
+    /// writer's schema and the per-row value checks such as [`Self::with_timestamp_range`].
+    /// Only the row arity check remains, as building a batch relies on it. Use this on
+    /// hot paths whose rows are known to match the table: a buffer built for another
+    /// schema is then sent as is and rejected by the server, or worse, written with
+    /// the wrong column types.
+    #[must_use]
+    pub fn with_validation(mut self, enabled: bool) -> Self {
+        self.validation = enabled;
+        self
+    }
+
+    /// Close and reopen the underlying stream once `interval` is reached, disabled by default
+    ///
+    /// A bulk stream is a single long-lived gRPC call, pinned to the frontend that
+    /// accepted it. On a backfill running for hours that call never ends, so a load
+    /// balancer cannot spread the load onto frontends added or restarted in the
+    /// meantime, and proxies enforcing a maximum stream age cut it off mid-write.
+    /// Recycling the stream periodically bounds its lifetime instead.
+    ///
+    /// The reset is invisible to the caller: before the next batch is sent, the writer
+    /// waits for every outstanding batch of the old stream, keeps their responses for
+    /// `wait_for_response` and `finish`, and resends the schema on the new stream.
+    /// Request ids keep counting across streams. The wait briefly drops the number of
+    /// batches in flight to zero, so intervals should span many batches.
+    #[must_use]
+    pub fn with_stream_reset_interval(mut self, interval: StreamResetInterval) -> Self {
+        self.stream_reset_interval = Some(interval);
+        self
+    }
+
+    /// Retry batches failed by a transient error up to `max_retries` times, disabled by default
+    ///
+    /// A server error ends the whole stream, failing every batch in flight. When the
+    /// error is transient, i.e. of [`ErrorKind::Transport`] such as `Unavailable`,
+    /// `DeadlineExceeded` or a timeout, the writer opens a new stream after a
+    /// backoff, see [`Self::with_retry_backoff`], and resends every unacknowledged
+    /// batch with its original request id, so acks and `wait_for_response` keep
+    /// working and report the rows of the successful attempt.
+    /// Any other error, such as `InvalidArgument` or a schema mismatch, surfaces
+    /// immediately. The attempts count from the last response received, and a
+    /// [`RetryBudget`](crate::retry::RetryBudget) of the client, if any, must allow
+    /// every retry as well. Retries are counted in [`BulkMetricsSnapshot::retries`].
+    ///
+    /// Unacknowledged batches are kept in memory while retries are enabled, up to
+    /// the bound on batches in flight, see [`Self::with_max_in_flight`].
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Wait `base` before the first retry, doubling the delay for every further
+    /// attempt up to `max`
+    ///
+    /// Each delay is jittered to a random value between half and all of it, so that
+    /// writers failed by the same outage do not retry in lockstep.
+    #[must_use]
+    pub fn with_retry_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.retry_backoff_base = base;
+        self.retry_backoff_max = max;
+        self
+    }
+
+    /// Adapt the recommended rows per batch between `min_rows` and `max_rows` to the
+    /// observed latency, disabled by default
+    ///
+    /// The writer does not split or merge batches itself; callers size their buffers
+    /// with [`BulkStreamWriter::recommended_batch_size`]. The size starts at `min_rows`
+    /// and follows a control loop driven by every acknowledged batch, measured from
+    /// sending it to its response:
+    ///
+    /// - below the target latency, see [`Self::with_batch_latency_target`], the size
+    ///   grows by a quarter, up to `max_rows`;
+    /// - above it, the size is halved, down to `min_rows`;
+    /// - an error from the server, which is how it pushes back, also halves it.
+    ///
+    /// Growing slowly and backing off fast keeps the size near the largest batch the
+    /// server and network handle within the target, as it shifts with row width and RTT.
+    #[must_use]
+    pub fn with_adaptive_batching(mut self, min_rows: usize, max_rows: usize) -> Self {
+        let min_rows = min_rows.max(1);
+        self.adaptive_batching = Some((min_rows, max_rows.max(min_rows)));
+        self
+    }
+
+    /// Set the latency the adaptive batch size is tuned to, one second by default
+    #[must_use]
+    pub fn with_batch_latency_target(mut self, target: Duration) -> Self {
+        self.batch_latency_target = target;
+        self
+    }
+
+    /// Declare whether the stream appends or upserts rows, undeclared by default
+    ///
+    /// The mode is sent as a hint when the stream is opened; see [`WriteMode`] for
+    /// when the server applies it. With [`WriteMode::Upsert`] and validation enabled,
+    /// a batch with a null in any tag column is rejected before it is sent.
+    #[must_use]
+    pub fn with_write_mode(mut self, mode: WriteMode) -> Self {
+        self.write_mode = Some(mode);
+        self
+    }
+
+    /// Spill the rows of a buffer from [`BulkStreamWriter::alloc_rows_buffer`] to a
+    /// temporary file in `temp_dir` whenever about `threshold` bytes are buffered,
+    /// disabled by default
+    ///
+    /// This caps the memory of a giant buffer at roughly `threshold` plus one batch
+    /// per parallel request. Writing the buffer sends every spilled part as a batch
+    /// of its own, read back from disk as the in-flight limit allows;
+    /// [`BulkStreamWriter::write_rows`] waits for all of them and the [`Ack`] of
+    /// [`BulkStreamWriter::write_rows_async`] covers all of them, see
+    /// [`Ack::request_ids`]. The price is latency: every spilled row is encoded to
+    /// disk and read back before it is sent, and nothing is sent until the buffer is
+    /// written. With retries enabled, unacknowledged parts are kept in memory to be
+    /// resent, which weakens the cap. Spill files are deleted once they are read back
+    /// or their buffer is dropped.
+    #[must_use]
+    pub fn with_spill(mut self, threshold: usize, temp_dir: impl Into<PathBuf>) -> Self {
+        self.spill = Some((threshold, temp_dir.into()));
+        self
+    }
+
+    /// Log every batch whose round trip exceeds `threshold` with a `tracing` warning,
+    /// disabled by default
+    ///
+    /// The warning carries the table, request id, row count and latency of the
+    /// batch, so tail latencies stand out without logging every batch. The latency
+    /// is measured from sending the batch to the writer reading its response, like
+    /// that of [`Self::with_adaptive_batching`]; a caller that waits long between
+    /// calls to the writer inflates it.
+    #[must_use]
+    pub fn with_slow_batch_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_batch_threshold = Some(threshold);
+        self
+    }
+}
+
+/// The control loop behind [`BulkWriteOptions::with_adaptive_batching`]
+#[derive(Debug, Clone, Copy)]
+struct BatchSizer {
+    min_rows: usize,
+    max_rows: usize,
+    target_latency: Duration,
+    current: usize,
+}
+
+impl BatchSizer {
+    fn new(min_rows: usize, max_rows: usize, target_latency: Duration) -> Self {
+        Self {
+            min_rows,
+            max_rows,
+            target_latency,
+            current: min_rows,
+        }
+    }
+
+    /// Adapt to the latency of an acknowledged batch
+    fn observe(&mut self, latency: Duration) {
+        if latency <= self.target_latency {
+            let step = (self.current / 4).max(1);
+            self.current = self.current.saturating_add(step).min(self.max_rows);
+        } else {
+            self.back_off();
+        }
+    }
+
+    fn back_off(&mut self) {
+        self.current = (self.current / 2).max(self.min_rows);
+    }
+}
+
+/// When a [`BulkStreamWriter`] recycles its stream, see
+/// [`BulkWriteOptions::with_stream_reset_interval`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamResetInterval {
+    /// After this many batches were sent on a stream
+    Batches(u64),
+    /// After this many bytes of encoded batches were sent on a stream
+    Bytes(u64),
+}
+
+/// Outcome of [`BulkStreamWriter::cancel`]
+#[derive(Debug, Clone, Default)]
+pub struct CancelSummary {
+    /// Responses of the batches acknowledged before the stream was closed, ordered
+    /// by request id
+    pub acknowledged: Vec<DoPutResponse>,
+    /// Request ids of the batches sent but not acknowledged by the deadline, which
+    /// the server may or may not have written
+    pub unacknowledged: Vec<RequestId>,
+}
+
+/// Timing counters of a [`BulkStreamWriter`], telling whether row generation or the
+/// network is the bottleneck
+///
+/// `network_time` is the time spent inside the writer submitting batches and waiting
+/// for responses, including waits for a free slot when the bound on batches in
+/// flight is reached. `buffering_time` is the time between those calls, which the caller spends
+/// generating rows and adding them to a buffer. Timing per call rather than per row
+/// keeps `add_row` free of clock reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkMetricsSnapshot {
+    /// Number of batches submitted
+    pub batches: u64,
+    /// Number of rows submitted
+    pub rows: u64,
+    /// Time spent generating and buffering rows between network calls
+    pub buffering_time: Duration,
+    /// Time spent in network calls of the writer
+    pub network_time: Duration,
+    /// Number of times the batches of a failed stream were resent on a new one, see
+    /// [`BulkWriteOptions::with_max_retries`]
+    pub retries: u64,
+}
+
+/// Ingestion counters of a [`BulkStreamWriter`], see [`BulkStreamWriter::stats`]
+///
+/// Bytes count the Arrow data of the batches sent, including resent ones:
+/// `uncompressed_bytes` is the size of their buffers before encoding and
+/// `compressed_bytes` the size of the encoded IPC messages put on the stream, after
+/// the compression set by [`BulkWriteOptions::with_compression`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkWriteStats {
+    /// Number of rows submitted
+    pub rows: u64,
+    /// Number of batches submitted
+    pub batches: u64,
+    /// Size of the batches sent before compression
+    pub uncompressed_bytes: u64,
+    /// Size of the batches sent after compression
+    pub compressed_bytes: u64,
+    /// Number of batches sent but not acknowledged yet
+    pub in_flight: usize,
+    /// Number of retries, see [`BulkWriteOptions::with_max_retries`]
+    pub retries: u64,
+    /// Sum of the affected rows reported by the server
+    pub affected_rows: u64,
+}
+
+impl BulkWriteStats {
+    /// The counts accumulated since `earlier`, with the current number of batches
+    /// in flight
+    fn since(&self, earlier: &BulkWriteStats) -> BulkWriteStats {
+        BulkWriteStats {
+            rows: self.rows - earlier.rows,
+            batches: self.batches - earlier.batches,
+            uncompressed_bytes: self.uncompressed_bytes - earlier.uncompressed_bytes,
+            compressed_bytes: self.compressed_bytes - earlier.compressed_bytes,
+            in_flight: self.in_flight,
+            retries: self.retries - earlier.retries,
+            affected_rows: self.affected_rows - earlier.affected_rows,
+        }
+    }
+
+    /// The ratio of uncompressed to compressed bytes, if any batch was sent
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.compressed_bytes > 0)
+            .then(|| self.uncompressed_bytes as f64 / self.compressed_bytes as f64)
+    }
+}
+
+/// The atomic counters behind [`BulkWriteStats`]
+#[derive(Debug, Default)]
+struct WriteCounters {
+    rows: AtomicU64,
+    batches: AtomicU64,
+    uncompressed_bytes: AtomicU64,
+    compressed_bytes: AtomicU64,
+    affected_rows: AtomicU64,
 }
 
 /// High-performance bulk stream writer that maintains a persistent connection
 /// Each writer is bound to a specific table with fixed schema
 pub struct BulkStreamWriter {
     sender: mpsc::Sender<FlightData>,
-    response_stream: Pin<Box<dyn Stream<Item = Result<DoPutResponse>>>>,
+    response_stream: ResponseStream,
     table_schema: TableSchema,
     // Cache the Arrow schema to avoid recreating it for each batch
     arrow_schema: Arc<Schema>,
@@ -161,14 +933,48 @@ pub struct BulkStreamWriter {
     field_map: HashMap<String, usize>,
     next_request_id: RequestId,
     encoder: FlightEncoder,
+    compression: CompressionType,
     schema_sent: bool,
     // Parallel processing fields
     parallelism: usize,
+    max_in_flight: usize,
     timeout: Duration,
-    // Track pending requests: request_id -> sent_time
-    pending_requests: HashMap<RequestId, Instant>,
+    timestamp_range: Option<TimestampRange>,
+    on_error: Option<OnErrorCallback>,
+    validation: bool,
+    // Track pending requests: request_id -> (sent_time, rows)
+    pending_requests: HashMap<RequestId, (Instant, usize)>,
     // Cache completed responses that were processed but not yet retrieved
     completed_responses: HashMap<RequestId, (DoPutResponse, Instant)>,
+    // Responses of requests failed by the server, recorded while reporting the error.
+    // A request still pending is reported again by every later error, so keep one
+    // response per request
+    failed_responses: parking_lot::Mutex<HashMap<RequestId, DoPutResponse>>,
+    // Senders resolving the `Ack` of each batch submitted with `write_rows_async`
+    ack_senders: AckSenders,
+    metrics: BulkMetricsSnapshot,
+    counters: WriteCounters,
+    last_network_call_end: Instant,
+    // Reopens the stream when `stream_reset_interval` is reached
+    stream_opener: Option<StreamOpener>,
+    stream_reset_interval: Option<StreamResetInterval>,
+    // Batches and bytes sent on the current stream
+    stream_batches: u64,
+    stream_bytes: u64,
+    max_retries: usize,
+    retry_backoff_base: Duration,
+    retry_backoff_max: Duration,
+    retry_budget: Option<Arc<RetryBudget>>,
+    // Retries since the last response received
+    retry_attempt: usize,
+    // Batches kept to be resent if the stream fails, only while retries are enabled
+    unacked_batches: HashMap<RequestId, RecordBatch>,
+    batch_sizer: Option<BatchSizer>,
+    slow_batch_threshold: Option<Duration>,
+    // Indices of the tag columns, checked for nulls when upserting
+    upsert_key_columns: Vec<usize>,
+    interceptor: Option<RequestInterceptor>,
+    spill: Option<(usize, PathBuf)>,
 }
 
 impl BulkStreamWriter {
@@ -178,20 +984,46 @@ impl BulkStreamWriter {
         table_schema: &TableSchema,
         options: BulkWriteOptions,
     ) -> Result<Self> {
+        table_schema.validate()?;
+        // Convert table schema to Arrow schema
+        let arrow_schema = table_arrow_schema(table_schema)?;
+
+        let retry_budget = database.client().retry_budget().cloned();
+        let database = database.clone();
+        let write_mode = options.write_mode;
+        let stream_opener: StreamOpener = Box::new(move || {
+            let database = database.clone();
+            async move { open_bulk_stream(&database, write_mode).await }.boxed_local()
+        });
+        let (sender, response_stream) = stream_opener().await?;
+
+        let mut writer = Self::from_parts(
+            table_schema,
+            arrow_schema,
+            options,
+            sender,
+            response_stream,
+            Some(stream_opener),
+        );
+        writer.retry_budget = retry_budget;
+        Ok(writer)
+    }
+
+    /// Assemble a writer around an already opened request sender and response stream
+    fn from_parts(
+        table_schema: &TableSchema,
+        arrow_schema: Arc<Schema>,
+        options: BulkWriteOptions,
+        sender: mpsc::Sender<FlightData>,
+        response_stream: ResponseStream,
+        stream_opener: Option<StreamOpener>,
+    ) -> Self {
         // Create the encoder with compression settings
         let encoder = FlightEncoder::with_compression(options.compression);
 
-        // Convert table schema to Arrow schema
-        let fields: Result<Vec<Field>> = table_schema
-            .columns()
-            .iter()
-            .map(|col| {
-                let nullable = col.semantic_type != SemanticType::Timestamp;
-                column_to_arrow_data_type(col)
-                    .map(|data_type| Field::new(&col.name, data_type, nullable))
-            })
-            .collect();
-        let arrow_schema = Arc::new(Schema::new(fields?));
+        let ack_senders: AckSenders = Default::default();
+        let response_stream =
+            resolve_responses(response_stream, table_schema.name(), ack_senders.clone());
 
         // Pre-compute field name to index mapping for O(1) lookups in RowBuilder
         let field_map: HashMap<String, usize> = table_schema
@@ -201,18 +1033,7 @@ impl BulkStreamWriter {
             .map(|(i, col)| (col.name.clone(), i))
             .collect();
 
-        // Create a channel for streaming FlightData
-        let channel_buffer_size = get_env_or_default(
-            "GREPTIMEDB_CHANNEL_BUFFER_SIZE",
-            DEFAULT_CHANNEL_BUFFER_SIZE,
-        );
-        let (sender, receiver) = mpsc::channel::<FlightData>(channel_buffer_size);
-
-        // Convert receiver to a stream and start the do_put operation
-        let flight_stream = receiver.boxed();
-        let response_stream = database.do_put(flight_stream).await?;
-
-        Ok(Self {
+        Self {
             sender,
             response_stream,
             table_schema: table_schema.clone(),
@@ -220,33 +1041,221 @@ impl BulkStreamWriter {
             field_map,
             next_request_id: 0,
             encoder,
+            compression: options.compression,
             schema_sent: false,
             parallelism: options.parallelism,
+            max_in_flight: options.in_flight_limit(),
             timeout: options.timeout,
+            timestamp_range: options.timestamp_range,
+            on_error: options.on_error,
+            validation: options.validation,
             pending_requests: HashMap::new(),
             completed_responses: HashMap::new(),
-        })
+            failed_responses: parking_lot::Mutex::new(HashMap::new()),
+            ack_senders,
+            metrics: BulkMetricsSnapshot::default(),
+            counters: WriteCounters::default(),
+            last_network_call_end: Instant::now(),
+            stream_opener,
+            stream_reset_interval: options.stream_reset_interval,
+            stream_batches: 0,
+            stream_bytes: 0,
+            max_retries: options.max_retries,
+            retry_backoff_base: options.retry_backoff_base,
+            retry_backoff_max: options.retry_backoff_max,
+            retry_budget: None,
+            retry_attempt: 0,
+            unacked_batches: HashMap::new(),
+            batch_sizer: options.adaptive_batching.map(|(min_rows, max_rows)| {
+                BatchSizer::new(min_rows, max_rows, options.batch_latency_target)
+            }),
+            upsert_key_columns: match options.write_mode {
+                Some(WriteMode::Upsert) => table_schema
+                    .columns()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, column)| column.semantic_type == SemanticType::Tag)
+                    .map(|(index, _)| index)
+                    .collect(),
+                _ => Vec::new(),
+            },
+            interceptor: None,
+            spill: options.spill,
+            slow_batch_threshold: options.slow_batch_threshold,
+        }
+    }
+
+    /// Call `interceptor` with every batch just before it is sent, including batches
+    /// resent after a retry, to add metadata or inspect the outgoing request.
+    ///
+    /// The interceptor sees the `app_metadata` carrying the request id, but any change
+    /// to it is undone, as the writer relies on it to match responses to batches.
+    /// The interceptor runs on the write path and must be cheap and non-blocking.
+    #[must_use]
+    pub fn with_interceptor(mut self, interceptor: RequestInterceptor) -> Self {
+        self.interceptor = Some(interceptor);
+        self
     }
 
     /// Write rows to the stream using the fixed table schema
+    ///
+    /// A buffer spilled to disk is sent as several batches; this waits for all of
+    /// them and returns the response of the last one with the affected rows of every
+    /// batch, or the first error.
     pub async fn write_rows(&mut self, rows: Rows) -> Result<DoPutResponse> {
-        // Use the async implementation and wait for the response
-        let request_id = self.write_rows_async(rows).await?;
-        self.wait_for_response(request_id).await
+        let request_ids = self.submit_rows(rows).await?;
+        let (last, earlier) = request_ids
+            .split_last()
+            .expect("rows are submitted as at least one batch");
+        let mut affected_rows = 0;
+        for request_id in earlier {
+            affected_rows += self.wait_for_response(*request_id).await?.affected_rows();
+        }
+        let response = self.wait_for_response(*last).await?;
+        Ok(response.with_earlier_affected_rows(affected_rows))
     }
 
     /// Submit rows for writing without waiting for response
-    /// Returns a `request_id` that can be used to wait for the specific response
-    pub async fn write_rows_async(&mut self, rows: Rows) -> Result<RequestId> {
+    ///
+    /// Returns once the batch has been handed to the stream, with an [`Ack`] that
+    /// resolves when the server acknowledges this specific batch. The batch's
+    /// response is also cached as before, so `ack.request_id()` can still be used
+    /// with `wait_for_response`.
+    ///
+    /// When the bound on batches in flight is reached, this waits for one of them to
+    /// be acknowledged before sending, so a caller outpacing the server is slowed
+    /// down to its pace, see [`BulkWriteOptions::with_max_in_flight`].
+    pub async fn write_rows_async(&mut self, rows: Rows) -> Result<Ack> {
+        let request_ids = self.submit_rows(rows).await?;
+        let mut acks: Vec<Ack> = request_ids.into_iter().map(|id| self.ack(id)).collect();
+        let mut ack = acks
+            .pop()
+            .expect("rows are submitted as at least one batch");
+        ack.parts = acks;
+        Ok(ack)
+    }
+
+    /// Write a batch given as whole columns, see [`ColumnarBatch`]
+    ///
+    /// The columns become the Arrow arrays of the batch as they are, skipping the
+    /// per-cell [`Value`]s of the row path.
+    pub async fn write_columns(&mut self, columns: ColumnarBatch) -> Result<DoPutResponse> {
+        let request_id = self.submit_columns(columns).await?;
+        self.wait_for_response(request_id).await
+    }
+
+    /// Submit a batch given as whole columns without waiting for its response, like
+    /// [`BulkStreamWriter::write_rows_async`]
+    pub async fn write_columns_async(&mut self, columns: ColumnarBatch) -> Result<Ack> {
+        let request_id = self.submit_columns(columns).await?;
+        Ok(self.ack(request_id))
+    }
+
+    /// Write every row of `stream` in batches of `batch_size` rows, then wait for
+    /// all of them to be acknowledged
+    ///
+    /// Rows are taken from the stream only while fewer batches than the bound of
+    /// [`BulkWriteOptions::with_max_in_flight`] are in flight, so a fast source is
+    /// held back by the server instead of piling up in memory. The first batch that
+    /// fails, after retries if enabled, stops the write and its error is returned;
+    /// rows still in the stream are left there. Returns the stats of the batches of
+    /// this stream alone. A `batch_size` of zero is treated as one.
+    pub async fn write_stream<S>(&mut self, stream: S, batch_size: usize) -> Result<BulkWriteStats>
+    where
+        S: Stream<Item = Row>,
+    {
+        let batch_size = batch_size.max(1);
+        let row_buffer_size = batch_size.min(DEFAULT_ROW_BUFFER_SIZE);
+        let before = self.stats();
+        let mut stream = std::pin::pin!(stream);
+
+        let mut rows = self.alloc_rows_buffer(batch_size, row_buffer_size)?;
+        while let Some(row) = stream.next().await {
+            rows.add_row(row)?;
+            if rows.len() >= batch_size {
+                let next = self.alloc_rows_buffer(batch_size, row_buffer_size)?;
+                self.submit_rows(std::mem::replace(&mut rows, next)).await?;
+            }
+        }
+        if !rows.is_empty() {
+            self.submit_rows(rows).await?;
+        }
+        self.wait_for_all_pending().await?;
+
+        Ok(self.stats().since(&before))
+    }
+
+    /// The [`Ack`] of a submitted batch
+    fn ack(&mut self, request_id: RequestId) -> Ack {
+        // Responses are only read through `&mut self`, so registering the sender
+        // after submitting cannot miss the response
+        let (sender, receiver) = oneshot::channel();
+        self.ack_senders.lock().insert(request_id, sender);
+        Ack {
+            request_id,
+            receiver,
+            parts: Vec::new(),
+            parts_affected_rows: 0,
+        }
+    }
+
+    /// Validate and submit rows, returning the request ids of the batches, more than
+    /// one if the rows were spilled
+    async fn submit_rows(&mut self, rows: Rows) -> Result<Vec<RequestId>> {
+        let started = self.start_network_call();
+        let row_count = rows.len() as u64;
+        let result = self.validate_and_submit_rows(rows).await;
+        self.count_submitted(started, row_count, &result);
+        result
+    }
+
+    /// Convert and submit columns, returning the request id of the batch
+    async fn submit_columns(&mut self, columns: ColumnarBatch) -> Result<RequestId> {
+        let started = self.start_network_call();
+        let row_count = columns.len() as u64;
+        let result = match columns.into_record_batch(&self.table_schema, &self.arrow_schema) {
+            Ok(batch) => self.submit_record_batch(batch).await,
+            Err(e) => Err(e),
+        };
+        self.count_submitted(started, row_count, &result);
+        result
+    }
+
+    fn count_submitted<T>(&mut self, started: Instant, row_count: u64, result: &Result<T>) {
+        if result.is_ok() {
+            self.metrics.batches += 1;
+            self.metrics.rows += row_count;
+        }
+        self.finish_network_call(started);
+    }
+
+    async fn validate_and_submit_rows(&mut self, rows: Rows) -> Result<Vec<RequestId>> {
         // Ensure that the rows are not empty
         ensure!(!rows.is_empty(), error::EmptyRowsSnafu);
         // Validate that the rows schema matches the writer's schema
-        self.validate_rows_schema(&rows)?;
-
-        let record_batch = RecordBatch::try_from(rows)?; // Zero-cost conversion
-        let request_id = self.submit_record_batch(record_batch).await?;
+        if self.validation {
+            self.validate_rows_schema(&rows)?;
+        }
 
-        Ok(request_id)
+        let (spilled, record_batch) = rows.into_parts()?;
+        let Some(spilled) = spilled else {
+            return Ok(vec![self.submit_record_batch(record_batch).await?]);
+        };
+
+        // Every spilled part is a batch of its own, read back only once there is room
+        // for it; the caller counts one batch, the others are counted here
+        let mut request_ids = Vec::new();
+        for batch in spilled {
+            if !request_ids.is_empty() {
+                self.metrics.batches += 1;
+            }
+            request_ids.push(self.submit_record_batch(batch?).await?);
+        }
+        if record_batch.num_rows() > 0 {
+            self.metrics.batches += 1;
+            request_ids.push(self.submit_record_batch(record_batch).await?);
+        }
+        Ok(request_ids)
     }
 
     /// Wait for a specific request's response by `request_id`
@@ -254,6 +1263,13 @@ impl BulkStreamWriter {
         &mut self,
         target_request_id: RequestId,
     ) -> Result<DoPutResponse> {
+        let started = self.start_network_call();
+        let result = self.receive_response(target_request_id).await;
+        self.finish_network_call(started);
+        result
+    }
+
+    async fn receive_response(&mut self, target_request_id: RequestId) -> Result<DoPutResponse> {
         // Check if the response is already cached
         if let Some((response, _)) = self.completed_responses.remove(&target_request_id) {
             return Ok(response);
@@ -266,38 +1282,55 @@ impl BulkStreamWriter {
             let remaining_timeout = timeout_duration.saturating_sub(start_time.elapsed());
             // Check timeout
             if remaining_timeout.is_zero() {
-                return error::RequestTimeoutSnafu {
-                    request_ids: vec![target_request_id],
-                    timeout: self.timeout,
-                }
-                .fail();
+                return Err(self.report_failure(
+                    error::RequestTimeoutSnafu {
+                        request_ids: vec![target_request_id],
+                        timeout: self.timeout,
+                    }
+                    .build(),
+                ));
             }
 
             let next_result = timeout(remaining_timeout, self.response_stream.next()).await;
             let Ok(next_option) = next_result else {
-                return error::RequestTimeoutSnafu {
-                    request_ids: vec![target_request_id],
-                    timeout: self.timeout,
-                }
-                .fail();
+                return Err(self.report_failure(
+                    error::RequestTimeoutSnafu {
+                        request_ids: vec![target_request_id],
+                        timeout: self.timeout,
+                    }
+                    .build(),
+                ));
             };
             if let Some(response) = next_option {
-                let response = response?;
+                let response = match response {
+                    Ok(response) => response,
+                    Err(e) => {
+                        self.retry_or_fail(e).await?;
+                        continue;
+                    }
+                };
                 let request_id = response.request_id();
-                self.pending_requests.remove(&request_id);
+                self.complete_request(&response);
                 if request_id == target_request_id {
                     return Ok(response);
                 }
                 self.completed_responses
                     .insert(request_id, (response, Instant::now()));
             } else {
-                return error::StreamEndedSnafu.fail();
+                return Err(self.report_failure(error::StreamEndedSnafu.build()));
             }
         }
     }
 
     /// Wait for all pending requests to complete and return the responses
     pub async fn wait_for_all_pending(&mut self) -> Result<Vec<DoPutResponse>> {
+        let started = self.start_network_call();
+        let result = self.receive_all_pending().await;
+        self.finish_network_call(started);
+        result
+    }
+
+    async fn receive_all_pending(&mut self) -> Result<Vec<DoPutResponse>> {
         let mut responses =
             Vec::with_capacity(self.pending_requests.len() + self.completed_responses.len());
 
@@ -320,27 +1353,36 @@ impl BulkStreamWriter {
             select! {
                 () = timeout_sleep => {
                     let pending_ids: Vec<RequestId> = self.pending_requests.keys().copied().collect();
-                    return error::RequestTimeoutSnafu {
-                        request_ids: pending_ids,
-                        timeout: self.timeout,
-                    }
-                    .fail();
+                    return Err(self.report_failure(
+                        error::RequestTimeoutSnafu {
+                            request_ids: pending_ids,
+                            timeout: self.timeout,
+                        }
+                        .build(),
+                    ));
                 }
                 next_option = self.response_stream.next() => {
                     match next_option {
-                        Some(response) => {
+                        Some(Ok(response)) => {
                             // Process the first response
-                            self.handle_single_response(response?, &mut responses);
+                            self.handle_single_response(response, &mut responses);
 
                             // Drain immediately available responses to avoid false timeouts
                             loop {
                                 match self.response_stream.next().now_or_never() {
-                                    Some(Some(response)) => self.handle_single_response(response?, &mut responses),
+                                    Some(Some(Ok(response))) => {
+                                        self.handle_single_response(response, &mut responses);
+                                    }
+                                    Some(Some(Err(e))) => {
+                                        self.retry_or_fail(e).await?;
+                                        break;
+                                    }
                                     Some(None) => return self.handle_stream_end(responses),
                                     None => break, // No immediately available responses
                                 }
                             }
                         }
+                        Some(Err(e)) => self.retry_or_fail(e).await?,
                         None => return self.handle_stream_end(responses),
                     }
                 }
@@ -366,14 +1408,98 @@ impl BulkStreamWriter {
             .collect()
     }
 
-    /// Finish the bulk write operation and close the connection
-    pub async fn finish(self) -> Result<()> {
-        let _responses = self.finish_with_responses().await?;
-        // Discard responses since finish() doesn't return them
-        Ok(())
+    /// Get a snapshot of the time split between buffering rows and the network
+    pub fn metrics(&self) -> BulkMetricsSnapshot {
+        self.metrics
+    }
+
+    /// Get a snapshot of the ingestion counters of this writer
+    ///
+    /// The counters are plain atomic loads, so sampling them between writes, e.g. to
+    /// report progress, does not slow the writer down.
+    pub fn stats(&self) -> BulkWriteStats {
+        BulkWriteStats {
+            rows: self.counters.rows.load(Ordering::Relaxed),
+            batches: self.counters.batches.load(Ordering::Relaxed),
+            uncompressed_bytes: self.counters.uncompressed_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.counters.compressed_bytes.load(Ordering::Relaxed),
+            in_flight: self.pending_requests.len(),
+            retries: self.metrics.retries,
+            affected_rows: self.counters.affected_rows.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Take the responses of requests that the server failed
+    ///
+    /// A server error ends the response stream, so every request pending at that point
+    /// is recorded as failed, carrying the table name and the server's status code and
+    /// message. Each request is returned once, ordered by request id, with the latest
+    /// error reported for it.
+    pub fn take_failed_responses(&mut self) -> Vec<DoPutResponse> {
+        let mut failed: Vec<DoPutResponse> = self
+            .failed_responses
+            .get_mut()
+            .drain()
+            .map(|(_, response)| response)
+            .collect();
+        failed.sort_by_key(|response| response.request_id());
+        failed
+    }
+
+    /// Finish the bulk write operation and close the connection
+    pub async fn finish(self) -> Result<()> {
+        let _responses = self.finish_with_responses().await?;
+        // Discard responses since finish() doesn't return them
+        Ok(())
+    }
+
+    /// Stop writing, e.g. on SIGTERM, waiting at most `deadline` for the batches in
+    /// flight before closing the stream
+    ///
+    /// Consuming the writer stops it from accepting batches. Responses cached or
+    /// received within `deadline` are returned as acknowledged; a failed or ended
+    /// stream stops the wait early. Unlike [`BulkStreamWriter::finish_with_responses`],
+    /// which waits up to the configured timeout and fails if a batch is missing,
+    /// this never takes much longer than `deadline` and never fails. [`Ack`]s of
+    /// unacknowledged batches resolve to `AckDropped`. To cancel on a signal, race
+    /// the writing loop against it with `tokio::select!` and call this afterwards.
+    ///
+    /// Delivery is at least once: an unacknowledged batch may have been written by
+    /// the server with only its response lost, so resending it can duplicate its
+    /// rows unless the table deduplicates them by primary key, i.e. is not in append
+    /// mode.
+    pub async fn cancel(mut self, deadline: Duration) -> CancelSummary {
+        let mut acknowledged = Vec::with_capacity(self.completed_responses.len());
+        let completed_responses = std::mem::take(&mut self.completed_responses);
+        for (request_id, (response, _)) in completed_responses {
+            self.pending_requests.remove(&request_id);
+            acknowledged.push(response);
+        }
+
+        let deadline = tokio::time::Instant::now() + deadline;
+        while !self.pending_requests.is_empty() {
+            match tokio::time::timeout_at(deadline, self.response_stream.next()).await {
+                Ok(Some(Ok(response))) => self.handle_single_response(response, &mut acknowledged),
+                // A failed or ended stream answers no further batch
+                Ok(Some(Err(_)) | None) | Err(_) => break,
+            }
+        }
+        self.sender.close_channel();
+
+        acknowledged.sort_by_key(DoPutResponse::request_id);
+        let mut unacknowledged: Vec<RequestId> = self.pending_requests.keys().copied().collect();
+        unacknowledged.sort_unstable();
+        CancelSummary {
+            acknowledged,
+            unacknowledged,
+        }
     }
 
     /// Finish the bulk write operation and return all responses
+    ///
+    /// Waits for every pending batch, failing with `RequestTimeout` if the server
+    /// does not answer within the configured timeout; see
+    /// [`BulkStreamWriter::cancel`] to stop within a deadline of your choice.
     pub async fn finish_with_responses(mut self) -> Result<Vec<DoPutResponse>> {
         let mut all_responses = Vec::new();
 
@@ -400,15 +1526,84 @@ impl BulkStreamWriter {
         Ok(all_responses)
     }
 
+    /// The number of rows the next batch should have, with adaptive batching enabled
+    ///
+    /// Pass it as the capacity of [`Self::alloc_rows_buffer`] before filling each
+    /// buffer, see [`BulkWriteOptions::with_adaptive_batching`]. Returns `None`
+    /// without adaptive batching.
+    pub fn recommended_batch_size(&self) -> Option<usize> {
+        self.batch_sizer.map(|sizer| sizer.current)
+    }
+
     /// Allocate a new rows buffer that is bound to this writer's schema
     /// This ensures schema compatibility and provides optimal performance
     pub fn alloc_rows_buffer(&self, capacity: usize, row_buffer_size: usize) -> Result<Rows> {
-        Rows::with_arrow_schema(
+        let rows = Rows::with_arrow_schema(
             self.column_schemas(),
             self.arrow_schema.clone(),
             capacity,
             row_buffer_size,
-        )
+        )?;
+
+        let rows = match self.timestamp_range.filter(|_| self.validation) {
+            Some(range) => rows.with_timestamp_range(range),
+            None => rows,
+        };
+        Ok(match &self.spill {
+            Some((threshold, temp_dir)) => rows.with_spill(*threshold, temp_dir.clone()),
+            None => rows,
+        })
+    }
+
+    /// Drain the rows currently held by `collector` and submit them in batches of up to
+    /// `batch_size` rows, without waiting for the responses
+    ///
+    /// Rows pushed while this runs are left for the next call. On an error, the rows
+    /// not submitted yet are put back in front of the collector, in their original
+    /// order, so a later call writes them; only a row rejected by the buffer, e.g. for
+    /// an implausible timestamp, is dropped with the error. Batches submitted before
+    /// the error are not put back.
+    pub async fn write_collected(
+        &mut self,
+        collector: &SharedRowCollector,
+        batch_size: usize,
+    ) -> Result<Vec<RequestId>> {
+        let batch_size = batch_size.max(1);
+        let mut rows = collector.take();
+        let mut request_ids = Vec::with_capacity(rows.len().div_ceil(batch_size));
+
+        while !rows.is_empty() {
+            let rest = rows.split_off(batch_size.min(rows.len()));
+            let batch = std::mem::replace(&mut rows, rest);
+            let buffer = match self.alloc_rows_buffer(batch_size, batch_size) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    collector.put_back(batch.into_iter().chain(rows));
+                    return Err(e);
+                }
+            };
+            let buffer = match fill_buffer(buffer, &batch) {
+                Ok(buffer) => buffer,
+                Err((rejected, e)) => {
+                    let unsubmitted = batch
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(index, _)| *index != rejected)
+                        .map(|(_, row)| row);
+                    collector.put_back(unsubmitted.chain(rows));
+                    return Err(e);
+                }
+            };
+            match self.submit_rows(buffer).await {
+                Ok(submitted) => request_ids.extend(submitted),
+                Err(e) => {
+                    collector.put_back(batch.into_iter().chain(rows));
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(request_ids)
     }
 
     /// Create a new Row builder that is compatible with this writer's schema
@@ -431,21 +1626,39 @@ impl BulkStreamWriter {
         self.table_schema.columns()
     }
 
+    /// Get the compression of the batches sent by this writer
+    #[must_use]
+    pub fn compression(&self) -> CompressionType {
+        self.compression
+    }
+
+    /// Get the parallelism of this writer
+    #[must_use]
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+
+    /// Get the maximum number of batches in flight, see
+    /// [`BulkWriteOptions::with_max_in_flight`]
+    #[must_use]
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
     /// Helper method to handle a single response
     fn handle_single_response(
         &mut self,
         response: DoPutResponse,
         responses: &mut Vec<DoPutResponse>,
     ) {
-        let request_id = response.request_id();
-        self.pending_requests.remove(&request_id);
+        self.complete_request(&response);
         responses.push(response);
     }
 
     /// Helper method to receive a single response and remove the pending request
     fn receive_response_and_remove_pending(&mut self, response: DoPutResponse) {
         let request_id = response.request_id();
-        self.pending_requests.remove(&request_id);
+        self.complete_request(&response);
         self.completed_responses
             .insert(request_id, (response, Instant::now()));
 
@@ -466,7 +1679,9 @@ impl BulkStreamWriter {
 
     /// Helper method to handle stream end cases
     fn handle_stream_end(&self, responses: Vec<DoPutResponse>) -> Result<Vec<DoPutResponse>> {
-        ensure!(self.pending_requests.is_empty(), error::StreamEndedSnafu);
+        if !self.pending_requests.is_empty() {
+            return Err(self.report_failure(error::StreamEndedSnafu.build()));
+        }
         Ok(responses)
     }
 
@@ -475,74 +1690,296 @@ impl BulkStreamWriter {
     fn handle_stream_end_during_processing(&self) -> Result<()> {
         if !self.pending_requests.is_empty() {
             let pending_ids: Vec<RequestId> = self.pending_requests.keys().copied().collect();
-            return error::StreamEndedWithPendingRequestsSnafu {
-                request_ids: pending_ids,
-            }
-            .fail();
+            return Err(self.report_failure(
+                error::StreamEndedWithPendingRequestsSnafu {
+                    request_ids: pending_ids,
+                }
+                .build(),
+            ));
         }
         Ok(())
     }
 
+    /// Notify the `on_error` callback about the requests affected by `error`
+    ///
+    /// Errors that name their requests report those, any other error breaks
+    /// the stream and thereby fails every pending request.
+    fn report_failure(&self, error: Error) -> Error {
+        let affected: Vec<RequestId> = match &error {
+            Error::RequestTimeout { request_ids, .. }
+            | Error::StreamEndedWithPendingRequests { request_ids, .. } => request_ids.clone(),
+            _ => self.pending_requests.keys().copied().collect(),
+        };
+
+        let mut ack_senders = self.ack_senders.lock();
+        if let Error::Server { status, msg } = &error {
+            let table_name: Arc<str> = Arc::from(self.table_name());
+            let mut failed_responses = self.failed_responses.lock();
+            for request_id in &affected {
+                let error = ResponseError::new(status.code(), msg.clone());
+                let response =
+                    DoPutResponse::failed(*request_id, error).with_table_name(table_name.clone());
+                if let Some(sender) = ack_senders.remove(request_id) {
+                    let _ = sender.send(response.clone());
+                }
+                failed_responses.insert(*request_id, response);
+            }
+        } else {
+            // Dropping the senders resolves the affected acks with an error
+            for request_id in &affected {
+                ack_senders.remove(request_id);
+            }
+        }
+        drop(ack_senders);
+
+        if let Some(on_error) = &self.on_error {
+            for request_id in &affected {
+                on_error(*request_id, &error);
+            }
+        }
+        error
+    }
+
     /// Submit a record batch without waiting for response
     /// Returns the `request_id` for later tracking
     async fn submit_record_batch(&mut self, batch: RecordBatch) -> Result<RequestId> {
+        if self.validation {
+            self.validate_upsert_keys(&batch)?;
+        }
+        if self.stream_reset_due() {
+            self.reset_stream().await?;
+        }
+
         // Send schema first if not already sent
         if !self.schema_sent {
-            let mut schema_data = self.encoder.encode(FlightMessage::Schema(batch.schema()));
-            let metadata = DoPutMetadata::new(0);
-            schema_data.app_metadata = serde_json::to_vec(&metadata)
-                .context(error::SerializeMetadataSnafu)?
-                .into();
-
-            schema_data.flight_descriptor = Some(FlightDescriptor {
-                r#type: arrow_flight::flight_descriptor::DescriptorType::Path as i32,
-                path: vec![self.table_name().to_string()],
-                ..Default::default()
-            });
-
-            self.sender
-                .send(schema_data)
-                .await
-                .context(error::SendDataSnafu)?;
-
-            let response_result = timeout(self.timeout, self.response_stream.next()).await;
-            match response_result {
-                Ok(Some(response)) => {
-                    let _schema_response = response?;
-                }
-                Ok(None) => return error::StreamEndedSnafu.fail(),
-                Err(_) => {
-                    return error::RequestTimeoutSnafu {
-                        request_ids: vec![],
-                        timeout: self.timeout,
-                    }
-                    .fail();
-                }
+            if let Err(e) = self.send_schema(batch.schema()).await {
+                self.retry_or_fail(e).await?;
             }
-
-            self.schema_sent = true;
         }
 
-        // Wait for available slot if we've reached parallelism limit
-        while self.pending_requests.len() >= self.parallelism {
+        // Wait for available slot if we've reached the in-flight limit
+        while self.pending_requests.len() >= self.max_in_flight {
             self.process_pending_responses().await?;
         }
 
         // Send the request
         let request_id = self.next_request_id();
+        if self.max_retries > 0 {
+            // Keep the batch until it is acknowledged, to resend it if the stream fails
+            self.unacked_batches.insert(request_id, batch.clone());
+        }
+        if let Some(retry_budget) = &self.retry_budget {
+            retry_budget.record_request();
+        }
+        let row_count = batch.num_rows();
+        self.send_batch(request_id, batch).await?;
+        self.counters.batches.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .rows
+            .fetch_add(row_count as u64, Ordering::Relaxed);
+
+        // Track this request but don't wait for response
+        self.pending_requests
+            .insert(request_id, (Instant::now(), row_count));
+
+        Ok(request_id)
+    }
+
+    /// Send the schema message that opens a stream and wait for its acknowledgement
+    async fn send_schema(&mut self, schema: Arc<Schema>) -> Result<()> {
+        let mut schema_data = self.encoder.encode(FlightMessage::Schema(schema));
+        let metadata = DoPutMetadata::new(0);
+        schema_data.app_metadata = serde_json::to_vec(&metadata)
+            .context(error::SerializeMetadataSnafu)?
+            .into();
+
+        schema_data.flight_descriptor = Some(FlightDescriptor {
+            r#type: arrow_flight::flight_descriptor::DescriptorType::Path as i32,
+            path: vec![self.table_name().to_string()],
+            ..Default::default()
+        });
+
+        self.sender
+            .send(schema_data)
+            .await
+            .context(error::SendDataSnafu)?;
+
+        let response_result = timeout(self.timeout, self.response_stream.next()).await;
+        match response_result {
+            Ok(Some(response)) => {
+                let _schema_response = response?;
+            }
+            Ok(None) => return error::StreamEndedSnafu.fail(),
+            Err(_) => {
+                return error::RequestTimeoutSnafu {
+                    request_ids: vec![],
+                    timeout: self.timeout,
+                }
+                .fail();
+            }
+        }
+
+        self.schema_sent = true;
+        Ok(())
+    }
+
+    /// Send `batch` on the current stream as request `request_id`
+    async fn send_batch(&mut self, request_id: RequestId, batch: RecordBatch) -> Result<()> {
+        let uncompressed_size = record_batch_data_size(&batch) as u64;
         let message = FlightMessage::RecordBatch(batch);
         let mut data = self.encoder.encode(message);
         let metadata = DoPutMetadata::new(request_id);
         data.app_metadata = serde_json::to_vec(&metadata)
             .context(error::SerializeMetadataSnafu)?
             .into();
+        if let Some(interceptor) = &self.interceptor {
+            let app_metadata = data.app_metadata.clone();
+            interceptor(&mut data);
+            // The request id pairs the batch with its response, keep it whatever the
+            // interceptor did
+            data.app_metadata = app_metadata;
+        }
 
+        let size = (data.data_header.len() + data.data_body.len()) as u64;
         self.sender.send(data).await.context(error::SendDataSnafu)?;
+        self.stream_batches += 1;
+        self.stream_bytes += size;
+        self.counters
+            .uncompressed_bytes
+            .fetch_add(uncompressed_size, Ordering::Relaxed);
+        self.counters
+            .compressed_bytes
+            .fetch_add(size, Ordering::Relaxed);
+        Ok(())
+    }
 
-        // Track this request but don't wait for response
-        self.pending_requests.insert(request_id, Instant::now());
+    /// Forget a request whose response arrived
+    fn complete_request(&mut self, response: &DoPutResponse) {
+        let request_id = response.request_id();
+        self.counters
+            .affected_rows
+            .fetch_add(response.affected_rows() as u64, Ordering::Relaxed);
+        if let Some((sent, rows)) = self.pending_requests.remove(&request_id) {
+            let latency = sent.elapsed();
+            if let Some(sizer) = &mut self.batch_sizer {
+                sizer.observe(latency);
+            }
+            if self
+                .slow_batch_threshold
+                .is_some_and(|threshold| latency > threshold)
+            {
+                tracing::warn!(
+                    table = self.table_schema.name(),
+                    request_id,
+                    rows,
+                    latency_ms = latency.as_millis() as u64,
+                    "Slow bulk write batch"
+                );
+            }
+        }
+        self.unacked_batches.remove(&request_id);
+        self.retry_attempt = 0;
+    }
 
-        Ok(request_id)
+    /// Resend the unacknowledged batches on a new stream if `error` is transient and
+    /// retries are left, or report it as the failure of the pending batches
+    async fn retry_or_fail(&mut self, mut error: Error) -> Result<()> {
+        if let Some(sizer) = &mut self.batch_sizer {
+            sizer.back_off();
+        }
+        while self.should_retry(&error) {
+            self.retry_attempt += 1;
+            self.metrics.retries += 1;
+            tokio::time::sleep(self.retry_delay()).await;
+            match self.resend_unacked().await {
+                Ok(()) => return Ok(()),
+                Err(e) => error = e,
+            }
+        }
+        self.unacked_batches.clear();
+        Err(self.report_failure(error))
+    }
+
+    fn should_retry(&self, error: &Error) -> bool {
+        let transient = error.kind() == ErrorKind::Transport;
+        if !transient || self.stream_opener.is_none() || self.retry_attempt >= self.max_retries {
+            return false;
+        }
+        match &self.retry_budget {
+            Some(retry_budget) => retry_budget.try_retry(),
+            None => true,
+        }
+    }
+
+    /// The backoff before the current attempt: a random delay between half and all of
+    /// `base * 2^(attempt - 1)`, capped at `max`
+    fn retry_delay(&self) -> Duration {
+        let exponent = self.retry_attempt.saturating_sub(1).min(31) as u32;
+        let delay = self
+            .retry_backoff_base
+            .saturating_mul(1 << exponent)
+            .min(self.retry_backoff_max);
+        delay.mul_f64(0.5 + rand::random::<f64>() * 0.5)
+    }
+
+    /// Open a new stream and resend every unacknowledged batch with its request id
+    async fn resend_unacked(&mut self) -> Result<()> {
+        self.open_new_stream().await?;
+        self.send_schema(self.arrow_schema.clone()).await?;
+
+        let mut request_ids: Vec<RequestId> = self.unacked_batches.keys().copied().collect();
+        request_ids.sort_unstable();
+        for request_id in request_ids {
+            let batch = self.unacked_batches[&request_id].clone();
+            let row_count = batch.num_rows();
+            self.send_batch(request_id, batch).await?;
+            self.pending_requests
+                .insert(request_id, (Instant::now(), row_count));
+        }
+        Ok(())
+    }
+
+    /// Whether the current stream has reached the configured reset interval
+    fn stream_reset_due(&self) -> bool {
+        if self.stream_opener.is_none() || self.stream_batches == 0 {
+            return false;
+        }
+        match self.stream_reset_interval {
+            Some(StreamResetInterval::Batches(batches)) => self.stream_batches >= batches,
+            Some(StreamResetInterval::Bytes(bytes)) => self.stream_bytes >= bytes,
+            None => false,
+        }
+    }
+
+    /// Replace the current stream with a new one, without losing any outstanding batch
+    async fn reset_stream(&mut self) -> Result<()> {
+        // Every batch of the old stream must be acknowledged before it is closed,
+        // the responses are kept for the caller to retrieve as usual
+        for response in self.receive_all_pending().await? {
+            self.completed_responses
+                .insert(response.request_id(), (response, Instant::now()));
+        }
+
+        self.open_new_stream().await
+    }
+
+    /// Replace the current stream with a new one from the stream opener
+    async fn open_new_stream(&mut self) -> Result<()> {
+        let Some(stream_opener) = &self.stream_opener else {
+            return Ok(());
+        };
+        let (sender, response_stream) = stream_opener().await?;
+        self.sender.close_channel();
+        self.sender = sender;
+        self.response_stream = resolve_responses(
+            response_stream,
+            self.table_schema.name(),
+            self.ack_senders.clone(),
+        );
+        self.schema_sent = false;
+        self.stream_batches = 0;
+        self.stream_bytes = 0;
+        Ok(())
     }
 
     /// Check for timed out requests
@@ -553,7 +1990,7 @@ impl BulkStreamWriter {
         let timed_out_requests: Vec<RequestId> = self
             .pending_requests
             .iter()
-            .filter_map(|(&request_id, &sent_time)| {
+            .filter_map(|(&request_id, &(sent_time, _))| {
                 if now.duration_since(sent_time) > timeout_duration {
                     Some(request_id)
                 } else {
@@ -563,11 +2000,13 @@ impl BulkStreamWriter {
             .collect();
 
         if !timed_out_requests.is_empty() {
-            return error::RequestTimeoutSnafu {
-                request_ids: timed_out_requests,
-                timeout: self.timeout,
-            }
-            .fail();
+            return Err(self.report_failure(
+                error::RequestTimeoutSnafu {
+                    request_ids: timed_out_requests,
+                    timeout: self.timeout,
+                }
+                .build(),
+            ));
         }
 
         Ok(())
@@ -582,24 +2021,26 @@ impl BulkStreamWriter {
         // First, wait for at least one response (blocking)
         let response_result = timeout(self.timeout, self.response_stream.next()).await;
         match response_result {
-            Ok(Some(response)) => self.receive_response_and_remove_pending(response?),
+            Ok(Some(Ok(response))) => self.receive_response_and_remove_pending(response),
+            Ok(Some(Err(e))) => return self.retry_or_fail(e).await,
             Ok(None) => return self.handle_stream_end_during_processing(),
             Err(_) => {
                 let pending_ids: Vec<RequestId> = self.pending_requests.keys().copied().collect();
-                return error::RequestTimeoutSnafu {
-                    request_ids: pending_ids,
-                    timeout: self.timeout,
-                }
-                .fail();
+                return Err(self.report_failure(
+                    error::RequestTimeoutSnafu {
+                        request_ids: pending_ids,
+                        timeout: self.timeout,
+                    }
+                    .build(),
+                ));
             }
         }
 
         // Then drain any additional responses quickly
         loop {
             match self.response_stream.next().now_or_never() {
-                Some(Some(response)) => {
-                    self.receive_response_and_remove_pending(response?);
-                }
+                Some(Some(Ok(response))) => self.receive_response_and_remove_pending(response),
+                Some(Some(Err(e))) => return self.retry_or_fail(e).await,
                 Some(None) => return self.handle_stream_end_during_processing(),
                 None => break, // No immediately available responses
             }
@@ -633,6 +2074,24 @@ impl BulkStreamWriter {
         Ok(())
     }
 
+    /// Check that no tag column of `batch` is null, if upserting
+    fn validate_upsert_keys(&self, batch: &RecordBatch) -> Result<()> {
+        for &index in &self.upsert_key_columns {
+            let column = batch.column(index);
+            if column.null_count() == 0 {
+                continue;
+            }
+            let row = (0..column.len()).find(|&row| column.is_null(row));
+            return error::MissingPrimaryKeySnafu {
+                table: self.table_name(),
+                column: self.table_schema.columns()[index].name.as_str(),
+                row: row.unwrap_or_default(),
+            }
+            .fail();
+        }
+        Ok(())
+    }
+
     /// Helper to create schema mismatch error with lazy formatting
     #[cold]
     fn schema_mismatch_error(
@@ -646,6 +2105,19 @@ impl BulkStreamWriter {
         .fail()
     }
 
+    /// Account the time since the previous network call as buffering time
+    fn start_network_call(&mut self) -> Instant {
+        let now = Instant::now();
+        self.metrics.buffering_time += now.saturating_duration_since(self.last_network_call_end);
+        now
+    }
+
+    fn finish_network_call(&mut self, started: Instant) {
+        let now = Instant::now();
+        self.metrics.network_time += now.saturating_duration_since(started);
+        self.last_network_call_end = now;
+    }
+
     fn next_request_id(&mut self) -> RequestId {
         // Skip ID 0 as it's reserved for special cases
         self.next_request_id = self.next_request_id.wrapping_add(1);
@@ -656,6 +2128,66 @@ impl BulkStreamWriter {
     }
 }
 
+/// Open a "`DoPut`" stream on `database`
+async fn open_bulk_stream(
+    database: &Database,
+    write_mode: Option<WriteMode>,
+) -> Result<(mpsc::Sender<FlightData>, ResponseStream)> {
+    // Create a channel for streaming FlightData
+    let channel_buffer_size = get_env_or_default(
+        "GREPTIMEDB_CHANNEL_BUFFER_SIZE",
+        DEFAULT_CHANNEL_BUFFER_SIZE,
+    );
+    let (sender, receiver) = mpsc::channel::<FlightData>(channel_buffer_size);
+
+    // Convert receiver to a stream and start the do_put operation
+    let flight_stream = receiver.boxed();
+    let hints: Vec<_> = write_mode.map(WriteMode::hint).into_iter().collect();
+    let response_stream = database.do_put_with_hints(flight_stream, &hints).await?;
+    Ok((sender, response_stream))
+}
+
+/// Tag every response with the table it belongs to and resolve its `Ack`, if any
+fn resolve_responses(
+    response_stream: ResponseStream,
+    table_name: &str,
+    ack_senders: AckSenders,
+) -> ResponseStream {
+    let table_name: Arc<str> = Arc::from(table_name);
+    Box::pin(response_stream.map(move |response| {
+        response.map(|response| {
+            let response = response.with_table_name(table_name.clone());
+            if let Some(sender) = ack_senders.lock().remove(&response.request_id()) {
+                let _ = sender.send(response.clone());
+            }
+            response
+        })
+    }))
+}
+
+/// The size of the data buffers of `batch`, without the capacity they over-allocate
+fn record_batch_data_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| column.to_data().get_slice_memory_size().unwrap_or(0))
+        .sum()
+}
+
+/// Convert a table schema to the Arrow schema used on the wire
+fn table_arrow_schema(table_schema: &TableSchema) -> Result<Arc<Schema>> {
+    let fields: Result<Vec<Field>> = table_schema
+        .columns()
+        .iter()
+        .map(|col| {
+            let nullable = col.semantic_type != SemanticType::Timestamp;
+            column_to_arrow_data_type(col)
+                .map(|data_type| Field::new(&col.name, data_type, nullable))
+        })
+        .collect();
+    Ok(Arc::new(Schema::new(fields?)))
+}
+
 // Helper function to convert ColumnDataType to Arrow DataType
 // Based on GreptimeDB Java implementation - only supports actually implemented types
 fn column_to_arrow_data_type(column: &Column) -> Result<DataType> {
@@ -723,6 +2255,66 @@ fn column_to_arrow_data_type(column: &Column) -> Result<DataType> {
     })
 }
 
+/// Add a copy of every row of `batch` to `buffer`, or return the index of the
+/// rejected row with the error
+fn fill_buffer(mut buffer: Rows, batch: &[Row]) -> std::result::Result<Rows, (usize, Error)> {
+    for (index, row) in batch.iter().enumerate() {
+        buffer.add_row(row.clone()).map_err(|e| (index, e))?;
+    }
+    Ok(buffer)
+}
+
+/// Thread-safe buffer that several producers push rows into
+///
+/// Clones share the same buffer, so hand one clone to each producer thread and let the
+/// task owning the [`BulkStreamWriter`] drain it with
+/// [`BulkStreamWriter::write_collected`]. Rows pushed from different threads are not
+/// ordered relative to each other.
+#[derive(Debug, Clone, Default)]
+pub struct SharedRowCollector {
+    rows: Arc<parking_lot::Mutex<Vec<Row>>>,
+}
+
+impl SharedRowCollector {
+    /// Create a new empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a row into the collector
+    pub fn push(&self, row: Row) {
+        self.rows.lock().push(row);
+    }
+
+    /// Push multiple rows into the collector under a single lock
+    pub fn extend(&self, rows: impl IntoIterator<Item = Row>) {
+        self.rows.lock().extend(rows);
+    }
+
+    /// Get the number of rows waiting to be written
+    pub fn len(&self) -> usize {
+        self.rows.lock().len()
+    }
+
+    /// Check if no rows are waiting to be written
+    pub fn is_empty(&self) -> bool {
+        self.rows.lock().is_empty()
+    }
+
+    /// Take all rows collected so far, leaving the collector empty
+    pub fn take(&self) -> Vec<Row> {
+        std::mem::take(&mut *self.rows.lock())
+    }
+
+    /// Return taken rows in front of the rows pushed since they were taken
+    fn put_back(&self, rows: impl IntoIterator<Item = Row>) {
+        let mut guard = self.rows.lock();
+        let pushed = std::mem::take(&mut *guard);
+        guard.extend(rows);
+        guard.extend(pushed);
+    }
+}
+
 /// High-level rows abstraction with buffered batch conversion
 /// This provides a user-friendly API while maintaining optimal performance
 pub struct Rows {
@@ -732,6 +2324,10 @@ pub struct Rows {
     // Row buffering for improved performance
     row_buffer: Vec<Row>,
     buffer_size: usize,
+    // Optional timestamp plausibility check: range and (column index, unit) pairs
+    timestamp_check: Option<(TimestampRange, Vec<(usize, TimeUnit)>)>,
+    // Optional spilling of the built rows to disk
+    spill: Option<Spill>,
 }
 
 impl Rows {
@@ -746,6 +2342,8 @@ impl Rows {
             column_count: column_schemas.len(),
             row_buffer: Vec::with_capacity(row_buffer_size),
             buffer_size: row_buffer_size,
+            timestamp_check: None,
+            spill: None,
         })
     }
 
@@ -765,9 +2363,38 @@ impl Rows {
             column_count: column_schemas.len(),
             row_buffer: Vec::with_capacity(row_buffer_size),
             buffer_size: row_buffer_size,
+            timestamp_check: None,
+            spill: None,
         })
     }
 
+    /// Check every timestamp column against `range` when rows are added.
+    ///
+    /// See [`TimestampRange`] for why this is a heuristic.
+    #[must_use]
+    pub fn with_timestamp_range(mut self, range: TimestampRange) -> Self {
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, field)| match field.data_type() {
+                DataType::Timestamp(unit, _) => Some((index, *unit)),
+                _ => None,
+            })
+            .collect();
+        self.timestamp_check = Some((range, columns));
+        self
+    }
+
+    /// Spill the built rows to a temporary file in `temp_dir` whenever about
+    /// `threshold` bytes are buffered, see [`BulkWriteOptions::with_spill`]
+    #[must_use]
+    pub fn with_spill(mut self, threshold: usize, temp_dir: impl Into<PathBuf>) -> Self {
+        self.spill = Some(Spill::new(threshold, temp_dir.into()));
+        self
+    }
+
     /// Add a row to the collection using move semantics
     pub fn add_row(&mut self, row: Row) -> Result<()> {
         // Validate column count matches schema
@@ -779,16 +2406,88 @@ impl Rows {
             }
         );
 
+        if let Some((range, columns)) = &self.timestamp_check {
+            for &(index, unit) in columns {
+                if let Some(value) = row.value(index) {
+                    range.check(self.schema.field(index).name(), unit, value)?;
+                }
+            }
+        }
+
+        let spill_due = self.spill.as_mut().is_some_and(|spill| spill.record(&row));
         self.row_buffer.push(row);
 
         // If buffer is full, flush it to a RecordBatch
-        if self.row_buffer.len() >= self.buffer_size {
+        if spill_due || self.row_buffer.len() >= self.buffer_size {
             self.flush_buffer()?;
         }
+        if spill_due {
+            let batch = self.builder.take_batch()?;
+            if let Some(spill) = &mut self.spill {
+                spill.write(&batch)?;
+            }
+        }
 
         Ok(())
     }
 
+    /// Add a sparse row, filling unset columns with nulls
+    pub fn add_sparse_row(&mut self, row: SparseRow) -> Result<()> {
+        self.add_row(row.into_dense_with_width(self.column_count)?)
+    }
+
+    /// Add every row of an Arrow `RecordBatch`, returning the number of rows added.
+    ///
+    /// The columns of `batch` must match the schema of this collection by name, order
+    /// and data type. Rows go through [`Rows::add_row`], so a timestamp range set
+    /// with [`Rows::with_timestamp_range`] applies to them as well.
+    pub fn add_record_batch(&mut self, batch: &RecordBatch) -> Result<usize> {
+        let batch_schema = batch.schema();
+        ensure!(
+            batch_schema.fields().len() == self.column_count,
+            error::InvalidColumnCountSnafu {
+                expected: self.column_count,
+                actual: batch_schema.fields().len(),
+            }
+        );
+
+        for (index, (expected, actual)) in self
+            .schema
+            .fields()
+            .iter()
+            .zip(batch_schema.fields().iter())
+            .enumerate()
+        {
+            let same_type = match (expected.data_type(), actual.data_type()) {
+                // The time zone is metadata only, the stored values are UTC either way
+                (DataType::Timestamp(expected, _), DataType::Timestamp(actual, _)) => {
+                    expected == actual
+                }
+                (expected, actual) => expected == actual,
+            };
+            ensure!(
+                expected.name() == actual.name() && same_type,
+                error::RecordBatchColumnMismatchSnafu {
+                    index,
+                    expected: format!("`{}` {}", expected.name(), expected.data_type()),
+                    actual: format!("`{}` {}", actual.name(), actual.data_type()),
+                }
+            );
+        }
+
+        let num_rows = batch.num_rows();
+        for row_index in 0..num_rows {
+            let values = batch
+                .columns()
+                .iter()
+                .map(|array| arrow_value(array.as_ref(), row_index))
+                .collect::<Result<Vec<_>>>()?;
+            self.add_row(Row::from_values(values))?;
+        }
+
+        Ok(num_rows)
+    }
+
     /// Flush the current row buffer to the builder
     fn flush_buffer(&mut self) -> Result<()> {
         if self.row_buffer.is_empty() {
@@ -805,7 +2504,8 @@ impl Rows {
     /// Get the current number of rows
     #[must_use]
     pub fn len(&self) -> usize {
-        self.builder.len() + self.row_buffer.len()
+        let spilled = self.spill.as_ref().map_or(0, Spill::rows);
+        self.builder.len() + self.row_buffer.len() + spilled
     }
 
     /// Check if the collection is empty
@@ -821,18 +2521,35 @@ impl Rows {
     pub fn schema(&self) -> &Schema {
         &self.schema
     }
+
+    /// Split into the spilled batches, if any, and a batch of the rows still in memory
+    fn into_parts(mut self) -> Result<(Option<spill::SpilledBatches>, RecordBatch)> {
+        self.flush_buffer()?;
+        let batch = self.builder.take_batch()?;
+        let spilled = match self.spill.take() {
+            Some(spill) => spill.into_batches()?,
+            None => None,
+        };
+        Ok((spilled, batch))
+    }
 }
 
 /// Convert Rows to RecordBatch, handling buffered data
 impl TryFrom<Rows> for RecordBatch {
     type Error = crate::Error;
 
-    fn try_from(mut rows: Rows) -> Result<Self> {
-        // Flush any remaining buffered rows to the builder
-        rows.flush_buffer()?;
-
-        // Build the single RecordBatch
-        rows.builder.build()
+    fn try_from(rows: Rows) -> Result<Self> {
+        let schema = rows.schema.clone();
+        let (spilled, batch) = rows.into_parts()?;
+        let Some(spilled) = spilled else {
+            return Ok(batch);
+        };
+
+        // Reading spilled rows back into one batch gives up the memory cap, but keeps
+        // the conversion correct
+        let mut batches = spilled.collect::<Result<Vec<_>>>()?;
+        batches.push(batch);
+        arrow::compute::concat_batches(&schema, &batches).context(error::CreateRecordBatchSnafu)
     }
 }
 
@@ -899,15 +2616,16 @@ impl RowBatchBuilder {
         Ok(())
     }
 
-    /// Build the RecordBatch from accumulated rows
-    fn build(mut self) -> Result<RecordBatch> {
+    /// Build the RecordBatch from accumulated rows, leaving the builder empty
+    fn take_batch(&mut self) -> Result<RecordBatch> {
         let arrays: Result<Vec<Arc<dyn Array>>> = self
             .builders
             .iter_mut()
             .map(ArrayBuilderEnum::finish)
             .collect();
+        self.current_rows = 0;
 
-        RecordBatch::try_new(self.schema, arrays?).context(error::CreateRecordBatchSnafu)
+        RecordBatch::try_new(self.schema.clone(), arrays?).context(error::CreateRecordBatchSnafu)
     }
 
     /// Get the current number of rows in the builder
@@ -1019,6 +2737,58 @@ impl ArrayBuilderEnum {
     }
 }
 
+/// Read the value at `row` of an Arrow array as a [`Value`]
+fn arrow_value(array: &dyn Array, row: usize) -> Result<Value> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Date32Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+        Int8Type, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    };
+
+    if array.is_null(row) {
+        return Ok(Value::Null);
+    }
+
+    Ok(match array.data_type() {
+        DataType::Boolean => Value::Boolean(array.as_boolean().value(row)),
+        DataType::Int8 => Value::Int8(array.as_primitive::<Int8Type>().value(row)),
+        DataType::Int16 => Value::Int16(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => Value::Int32(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => Value::Int64(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => Value::Uint8(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => Value::Uint16(array.as_primitive::<UInt16Type>().value(row)),
+        DataType::UInt32 => Value::Uint32(array.as_primitive::<UInt32Type>().value(row)),
+        DataType::UInt64 => Value::Uint64(array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float32 => Value::Float32(array.as_primitive::<Float32Type>().value(row)),
+        DataType::Float64 => Value::Float64(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => Value::String(array.as_string::<i32>().value(row).to_string()),
+        DataType::Binary => Value::Binary(array.as_binary::<i32>().value(row).to_vec()),
+        DataType::Date32 => Value::Date(array.as_primitive::<Date32Type>().value(row)),
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            Value::TimestampSecond(array.as_primitive::<TimestampSecondType>().value(row))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            Value::TimestampMillisecond(array.as_primitive::<TimestampMillisecondType>().value(row))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            Value::TimestampMicrosecond(array.as_primitive::<TimestampMicrosecondType>().value(row))
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            Value::TimestampNanosecond(array.as_primitive::<TimestampNanosecondType>().value(row))
+        }
+        DataType::Decimal128(_, _) => {
+            Value::Decimal128(array.as_primitive::<Decimal128Type>().value(row))
+        }
+        data_type => {
+            return error::UnsupportedDataTypeSnafu {
+                data_type: format!("{data_type}. Not supported when converting a RecordBatch"),
+            }
+            .fail();
+        }
+    })
+}
+
 /// Create an array builder enum for the given column with adaptive sizing
 /// Uses enum dispatch for maximum performance (zero-cost polymorphism)
 fn create_array_builder(
@@ -1147,15 +2917,41 @@ impl_arrow_builder!(Decimal128Builder, get_decimal128_unchecked, i128);
 
 // String and Binary types
 impl_arrow_builder!(StringBuilder, take_string_unchecked, String);
-impl_arrow_builder!(BinaryBuilder, take_binary_unchecked, Vec<u8>);
+
+impl ArrayBuilder for BinaryBuilder {
+    fn append_values_from_rows(&mut self, rows: &mut [Row], col_idx: usize) -> Result<()> {
+        for row in rows {
+            if let Some(Value::BinaryChunks(chunks)) = row.value(col_idx) {
+                // Write the chunks straight into the values buffer and close the value
+                // with an empty append, instead of joining them into a temporary first
+                for chunk in chunks {
+                    // Writing into the in-memory buffer of the builder cannot fail
+                    let _ = self.write_all(chunk);
+                }
+                self.append_value(b"");
+                continue;
+            }
+            // Use unchecked version for performance - col_idx is guaranteed to be valid by schema
+            self.append_option(unsafe { row.take_binary_unchecked(col_idx) });
+        }
+        Ok(())
+    }
+}
 
 /// A helper for building rows with schema-aware field access
 /// This prevents common mistakes like incorrect field order or types
 /// Uses O(1) field name lookup for optimal performance
+///
+/// Get one from [`BulkStreamWriter::new_row`] or, without a writer, from
+/// [`RowBuilder::for_schema`]. Each column can be set once; columns left unset are
+/// filled with [`Value::Null`] by [`RowBuilder::build`], except for the timestamp
+/// column, which cannot be null.
 pub struct RowBuilder<'a> {
     schema: &'a [Column],
     field_map: &'a HashMap<String, usize>, // Pre-computed field name to index mapping
     values: Vec<Option<Value>>,
+    // Index of the column the next `push` fills
+    next: usize,
 }
 
 impl<'a> RowBuilder<'a> {
@@ -1164,26 +2960,53 @@ impl<'a> RowBuilder<'a> {
             schema,
             field_map,
             values: vec![None; schema.len()],
+            next: 0,
         }
     }
 
+    /// Create a builder for rows of `schema`, addressing columns by their exact name
+    pub fn for_schema(schema: &'a TableSchema) -> Self {
+        Self::new(schema.columns(), schema.column_index_map())
+    }
+
+    /// Set the value of the next column in schema order, starting with the first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a value was already pushed for every column.
+    pub fn push(mut self, value: Value) -> Result<Self> {
+        ensure!(
+            self.next < self.values.len(),
+            error::TooManyValuesSnafu {
+                column_count: self.values.len(),
+            }
+        );
+
+        self.values[self.next] = Some(value);
+        self.next += 1;
+        Ok(self)
+    }
+
     /// Set a field value by name with O(1) lookup performance.
     /// This ensures correct field mapping and prevents field order mistakes.
-    pub fn set(mut self, field_name: &str, value: Value) -> Result<Self> {
-        let field_index = self
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the schema has no column `field_name` or if it was already set.
+    pub fn set(self, field_name: &str, value: Value) -> Result<Self> {
+        let field_index = *self
             .field_map
             .get(field_name)
             .context(error::MissingFieldSnafu { field: field_name })?;
 
-        self.values[*field_index] = Some(value);
-        Ok(self)
+        self.set_by_index(field_index, value)
     }
 
     /// Set a field value by index. This is faster than `set` as it avoids a map lookup.
     ///
     /// # Errors
     ///
-    /// Returns `Err` if `index` is out of bounds.
+    /// Returns `Err` if `index` is out of bounds or if the column was already set.
     pub fn set_by_index(mut self, index: usize, value: Value) -> Result<Self> {
         ensure!(
             index < self.values.len(),
@@ -1192,6 +3015,12 @@ impl<'a> RowBuilder<'a> {
                 total: self.values.len(),
             }
         );
+        ensure!(
+            self.values[index].is_none(),
+            error::ColumnAlreadySetSnafu {
+                column: self.schema[index].name.clone(),
+            }
+        );
 
         self.values[index] = Some(value);
         Ok(self)
@@ -1203,19 +3032,24 @@ impl<'a> RowBuilder<'a> {
         self.schema.len()
     }
 
-    /// Build the final Row, ensuring all required fields are set
+    /// Build the final Row, filling unset columns with [`Value::Null`]
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the timestamp column was not set, as it cannot be null.
     pub fn build(self) -> Result<Row> {
         let mut row_values = Vec::with_capacity(self.values.len());
 
         for (i, opt_value) in self.values.into_iter().enumerate() {
             match opt_value {
                 Some(value) => row_values.push(value),
-                None => {
+                None if self.schema[i].semantic_type == SemanticType::Timestamp => {
                     return error::MissingFieldSnafu {
                         field: self.schema[i].name.clone(),
                     }
                     .fail();
                 }
+                None => row_values.push(Value::Null),
             }
         }
 
@@ -1227,10 +3061,10 @@ impl<'a> RowBuilder<'a> {
 pub use crate::api::v1::ColumnDataType as ColumnType;
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::api::v1::{ColumnDataType, SemanticType};
-    use crate::table::{Column, Value};
+    use crate::table::{Column, ColumnOptions, Value};
 
     #[test]
     fn test_rows_schema_validation() {
@@ -1241,18 +3075,24 @@ mod tests {
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "name".to_string(),
                 data_type: ColumnDataType::String,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "timestamp".to_string(),
                 data_type: ColumnDataType::TimestampMillisecond,
                 semantic_type: SemanticType::Timestamp,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -1263,12 +3103,16 @@ mod tests {
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "value".to_string(),          // Different column name
                 data_type: ColumnDataType::Float64, // Different data type
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -1295,12 +3139,16 @@ mod tests {
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "message".to_string(),
                 data_type: ColumnDataType::String,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -1325,6 +3173,40 @@ mod tests {
         assert!(!rows.is_empty());
     }
 
+    #[test]
+    fn test_binary_chunks_are_encoded_concatenated() {
+        use arrow_array::BinaryArray;
+
+        let schema = vec![Column {
+            name: "blob".to_string(),
+            data_type: ColumnDataType::Binary,
+            semantic_type: SemanticType::Field,
+            data_type_extension: None,
+            is_primary_value: false,
+            options: ColumnOptions::default(),
+        }];
+        let mut rows = Rows::new(&schema, 3, 3).unwrap();
+        let chunks = vec![
+            bytes::Bytes::from_static(b"ab"),
+            bytes::Bytes::from_static(b"cd"),
+        ];
+        rows.add_row(Row::from_values(vec![Value::BinaryChunks(chunks)]))
+            .unwrap();
+        rows.add_row(Row::from_values(vec![Value::Binary(b"ef".to_vec())]))
+            .unwrap();
+        rows.add_row(Row::from_values(vec![Value::Null])).unwrap();
+
+        let batch = RecordBatch::try_from(rows).unwrap();
+        let column = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .unwrap();
+        assert_eq!(column.value(0), b"abcd");
+        assert_eq!(column.value(1), b"ef");
+        assert!(column.is_null(2));
+    }
+
     #[test]
     fn test_non_nullable_timestamp_field_with_null_should_error() {
         // Create schema with timestamp field (non-nullable)
@@ -1334,12 +3216,16 @@ mod tests {
                 data_type: ColumnDataType::TimestampMillisecond,
                 semantic_type: SemanticType::Timestamp,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "value".to_string(),
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -1369,12 +3255,16 @@ mod tests {
                 data_type: ColumnDataType::TimestampMillisecond,
                 semantic_type: SemanticType::Timestamp,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "value".to_string(),
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -1395,6 +3285,1299 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rows_timestamp_range_check() {
+        let schema = vec![
+            Column {
+                name: "ts".to_string(),
+                data_type: ColumnDataType::TimestampMillisecond,
+                semantic_type: SemanticType::Timestamp,
+                data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
+            },
+            Column {
+                name: "value".to_string(),
+                data_type: ColumnDataType::Int64,
+                semantic_type: SemanticType::Field,
+                data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
+            },
+        ];
+
+        let mut rows = Rows::new(&schema, 5, 5)
+            .expect("Failed to create rows")
+            .with_timestamp_range(TimestampRange::default());
+
+        // Seconds written into a millisecond column
+        let result = rows.add_row(Row::from_values(vec![
+            Value::TimestampMillisecond(1_700_000_000),
+            Value::Int64(1),
+        ]));
+        assert!(matches!(
+            result,
+            Err(crate::Error::ImplausibleTimestamp { .. })
+        ));
+        assert!(rows.is_empty());
+
+        rows.add_row(Row::from_values(vec![
+            Value::TimestampMillisecond(1_700_000_000_000),
+            Value::Int64(1),
+        ]))
+        .expect("Plausible timestamp should be accepted");
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_rows_add_record_batch() {
+        use arrow_array::{
+            BinaryArray, Date32Array, Decimal128Array, Float64Array, StringArray,
+            TimestampMillisecondArray, UInt32Array,
+        };
+
+        let schema = TableSchema::builder()
+            .name("batch")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String)
+            .add_field("count", ColumnDataType::Uint32)
+            .add_field("value", ColumnDataType::Float64)
+            .add_field("payload", ColumnDataType::Binary)
+            .add_field("day", ColumnDataType::Date)
+            .add_decimal128_field("price", 10, 2);
+        let mut rows = Rows::new(schema.columns(), 10, 10).unwrap();
+
+        let columns: Vec<Arc<dyn Array>> = vec![
+            Arc::new(TimestampMillisecondArray::from(vec![1_000, 2_000])),
+            Arc::new(StringArray::from(vec![Some("a"), None])),
+            Arc::new(UInt32Array::from(vec![1, 2])),
+            Arc::new(Float64Array::from(vec![0.5, 1.5])),
+            Arc::new(BinaryArray::from(vec![b"x".as_ref(), b"yz".as_ref()])),
+            Arc::new(Date32Array::from(vec![19_000, 19_001])),
+            Arc::new(
+                Decimal128Array::from(vec![1_234, -5])
+                    .with_precision_and_scale(10, 2)
+                    .unwrap(),
+            ),
+        ];
+        let batch = RecordBatch::try_new(Arc::new(rows.schema().clone()), columns).unwrap();
+
+        assert_eq!(rows.add_record_batch(&batch).unwrap(), 2);
+        assert_eq!(rows.len(), 2);
+
+        let first = &rows.row_buffer[0];
+        assert!(matches!(
+            first.value(0),
+            Some(Value::TimestampMillisecond(1_000))
+        ));
+        assert!(matches!(first.value(1), Some(Value::String(s)) if s == "a"));
+        assert!(matches!(first.value(2), Some(Value::Uint32(1))));
+        assert!(matches!(first.value(3), Some(Value::Float64(v)) if *v == 0.5));
+        assert!(matches!(first.value(4), Some(Value::Binary(b)) if b == b"x"));
+        assert!(matches!(first.value(5), Some(Value::Date(19_000))));
+        assert!(matches!(first.value(6), Some(Value::Decimal128(1_234))));
+        let second = &rows.row_buffer[1];
+        assert!(matches!(second.value(1), Some(Value::Null)));
+        assert!(matches!(second.value(6), Some(Value::Decimal128(-5))));
+
+        // Columns in a different order are rejected
+        let swapped = Schema::new(vec![
+            Field::new("value", DataType::Int64, true),
+            Field::new(
+                "ts",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(swapped),
+            vec![
+                Arc::new(Int64Array::from(vec![1])),
+                Arc::new(TimestampMillisecondArray::from(vec![1_000])),
+            ],
+        )
+        .unwrap();
+        let mut rows = Rows::new(stub_schema().columns(), 10, 10).unwrap();
+        assert!(matches!(
+            rows.add_record_batch(&batch),
+            Err(crate::Error::RecordBatchColumnMismatch { index: 0, .. })
+        ));
+        assert!(rows.is_empty());
+    }
+
+    fn stub_schema() -> TableSchema {
+        TableSchema::builder()
+            .name("stub")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("value", ColumnDataType::Int64)
+    }
+
+    /// Create a writer whose responses come from `responses` instead of a server.
+    /// The returned receiver yields the `FlightData` the writer sent.
+    pub(crate) fn stub_writer(
+        options: BulkWriteOptions,
+        responses: Vec<Result<DoPutResponse>>,
+    ) -> (BulkStreamWriter, mpsc::Receiver<FlightData>) {
+        stub_writer_for(&stub_schema(), options, responses)
+    }
+
+    /// Like [`stub_writer`], for a writer bound to `table_schema`
+    pub(crate) fn stub_writer_for(
+        table_schema: &TableSchema,
+        options: BulkWriteOptions,
+        responses: Vec<Result<DoPutResponse>>,
+    ) -> (BulkStreamWriter, mpsc::Receiver<FlightData>) {
+        let arrow_schema = table_arrow_schema(table_schema).unwrap();
+        let (sender, receiver) = mpsc::channel(64);
+        let response_stream: ResponseStream = Box::pin(futures::stream::iter(responses));
+        let writer = BulkStreamWriter::from_parts(
+            table_schema,
+            arrow_schema,
+            options,
+            sender,
+            response_stream,
+            None,
+        );
+        (writer, receiver)
+    }
+
+    fn stub_rows(writer: &BulkStreamWriter, count: usize) -> Rows {
+        let mut rows = writer.alloc_rows_buffer(count, count).unwrap();
+        for i in 0..count as i64 {
+            rows.add_row(Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000 + i),
+                Value::Int64(i),
+            ]))
+            .unwrap();
+        }
+        rows
+    }
+
+    /// A stub stream acknowledging every message it receives with its request id
+    fn echo_stream() -> (mpsc::Sender<FlightData>, ResponseStream) {
+        let (sender, receiver) = mpsc::channel::<FlightData>(64);
+        let responses = receiver.map(|data| {
+            let metadata: DoPutMetadata = serde_json::from_slice(&data.app_metadata).unwrap();
+            Ok(DoPutResponse::new(metadata.request_id(), 1))
+        });
+        (sender, Box::pin(responses))
+    }
+
+    #[tokio::test]
+    async fn test_stream_reset_interval_reopens_stream() {
+        let schema = stub_schema();
+        let opened = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let stream_opener: StreamOpener = {
+            let opened = opened.clone();
+            Box::new(move || {
+                opened.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                futures::future::ready(Ok(echo_stream())).boxed_local()
+            })
+        };
+        let (sender, response_stream) = echo_stream();
+        let mut writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            BulkWriteOptions::default().with_stream_reset_interval(StreamResetInterval::Batches(2)),
+            sender,
+            response_stream,
+            Some(stream_opener),
+        );
+
+        let mut acks = Vec::new();
+        for _ in 0..5 {
+            let rows = stub_rows(&writer, 1);
+            acks.push(writer.write_rows_async(rows).await.unwrap());
+        }
+        // Batches 1-2 go to the initial stream, 3-4 and 5 to two reopened ones
+        assert_eq!(opened.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(
+            acks.iter().map(Ack::request_id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+
+        let mut request_ids: Vec<RequestId> = writer
+            .finish_with_responses()
+            .await
+            .unwrap()
+            .iter()
+            .map(DoPutResponse::request_id)
+            .collect();
+        request_ids.sort_unstable();
+        assert_eq!(request_ids, vec![1, 2, 3, 4, 5]);
+        for ack in acks {
+            assert!(ack.await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_rows_bytes_and_affected_rows() {
+        let schema = stub_schema();
+        let (sender, response_stream) = echo_stream();
+        let mut writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            BulkWriteOptions::default().with_compression(CompressionType::None),
+            sender,
+            response_stream,
+            None,
+        );
+        assert_eq!(writer.stats(), BulkWriteStats::default());
+        assert_eq!(writer.stats().compression_ratio(), None);
+
+        for _ in 0..2 {
+            let rows = stub_rows(&writer, 3);
+            writer.write_rows(rows).await.unwrap();
+        }
+
+        let stats = writer.stats();
+        assert_eq!(stats.rows, 6);
+        assert_eq!(stats.batches, 2);
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.retries, 0);
+        // The echo stream reports one affected row per batch
+        assert_eq!(stats.affected_rows, 2);
+        // At least two columns of three 8-byte values per batch
+        assert!(stats.uncompressed_bytes >= 2 * 2 * 3 * 8);
+        assert!(stats.compressed_bytes > 0);
+        assert!(stats.compression_ratio().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_batching_follows_latency() {
+        // A stub stream acknowledging every message after the current delay
+        let delay_ms = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let (sender, receiver) = mpsc::channel::<FlightData>(64);
+        let responses = {
+            let delay_ms = delay_ms.clone();
+            receiver.then(move |data| {
+                let delay = delay_ms.load(std::sync::atomic::Ordering::SeqCst);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let metadata: DoPutMetadata =
+                        serde_json::from_slice(&data.app_metadata).unwrap();
+                    Ok(DoPutResponse::new(metadata.request_id(), 1))
+                }
+            })
+        };
+        let schema = stub_schema();
+        let mut writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            BulkWriteOptions::default()
+                .with_adaptive_batching(100, 200)
+                .with_batch_latency_target(Duration::from_millis(50)),
+            sender,
+            Box::pin(responses),
+            None,
+        );
+
+        // Fast acks grow the size by a quarter per batch up to the maximum
+        let mut sizes = vec![writer.recommended_batch_size().unwrap()];
+        for _ in 0..4 {
+            let rows = stub_rows(&writer, sizes.last().copied().unwrap());
+            writer.write_rows(rows).await.unwrap();
+            sizes.push(writer.recommended_batch_size().unwrap());
+        }
+        assert_eq!(sizes, [100, 125, 156, 195, 200]);
+
+        // Slow acks halve it down to the minimum
+        delay_ms.store(100, std::sync::atomic::Ordering::SeqCst);
+        for expected in [100, 100] {
+            let rows = stub_rows(&writer, 10);
+            writer.write_rows(rows).await.unwrap();
+            assert_eq!(writer.recommended_batch_size(), Some(expected));
+        }
+
+        let writer = stub_writer(BulkWriteOptions::default(), vec![]).0;
+        assert_eq!(writer.recommended_batch_size(), None);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_rejects_null_tag() {
+        let schema = TableSchema::builder()
+            .name("cpu")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String)
+            .add_field("usage", ColumnDataType::Float64);
+        let null_tag_rows = |writer: &BulkStreamWriter| {
+            let mut rows = writer.alloc_rows_buffer(2, 2).unwrap();
+            for host in [Value::String("a".to_string()), Value::Null] {
+                rows.add_row(Row::from_values(vec![
+                    Value::TimestampMillisecond(1_700_000_000_000),
+                    host,
+                    Value::Float64(0.5),
+                ]))
+                .unwrap();
+            }
+            rows
+        };
+
+        let options = BulkWriteOptions::default().with_write_mode(WriteMode::Upsert);
+        let (mut writer, _receiver) = stub_writer_for(&schema, options, vec![]);
+        let rows = null_tag_rows(&writer);
+        let error = writer.write_rows(rows).await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::MissingPrimaryKey { ref column, row: 1, .. } if column == "host"
+        ));
+
+        let options = BulkWriteOptions::default().with_write_mode(WriteMode::Append);
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 2))];
+        let (mut writer, _receiver) = stub_writer_for(&schema, options, responses);
+        let rows = null_tag_rows(&writer);
+        assert_eq!(writer.write_rows(rows).await.unwrap().affected_rows(), 2);
+    }
+
+    /// A writer whose initial stream acknowledges the schema and then fails with
+    /// `status`, reopening echo streams and counting them in `opened`
+    fn failing_stream_writer(
+        options: BulkWriteOptions,
+        status: tonic::Status,
+        opened: &Arc<std::sync::atomic::AtomicUsize>,
+    ) -> (BulkStreamWriter, mpsc::Receiver<FlightData>) {
+        let schema = stub_schema();
+        let stream_opener: StreamOpener = {
+            let opened = opened.clone();
+            Box::new(move || {
+                opened.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                futures::future::ready(Ok(echo_stream())).boxed_local()
+            })
+        };
+        let (sender, receiver) = mpsc::channel(64);
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Err(crate::Error::from(status)),
+        ];
+        let writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            options,
+            sender,
+            Box::pin(futures::stream::iter(responses)),
+            Some(stream_opener),
+        );
+        (writer, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_stream_is_retried() {
+        let opened = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let options = BulkWriteOptions::default()
+            .with_max_retries(2)
+            .with_retry_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let (mut writer, _receiver) = failing_stream_writer(
+            options,
+            tonic::Status::unavailable("server restarting"),
+            &opened,
+        );
+
+        let rows = stub_rows(&writer, 1);
+        let response = writer.write_rows(rows).await.unwrap();
+        assert_eq!(response.request_id(), 1);
+        assert_eq!(response.affected_rows(), 1);
+        assert_eq!(writer.metrics().retries, 1);
+        assert_eq!(opened.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_argument_is_not_retried() {
+        let opened = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let options = BulkWriteOptions::default()
+            .with_max_retries(2)
+            .with_retry_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let (mut writer, _receiver) = failing_stream_writer(
+            options,
+            tonic::Status::invalid_argument("schema mismatch"),
+            &opened,
+        );
+
+        let rows = stub_rows(&writer, 1);
+        let error = writer.write_rows(rows).await.unwrap_err();
+        assert!(matches!(error, Error::Server { .. }));
+        assert_eq!(writer.metrics().retries, 0);
+        assert_eq!(opened.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_on_error_fires_for_failed_batch() {
+        let failed = Arc::new(parking_lot::Mutex::new(Vec::<RequestId>::new()));
+        let on_error: OnErrorCallback = {
+            let failed = failed.clone();
+            Arc::new(move |request_id: RequestId, _error: &crate::Error| {
+                failed.lock().push(request_id)
+            })
+        };
+
+        let responses = vec![
+            // Schema acknowledgement
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 2)),
+            Err(crate::Error::from(tonic::Status::internal(
+                "region is read-only",
+            ))),
+        ];
+        let options = BulkWriteOptions::default().with_on_error(on_error);
+        let (mut writer, _receiver) = stub_writer(options, responses);
+
+        let first = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap()
+            .request_id();
+        let second = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap()
+            .request_id();
+        assert_eq!((first, second), (1, 2));
+        assert!(failed.lock().is_empty());
+
+        let result = writer.finish_with_responses().await;
+        assert!(matches!(result, Err(crate::Error::Server { .. })));
+        assert_eq!(*failed.lock(), vec![second]);
+    }
+
+    #[tokio::test]
+    async fn test_builder_options_are_inherited() {
+        let client = crate::Client::with_urls(["127.0.0.1:4001"]);
+        let inserter = BulkInserter::builder(client, "public")
+            .high_throughput()
+            .with_compression(CompressionType::Zstd)
+            .with_max_retries(2)
+            .build();
+        assert_eq!(inserter.default_options().parallelism, 16);
+
+        let (writer, _receiver) = stub_writer(inserter.write_options(None), vec![]);
+        assert_eq!(writer.compression(), CompressionType::Zstd);
+        assert_eq!(writer.parallelism(), 16);
+        assert_eq!(writer.max_retries, 2);
+
+        // Options passed for one writer replace the inherited ones
+        let options = BulkWriteOptions::default().with_parallelism(1);
+        let (writer, _receiver) = stub_writer(inserter.write_options(Some(options)), vec![]);
+        assert_eq!(writer.compression(), CompressionType::Lz4);
+        assert_eq!(writer.parallelism(), 1);
+
+        // Derived from the inherited ones, they keep what is not overridden
+        let options = inserter.default_options().clone().with_parallelism(1);
+        let (writer, _receiver) = stub_writer(inserter.write_options(Some(options)), vec![]);
+        assert_eq!(writer.compression(), CompressionType::Zstd);
+        assert_eq!(writer.parallelism(), 1);
+        assert_eq!(writer.max_retries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_stream() {
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 4)),
+            Ok(DoPutResponse::new(2, 4)),
+            Ok(DoPutResponse::new(3, 2)),
+        ];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let rows = futures::stream::iter(0..10).map(|i| {
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000 + i),
+                Value::Int64(i),
+            ])
+        });
+        let stats = writer.write_stream(rows, 4).await.unwrap();
+        assert_eq!(stats.rows, 10);
+        assert_eq!(stats.batches, 3);
+        assert_eq!(stats.affected_rows, 10);
+        assert_eq!(stats.in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_write_rows_async_is_bounded_by_max_in_flight() {
+        // Without a bound of its own, the parallelism bounds the batches in flight
+        let cases = [
+            (BulkWriteOptions::default().with_parallelism(3), 3),
+            (
+                BulkWriteOptions::default()
+                    .with_parallelism(8)
+                    .with_max_in_flight(2),
+                2,
+            ),
+        ];
+        for (options, bound) in cases {
+            // A transport acknowledging every message only after a delay
+            let (sender, receiver) = mpsc::channel::<FlightData>(64);
+            let responses = receiver.then(|data| async move {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                let metadata: DoPutMetadata = serde_json::from_slice(&data.app_metadata).unwrap();
+                Ok(DoPutResponse::new(metadata.request_id(), 1))
+            });
+            let schema = stub_schema();
+            let mut writer = BulkStreamWriter::from_parts(
+                &schema,
+                table_arrow_schema(&schema).unwrap(),
+                options,
+                sender,
+                Box::pin(responses),
+                None,
+            );
+            assert_eq!(writer.max_in_flight(), bound);
+
+            let mut max_in_flight = 0;
+            let mut acknowledged = 0;
+            for _ in 0..12 {
+                let rows = stub_rows(&writer, 2);
+                let _ack = writer.write_rows_async(rows).await.unwrap();
+                max_in_flight = max_in_flight.max(writer.stats().in_flight);
+                acknowledged += writer.flush_completed_responses().len();
+            }
+            assert_eq!(max_in_flight, bound);
+
+            acknowledged += writer.wait_for_all_pending().await.unwrap().len();
+            assert_eq!(acknowledged, 12);
+        }
+
+        // The bound never exceeds the parallelism
+        let options = BulkWriteOptions::default().with_parallelism(3);
+        assert_eq!(options.clone().with_max_in_flight(10).in_flight_limit(), 3);
+        assert_eq!(options.with_max_in_flight(0).in_flight_limit(), 1);
+    }
+
+    /// Counts the warnings emitted while it is the default subscriber
+    struct WarningCounter(Arc<AtomicU64>);
+
+    impl tracing::Subscriber for WarningCounter {
+        fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+            *metadata.level() == tracing::Level::WARN
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_slow_batch_is_logged() {
+        let warnings = Arc::new(AtomicU64::new(0));
+        let _guard = tracing::subscriber::set_default(WarningCounter(warnings.clone()));
+
+        // A transport answering at once, except for the second batch
+        let (sender, receiver) = mpsc::channel::<FlightData>(64);
+        let responses = receiver.then(|data| async move {
+            let metadata: DoPutMetadata = serde_json::from_slice(&data.app_metadata).unwrap();
+            if metadata.request_id() == 2 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Ok(DoPutResponse::new(metadata.request_id(), 1))
+        });
+        let schema = stub_schema();
+        let mut writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            BulkWriteOptions::default().with_slow_batch_threshold(Duration::from_millis(50)),
+            sender,
+            Box::pin(responses),
+            None,
+        );
+
+        for _ in 0..3 {
+            let rows = stub_rows(&writer, 2);
+            writer.write_rows(rows).await.unwrap();
+        }
+        assert_eq!(warnings.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_returns_unacknowledged_batches() {
+        // A server acknowledging the schema and the first batch, then hanging
+        let (sender, _receiver) = mpsc::channel::<FlightData>(64);
+        let responses = futures::stream::iter(vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 2)),
+        ])
+        .chain(futures::stream::pending());
+        let schema = stub_schema();
+        let mut writer = BulkStreamWriter::from_parts(
+            &schema,
+            table_arrow_schema(&schema).unwrap(),
+            BulkWriteOptions::default(),
+            sender,
+            Box::pin(responses),
+            None,
+        );
+
+        let mut acks = Vec::new();
+        for _ in 0..3 {
+            let rows = stub_rows(&writer, 2);
+            acks.push(writer.write_rows_async(rows).await.unwrap());
+        }
+
+        let started = Instant::now();
+        let summary = writer.cancel(Duration::from_millis(50)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let acknowledged: Vec<_> = summary
+            .acknowledged
+            .iter()
+            .map(DoPutResponse::request_id)
+            .collect();
+        assert_eq!(acknowledged, vec![1]);
+        assert_eq!(summary.unacknowledged, vec![2, 3]);
+
+        let mut acks = acks.into_iter();
+        assert_eq!(acks.next().unwrap().await.unwrap().affected_rows(), 2);
+        assert!(matches!(
+            acks.next().unwrap().await,
+            Err(crate::Error::AckDropped { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_stops_at_failed_batch() {
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Err(crate::Error::from(tonic::Status::internal(
+                "region is read-only",
+            ))),
+        ];
+        let options = BulkWriteOptions::default().with_parallelism(1);
+        let (mut writer, _receiver) = stub_writer(options, responses);
+
+        let taken = Arc::new(AtomicU64::new(0));
+        let rows = futures::stream::iter(0..100).map({
+            let taken = taken.clone();
+            move |i| {
+                taken.fetch_add(1, Ordering::Relaxed);
+                Row::from_values(vec![
+                    Value::TimestampMillisecond(1_700_000_000_000 + i),
+                    Value::Int64(i),
+                ])
+            }
+        });
+        let result = writer.write_stream(rows, 10).await;
+        assert!(matches!(result, Err(crate::Error::Server { .. })));
+        // The second batch waits for the first one, which failed
+        assert!(taken.load(Ordering::Relaxed) <= 20);
+    }
+
+    #[tokio::test]
+    async fn test_write_columns() {
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 3))];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let mut columns = ColumnarBatch::new();
+        columns
+            .add_i64_column(
+                "ts",
+                vec![1_700_000_000_000, 1_700_000_000_001, 1_700_000_000_002],
+                None,
+            )
+            .unwrap()
+            .add_i64_column("value", vec![1, 2, 3], Some(vec![true, false, true]))
+            .unwrap();
+        let response = writer.write_columns(columns).await.unwrap();
+        assert_eq!(response.affected_rows(), 3);
+        assert_eq!(writer.metrics().rows, 3);
+        assert_eq!(writer.metrics().batches, 1);
+
+        let error = writer
+            .write_columns(ColumnarBatch::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::EmptyRows { .. }));
+        assert_eq!(writer.metrics().batches, 1);
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_sees_every_batch() {
+        use arrow_flight::decode::FlightRecordBatchStream;
+
+        let observed = Arc::new(parking_lot::Mutex::new(Vec::<FlightData>::new()));
+        let interceptor: RequestInterceptor = {
+            let observed = observed.clone();
+            Arc::new(move |data: &mut FlightData| {
+                observed.lock().push(data.clone());
+                data.flight_descriptor = Some(FlightDescriptor::new_path(vec!["traced".into()]));
+                data.app_metadata = b"overwritten".to_vec().into();
+            })
+        };
+
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 1)),
+            Ok(DoPutResponse::new(2, 3)),
+        ];
+        let (writer, receiver) = stub_writer(BulkWriteOptions::default(), responses);
+        let mut writer = writer.with_interceptor(interceptor);
+
+        writer.write_rows(stub_rows(&writer, 1)).await.unwrap();
+        writer.write_rows(stub_rows(&writer, 3)).await.unwrap();
+        writer.finish().await.unwrap();
+
+        let request_id = |data: &FlightData| {
+            serde_json::from_slice::<DoPutMetadata>(&data.app_metadata)
+                .unwrap()
+                .request_id()
+        };
+        // The interceptor saw the request ids and rows of both batches
+        let observed = observed.lock().clone();
+        assert_eq!(
+            observed.iter().map(request_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        // The first message carries the schema
+        let sent: Vec<FlightData> = receiver.collect().await;
+        assert_eq!(sent.len(), 3);
+        let messages = std::iter::once(sent[0].clone()).chain(observed);
+        let batches: Vec<RecordBatch> =
+            FlightRecordBatchStream::new_from_flight_data(futures::stream::iter(messages).map(Ok))
+                .map(|batch| batch.unwrap())
+                .collect()
+                .await;
+        let sizes: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![1, 3]);
+
+        // The descriptor set by the interceptor is sent, the request ids are kept
+        let sent = &sent[1..];
+        assert_eq!(sent.iter().map(request_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(sent
+            .iter()
+            .all(|d| d.flight_descriptor.as_ref().unwrap().path == vec!["traced".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_failed_response_carries_table_name_and_error() {
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 2)),
+            Err(crate::Error::from(tonic::Status::internal(
+                "region is read-only",
+            ))),
+        ];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let first = writer.write_rows(stub_rows(&writer, 2)).await.unwrap();
+        assert!(first.is_success());
+        assert_eq!(first.table_name(), Some("stub"));
+        assert_eq!(first.affected_rows(), 2);
+
+        let second = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap()
+            .request_id();
+        assert!(writer.wait_for_response(second).await.is_err());
+
+        let failed = writer.take_failed_responses();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].request_id(), second);
+        assert_eq!(failed[0].table_name(), Some("stub"));
+        assert!(!failed[0].is_success());
+        let error = failed[0].error().unwrap();
+        assert_eq!(error.code(), tonic::Code::Internal);
+        assert!(error.message().contains("region is read-only"));
+        assert!(writer.take_failed_responses().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_responses_are_recorded_once_per_request() {
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Err(crate::Error::from(tonic::Status::internal("first"))),
+            Err(crate::Error::from(tonic::Status::internal("second"))),
+        ];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let first = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap()
+            .request_id();
+        let second = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap()
+            .request_id();
+        // Both errors fail both requests, which are still pending after the first one
+        assert!(writer.wait_for_response(first).await.is_err());
+        assert!(writer.wait_for_response(second).await.is_err());
+
+        let failed = writer.take_failed_responses();
+        assert_eq!(
+            failed.iter().map(|r| r.request_id()).collect::<Vec<_>>(),
+            vec![first, second]
+        );
+        assert!(failed
+            .iter()
+            .all(|r| r.error().unwrap().message().contains("second")));
+        assert!(writer.take_failed_responses().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_row_count() {
+        // The count catches up on the second query
+        let mut counts = vec![3, 5].into_iter();
+        verify_row_count_with("t", 5, 3, Duration::ZERO, || {
+            futures::future::ready(Ok(counts.next().unwrap()))
+        })
+        .await
+        .unwrap();
+
+        let result =
+            verify_row_count_with("t", 5, 3, Duration::ZERO, || futures::future::ready(Ok(3)))
+                .await;
+        assert!(matches!(
+            result,
+            Err(crate::Error::RowCountMismatch {
+                expected: 5,
+                actual: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_count_from_batches() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "count(*)",
+            DataType::Int64,
+            false,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![42]))]).unwrap();
+        assert_eq!(count_from_batches(&[batch]).unwrap(), 42);
+        assert!(count_from_batches(&[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ack_resolves_with_its_batch_response() {
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            // Acknowledged out of order
+            Ok(DoPutResponse::new(2, 3)),
+            Ok(DoPutResponse::new(1, 2)),
+            Err(crate::Error::from(tonic::Status::internal(
+                "region is read-only",
+            ))),
+        ];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let first = writer
+            .write_rows_async(stub_rows(&writer, 2))
+            .await
+            .unwrap();
+        let second = writer
+            .write_rows_async(stub_rows(&writer, 3))
+            .await
+            .unwrap();
+        let third = writer
+            .write_rows_async(stub_rows(&writer, 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            (first.request_id(), second.request_id(), third.request_id()),
+            (1, 2, 3)
+        );
+
+        // Await the first ack on another task while this one drives the writer
+        let first = tokio::spawn(first);
+        assert!(writer.wait_for_all_pending().await.is_err());
+
+        let first = first.await.unwrap().unwrap();
+        assert_eq!(first.request_id(), 1);
+        assert_eq!(first.affected_rows(), 2);
+        assert_eq!(first.table_name(), Some("stub"));
+
+        let second = second.await.unwrap();
+        assert_eq!(second.request_id(), 2);
+        assert_eq!(second.affected_rows(), 3);
+
+        let third = third.await.unwrap();
+        assert_eq!(third.request_id(), 3);
+        assert!(!third.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_ack_fails_when_stream_ends() {
+        let responses = vec![Ok(DoPutResponse::new(0, 0))];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let ack = writer
+            .write_rows_async(stub_rows(&writer, 1))
+            .await
+            .unwrap();
+        assert!(writer.wait_for_all_pending().await.is_err());
+        assert!(matches!(ack.await, Err(crate::Error::AckDropped { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_validation_off_skips_checks() {
+        // A buffer whose `value` column is Int32 instead of the writer's Int64
+        let mismatched = TableSchema::builder()
+            .name("stub")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("value", ColumnDataType::Int32);
+        let mismatched_rows = || {
+            let mut rows = Rows::new(mismatched.columns(), 1, 1).unwrap();
+            rows.add_row(Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000),
+                Value::Int32(1),
+            ]))
+            .unwrap();
+            rows
+        };
+        // Seconds written into a millisecond column
+        let implausible_row = || {
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000),
+                Value::Int64(1),
+            ])
+        };
+
+        let options = BulkWriteOptions::default().with_timestamp_range(TimestampRange::default());
+        let responses = vec![Ok(DoPutResponse::new(0, 0))];
+        let (mut writer, _receiver) = stub_writer(options.clone(), responses);
+        let mut rows = writer.alloc_rows_buffer(1, 1).unwrap();
+        assert!(rows.add_row(implausible_row()).is_err());
+        assert!(matches!(
+            writer.write_rows_async(mismatched_rows()).await,
+            Err(crate::Error::SchemaMismatch { .. })
+        ));
+
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 1))];
+        let (mut writer, _receiver) = stub_writer(options.with_validation(false), responses);
+        let mut rows = writer.alloc_rows_buffer(1, 1).unwrap();
+        assert!(rows.add_row(implausible_row()).is_ok());
+        let ack = writer.write_rows_async(mismatched_rows()).await.unwrap();
+        assert_eq!(ack.request_id(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_spilled_rows() {
+        use arrow_flight::decode::FlightRecordBatchStream;
+
+        let temp_dir =
+            std::env::temp_dir().join(format!("greptimedb-ingester-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let spill_files = || std::fs::read_dir(&temp_dir).unwrap().count();
+
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 3)),
+            Ok(DoPutResponse::new(2, 3)),
+            Ok(DoPutResponse::new(3, 3)),
+            Ok(DoPutResponse::new(4, 1)),
+        ];
+        // Rows of a timestamp and an i64 are about 16 bytes, so every third row spills
+        let options = BulkWriteOptions::default().with_spill(40, &temp_dir);
+        let (mut writer, receiver) = stub_writer(options, responses);
+
+        let mut rows = writer.alloc_rows_buffer(10, 2).unwrap();
+        for i in 0..10 {
+            rows.add_row(Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000 + i),
+                Value::Int64(i),
+            ]))
+            .unwrap();
+        }
+        assert_eq!(rows.len(), 10);
+        assert_eq!(spill_files(), 1);
+
+        let ack = writer.write_rows_async(rows).await.unwrap();
+        assert_eq!(ack.request_id(), 4);
+        assert_eq!(ack.request_ids(), vec![1, 2, 3, 4]);
+        assert_eq!(spill_files(), 0);
+        writer.finish().await.unwrap();
+        // The ack covers the rows of every part
+        assert_eq!(ack.await.unwrap().affected_rows(), 10);
+
+        let batches: Vec<RecordBatch> =
+            FlightRecordBatchStream::new_from_flight_data(receiver.map(Ok))
+                .map(|batch| batch.unwrap())
+                .collect()
+                .await;
+        let sizes: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![3, 3, 3, 1]);
+        let values: Vec<i64> = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(1)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+        // Converting a spilled buffer directly reads all of it back
+        let mut rows = Rows::new(stub_schema().columns(), 10, 2)
+            .unwrap()
+            .with_spill(40, &temp_dir);
+        for i in 0..5 {
+            rows.add_row(Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000 + i),
+                Value::Int64(i),
+            ]))
+            .unwrap();
+        }
+        let batch = RecordBatch::try_from(rows).unwrap();
+        assert_eq!(batch.num_rows(), 5);
+        assert_eq!(spill_files(), 0);
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spilled_write_reports_failure_of_any_part() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("greptimedb-ingester-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let spilled_rows = |writer: &BulkStreamWriter| {
+            let mut rows = writer.alloc_rows_buffer(10, 2).unwrap();
+            for i in 0..10 {
+                rows.add_row(Row::from_values(vec![
+                    Value::TimestampMillisecond(1_700_000_000_000 + i),
+                    Value::Int64(i),
+                ]))
+                .unwrap();
+            }
+            rows
+        };
+        let options = BulkWriteOptions::default().with_spill(40, &temp_dir);
+
+        // The second of four parts fails, the ack resolves to its response
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 3)),
+            Err(crate::Error::from(tonic::Status::internal("part failed"))),
+        ];
+        let (mut writer, _receiver) = stub_writer(options.clone(), responses);
+        let ack = writer
+            .write_rows_async(spilled_rows(&writer))
+            .await
+            .unwrap();
+        assert_eq!(ack.request_ids(), vec![1, 2, 3, 4]);
+        assert!(writer.wait_for_all_pending().await.is_err());
+        let response = ack.await.unwrap();
+        assert_eq!(response.request_id(), 2);
+        assert!(!response.is_success());
+
+        // write_rows waits for every part rather than only the last one
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 3)),
+            Err(crate::Error::from(tonic::Status::internal("part failed"))),
+        ];
+        let (mut writer, _receiver) = stub_writer(options, responses);
+        let result = writer.write_rows(spilled_rows(&writer)).await;
+        assert!(matches!(result, Err(Error::Server { .. })));
+        let failed = writer.take_failed_responses();
+        assert_eq!(
+            failed.iter().map(|r| r.request_id()).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_collected_puts_back_unsubmitted_rows() {
+        let collector = SharedRowCollector::new();
+        for i in 0..10 {
+            // The sixth row holds seconds instead of milliseconds
+            let ts = if i == 5 {
+                1_700_000_000
+            } else {
+                1_700_000_000_000 + i
+            };
+            collector.push(Row::from_values(vec![
+                Value::TimestampMillisecond(ts),
+                Value::Int64(i),
+            ]));
+        }
+
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 3))];
+        let options = BulkWriteOptions::default().with_timestamp_range(TimestampRange::default());
+        let (mut writer, _receiver) = stub_writer(options, responses);
+
+        let result = writer.write_collected(&collector, 3).await;
+        assert!(matches!(result, Err(Error::ImplausibleTimestamp { .. })));
+        // The first batch was submitted, the rejected row is dropped and the others
+        // wait for the next call in their original order
+        let left: Vec<_> = collector
+            .take()
+            .iter()
+            .map(|row| row.get_i64(1).unwrap())
+            .collect();
+        assert_eq!(left, vec![3, 4, 6, 7, 8, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_write_collected_from_multiple_threads() {
+        use arrow_flight::decode::FlightRecordBatchStream;
+
+        let collector = SharedRowCollector::new();
+        let producers: Vec<_> = (0..4_i64)
+            .map(|thread| {
+                let collector = collector.clone();
+                std::thread::spawn(move || {
+                    for i in 0..250 {
+                        collector.push(Row::from_values(vec![
+                            Value::TimestampMillisecond(1_700_000_000_000 + thread * 1000 + i),
+                            Value::Int64(i),
+                        ]));
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(collector.len(), 1000);
+
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 300)),
+            Ok(DoPutResponse::new(2, 300)),
+            Ok(DoPutResponse::new(3, 300)),
+            Ok(DoPutResponse::new(4, 100)),
+        ];
+        let (mut writer, receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let request_ids = writer.write_collected(&collector, 300).await.unwrap();
+        assert_eq!(request_ids, vec![1, 2, 3, 4]);
+        assert!(collector.is_empty());
+        writer.finish().await.unwrap();
+
+        let delivered: usize = FlightRecordBatchStream::new_from_flight_data(receiver.map(Ok))
+            .map(|batch| batch.unwrap().num_rows())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .sum();
+        assert_eq!(delivered, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_split_buffering_and_network_time() {
+        let responses = (0..=3).map(|id| Ok(DoPutResponse::new(id, 2))).collect();
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        for _ in 0..3 {
+            // An artificially slow generator
+            std::thread::sleep(Duration::from_millis(20));
+            let rows = stub_rows(&writer, 2);
+            let _ack = writer.write_rows_async(rows).await.unwrap();
+        }
+        writer.wait_for_all_pending().await.unwrap();
+
+        let metrics = writer.metrics();
+        assert_eq!(metrics.batches, 3);
+        assert_eq!(metrics.rows, 6);
+        assert!(metrics.buffering_time >= Duration::from_millis(60));
+        assert!(metrics.buffering_time > metrics.network_time);
+    }
+
+    #[tokio::test]
+    async fn test_time_index_checked_before_connecting() {
+        // No peers, so any network I/O would fail with a different error
+        let inserter = BulkInserter::new(Client::new(), "public");
+        let schema = stub_schema().add_timestamp("ingested_at", ColumnDataType::TimestampSecond);
+
+        let result = inserter.create_bulk_stream_writer(&schema, None).await;
+        let Err(crate::Error::InvalidTimeIndex { columns, .. }) = result else {
+            panic!("expected InvalidTimeIndex");
+        };
+        assert_eq!(columns, ["ts", "ingested_at"]);
+    }
+
+    #[test]
+    fn test_row_builder_for_schema() {
+        let schema = stub_schema().add_tag("host", ColumnDataType::String);
+
+        // Columns are addressed by name, in any order, and unset ones become null
+        let row = RowBuilder::for_schema(&schema)
+            .set("host", Value::from("web-1"))
+            .unwrap()
+            .set("ts", Value::TimestampMillisecond(1_700_000_000_000))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(matches!(
+            row.value(0),
+            Some(Value::TimestampMillisecond(1_700_000_000_000))
+        ));
+        assert!(matches!(row.value(1), Some(Value::Null)));
+        assert_eq!(row.get_string(2), Some("web-1".to_string()));
+
+        // The timestamp column cannot be left unset
+        let result = RowBuilder::for_schema(&schema)
+            .set("value", Value::Int64(1))
+            .unwrap()
+            .build();
+        assert!(matches!(result, Err(crate::Error::MissingField { field, .. }) if field == "ts"));
+
+        let result = RowBuilder::for_schema(&schema)
+            .set("value", Value::Int64(1))
+            .and_then(|builder| builder.set("value", Value::Int64(2)));
+        assert!(matches!(
+            result,
+            Err(crate::Error::ColumnAlreadySet { column, .. }) if column == "value"
+        ));
+
+        let result = RowBuilder::for_schema(&schema).set("missing", Value::Int64(1));
+        assert!(matches!(result, Err(crate::Error::MissingField { .. })));
+    }
+
+    #[test]
+    fn test_row_builder_push_rejects_extra_values() {
+        let (writer, _receiver) = stub_writer(BulkWriteOptions::default(), vec![]);
+
+        let row = writer
+            .new_row()
+            .push(Value::TimestampMillisecond(1_700_000_000_000))
+            .unwrap()
+            .push(Value::Int64(1))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(row.len(), 2);
+
+        let result = writer
+            .new_row()
+            .push(Value::TimestampMillisecond(1_700_000_000_000))
+            .and_then(|builder| builder.push(Value::Int64(1)))
+            .and_then(|builder| builder.push(Value::Int64(2)));
+        assert!(matches!(
+            result,
+            Err(crate::Error::TooManyValues {
+                column_count: 2,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_arrow_schema_nullable_fields() {
         use arrow_schema::{DataType, Field};
@@ -1406,18 +4589,24 @@ mod tests {
                 data_type: ColumnDataType::TimestampMillisecond,
                 semantic_type: SemanticType::Timestamp,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "value".to_string(),
                 data_type: ColumnDataType::Int64,
                 semantic_type: SemanticType::Field,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
             Column {
                 name: "tag".to_string(),
                 data_type: ColumnDataType::String,
                 semantic_type: SemanticType::Tag,
                 data_type_extension: None,
+                is_primary_value: false,
+                options: ColumnOptions::default(),
             },
         ];
 
@@ -0,0 +1,368 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Column-oriented input for the bulk writer
+//!
+//! Data that is already columnar, such as `Vec<i64>` and `Vec<String>` from a query
+//! engine or a decoder, can be handed to the writer as whole columns. Each column
+//! becomes an Arrow array without going through a [`Value`](crate::Value) per cell,
+//! and Arrow Flight sends record batches column by column, so nothing is
+//! transposed on the way.
+
+use std::sync::Arc;
+
+use arrow::buffer::{BooleanBuffer, NullBuffer, ScalarBuffer};
+use arrow_array::{
+    new_null_array, ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray,
+};
+use arrow_schema::{DataType, Schema, TimeUnit};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::table::TableSchema;
+use crate::{error, Result};
+
+/// Values of one column, in the order they were added
+#[derive(Debug, Clone)]
+enum ColumnData {
+    Int64(Vec<i64>),
+    Float64(Vec<f64>),
+    Boolean(Vec<bool>),
+    String(Vec<String>),
+}
+
+impl ColumnData {
+    fn len(&self) -> usize {
+        match self {
+            ColumnData::Int64(values) => values.len(),
+            ColumnData::Float64(values) => values.len(),
+            ColumnData::Boolean(values) => values.len(),
+            ColumnData::String(values) => values.len(),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            ColumnData::Int64(_) => "Int64 column",
+            ColumnData::Float64(_) => "Float64 column",
+            ColumnData::Boolean(_) => "Boolean column",
+            ColumnData::String(_) => "String column",
+        }
+    }
+
+    /// Build the array for a column of type `data_type`, or `None` if the values
+    /// cannot be stored in it
+    fn into_array(self, data_type: &DataType, nulls: Option<NullBuffer>) -> Option<ArrayRef> {
+        Some(match (self, data_type) {
+            (ColumnData::Int64(values), DataType::Int64) => {
+                Arc::new(Int64Array::new(ScalarBuffer::from(values), nulls))
+            }
+            (ColumnData::Int64(values), DataType::Timestamp(unit, _)) => {
+                let values = ScalarBuffer::from(values);
+                match unit {
+                    TimeUnit::Second => Arc::new(TimestampSecondArray::new(values, nulls)),
+                    TimeUnit::Millisecond => {
+                        Arc::new(TimestampMillisecondArray::new(values, nulls))
+                    }
+                    TimeUnit::Microsecond => {
+                        Arc::new(TimestampMicrosecondArray::new(values, nulls))
+                    }
+                    TimeUnit::Nanosecond => Arc::new(TimestampNanosecondArray::new(values, nulls)),
+                }
+            }
+            (ColumnData::Float64(values), DataType::Float64) => {
+                Arc::new(Float64Array::new(ScalarBuffer::from(values), nulls))
+            }
+            (ColumnData::Boolean(values), DataType::Boolean) => {
+                Arc::new(BooleanArray::new(BooleanBuffer::from(values), nulls))
+            }
+            (ColumnData::String(values), DataType::Utf8) => {
+                let (offsets, values, _) = StringArray::from_iter_values(values).into_parts();
+                Arc::new(StringArray::new(offsets, values, nulls))
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// A batch of rows given as whole columns, see [`BulkStreamWriter::write_columns`]
+///
+/// Columns are matched to the writer's table schema by name and may be added in
+/// any order; columns left out are written as nulls. An `Int64` column can also
+/// fill a timestamp column, its values taken in the unit of that column.
+///
+/// Every column takes an optional validity bitmap parallel to its values, where
+/// `false` marks a null row; the value at a null row is ignored. All columns and
+/// bitmaps must have the same length.
+///
+/// [`BulkStreamWriter::write_columns`]: super::BulkStreamWriter::write_columns
+#[derive(Debug, Clone, Default)]
+pub struct ColumnarBatch {
+    columns: Vec<(String, ColumnData, Option<Vec<bool>>)>,
+}
+
+impl ColumnarBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an `Int64` column, or the values of a timestamp column
+    pub fn add_i64_column(
+        &mut self,
+        name: impl Into<String>,
+        values: Vec<i64>,
+        validity: Option<Vec<bool>>,
+    ) -> Result<&mut Self> {
+        self.add_column(name.into(), ColumnData::Int64(values), validity)
+    }
+
+    /// Add a `Float64` column
+    pub fn add_f64_column(
+        &mut self,
+        name: impl Into<String>,
+        values: Vec<f64>,
+        validity: Option<Vec<bool>>,
+    ) -> Result<&mut Self> {
+        self.add_column(name.into(), ColumnData::Float64(values), validity)
+    }
+
+    /// Add a `Boolean` column
+    pub fn add_bool_column(
+        &mut self,
+        name: impl Into<String>,
+        values: Vec<bool>,
+        validity: Option<Vec<bool>>,
+    ) -> Result<&mut Self> {
+        self.add_column(name.into(), ColumnData::Boolean(values), validity)
+    }
+
+    /// Add a `String` column
+    pub fn add_string_column(
+        &mut self,
+        name: impl Into<String>,
+        values: Vec<String>,
+        validity: Option<Vec<bool>>,
+    ) -> Result<&mut Self> {
+        self.add_column(name.into(), ColumnData::String(values), validity)
+    }
+
+    /// Number of rows, zero until a column is added
+    pub fn len(&self) -> usize {
+        self.columns
+            .first()
+            .map_or(0, |(_, values, _)| values.len())
+    }
+
+    /// Check if the batch has no rows
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn add_column(
+        &mut self,
+        name: String,
+        values: ColumnData,
+        validity: Option<Vec<bool>>,
+    ) -> Result<&mut Self> {
+        ensure!(
+            !self
+                .columns
+                .iter()
+                .any(|(existing, _, _)| *existing == name),
+            error::DuplicateColumnSnafu { column: &name }
+        );
+        let expected = if self.columns.is_empty() {
+            values.len()
+        } else {
+            self.len()
+        };
+        for actual in std::iter::once(values.len()).chain(validity.as_ref().map(Vec::len)) {
+            ensure!(
+                actual == expected,
+                error::ColumnLengthMismatchSnafu {
+                    column: &name,
+                    expected,
+                    actual,
+                }
+            );
+        }
+        self.columns.push((name, values, validity));
+        Ok(self)
+    }
+
+    /// Build a record batch of `arrow_schema`, the Arrow schema of `table_schema`
+    ///
+    /// Fails on a column that is not in `table_schema` or whose values cannot be
+    /// stored in it, and on a missing column that is not nullable.
+    pub(crate) fn into_record_batch(
+        self,
+        table_schema: &TableSchema,
+        arrow_schema: &Arc<Schema>,
+    ) -> Result<RecordBatch> {
+        ensure!(!self.is_empty(), error::EmptyRowsSnafu);
+        let len = self.len();
+
+        let mut arrays: Vec<Option<ArrayRef>> = vec![None; arrow_schema.fields().len()];
+        for (name, values, validity) in self.columns {
+            let index = table_schema
+                .column_index(&name)
+                .context(error::UnknownColumnSnafu { column: &name })?;
+            let actual = values.type_name();
+            let array = values
+                .into_array(
+                    arrow_schema.field(index).data_type(),
+                    validity.map(NullBuffer::from),
+                )
+                .with_context(|| error::InvalidColumnValueSnafu {
+                    column: &name,
+                    expected: table_schema.columns()[index].data_type,
+                    actual,
+                })?;
+            arrays[index] = Some(array);
+        }
+
+        let arrays = arrays
+            .into_iter()
+            .zip(arrow_schema.fields())
+            .map(|(array, field)| array.unwrap_or_else(|| new_null_array(field.data_type(), len)))
+            .collect();
+        RecordBatch::try_new(arrow_schema.clone(), arrays).context(error::CreateRecordBatchSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::v1::ColumnDataType;
+    use crate::bulk::{table_arrow_schema, Rows};
+    use crate::table::{Row, Value};
+
+    fn schema() -> TableSchema {
+        TableSchema::builder()
+            .name("readings")
+            .build()
+            .unwrap()
+            .add_tag("sensor", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("value", ColumnDataType::Float64)
+            .add_field("count", ColumnDataType::Int64)
+            .add_field("ok", ColumnDataType::Boolean)
+    }
+
+    #[test]
+    fn test_columnar_batch_matches_rows() {
+        const ROWS: usize = 10_000;
+        let schema = schema();
+        let arrow_schema = table_arrow_schema(&schema).unwrap();
+
+        let sensors: Vec<String> = (0..ROWS).map(|i| format!("sensor-{}", i % 16)).collect();
+        let timestamps: Vec<i64> = (0..ROWS as i64).map(|i| 1_700_000_000_000 + i).collect();
+        let values: Vec<f64> = (0..ROWS).map(|i| i as f64 * 0.5).collect();
+        let counts: Vec<i64> = (0..ROWS as i64).collect();
+        let count_validity: Vec<bool> = (0..ROWS).map(|i| i % 10 != 0).collect();
+
+        let mut rows = Rows::new(schema.columns(), ROWS, 1024).unwrap();
+        for i in 0..ROWS {
+            rows.add_row(Row::from_values(vec![
+                Value::String(sensors[i].clone()),
+                Value::TimestampMillisecond(timestamps[i]),
+                Value::Float64(values[i]),
+                if count_validity[i] {
+                    Value::Int64(counts[i])
+                } else {
+                    Value::Null
+                },
+                Value::Null,
+            ]))
+            .unwrap();
+        }
+        let from_rows = RecordBatch::try_from(rows).unwrap();
+
+        let mut columns = ColumnarBatch::new();
+        columns
+            .add_i64_column("count", counts, Some(count_validity))
+            .unwrap()
+            .add_string_column("sensor", sensors, None)
+            .unwrap()
+            .add_i64_column("ts", timestamps, None)
+            .unwrap()
+            .add_f64_column("value", values, None)
+            .unwrap();
+        assert_eq!(columns.len(), ROWS);
+        let from_columns = columns.into_record_batch(&schema, &arrow_schema).unwrap();
+
+        assert_eq!(from_columns.num_rows(), from_rows.num_rows());
+        assert_eq!(from_columns, from_rows);
+    }
+
+    #[test]
+    fn test_columnar_batch_validation() {
+        let schema = schema();
+        let arrow_schema = table_arrow_schema(&schema).unwrap();
+
+        let mut columns = ColumnarBatch::new();
+        columns.add_i64_column("ts", vec![1, 2, 3], None).unwrap();
+        assert!(matches!(
+            columns.add_f64_column("value", vec![1.0, 2.0], None),
+            Err(crate::Error::ColumnLengthMismatch {
+                expected: 3,
+                actual: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            columns.add_f64_column("value", vec![1.0; 3], Some(vec![true])),
+            Err(crate::Error::ColumnLengthMismatch { actual: 1, .. })
+        ));
+        assert!(matches!(
+            columns.add_i64_column("ts", vec![1, 2, 3], None),
+            Err(crate::Error::DuplicateColumn { .. })
+        ));
+
+        let mut unknown = columns.clone();
+        unknown
+            .add_bool_column("flag", vec![true; 3], None)
+            .unwrap();
+        assert!(matches!(
+            unknown.into_record_batch(&schema, &arrow_schema),
+            Err(crate::Error::UnknownColumn { .. })
+        ));
+
+        let mut mistyped = columns.clone();
+        mistyped
+            .add_string_column("count", vec!["1".to_string(); 3], None)
+            .unwrap();
+        assert!(matches!(
+            mistyped.into_record_batch(&schema, &arrow_schema),
+            Err(crate::Error::InvalidColumnValue { .. })
+        ));
+
+        // The timestamp column is not nullable
+        let mut no_timestamp = ColumnarBatch::new();
+        no_timestamp
+            .add_f64_column("value", vec![1.0], None)
+            .unwrap();
+        assert!(matches!(
+            no_timestamp.into_record_batch(&schema, &arrow_schema),
+            Err(crate::Error::CreateRecordBatch { .. })
+        ));
+
+        assert!(matches!(
+            ColumnarBatch::new().into_record_batch(&schema, &arrow_schema),
+            Err(crate::Error::EmptyRows { .. })
+        ));
+    }
+}
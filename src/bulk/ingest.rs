@@ -0,0 +1,837 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! File ingestion on top of the bulk writer
+//!
+//! CSV and JSON Lines files are decoded straight into Arrow record batches of the
+//! writer's schema and streamed batch by batch, so a file is never materialized
+//! in memory as a whole.
+//!
+//! Files compressed at rest are decompressed on the fly, see [`FileCompression`]:
+//! gzip is always supported, zstd with the `zstd` feature.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::csv::ReaderBuilder as CsvReaderBuilder;
+use arrow::json::ReaderBuilder as JsonReaderBuilder;
+use arrow_array::builder::BinaryBuilder;
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use flate2::read::MultiGzDecoder;
+use snafu::{ensure, ResultExt};
+
+use super::{BulkInserter, BulkStreamWriter, BulkWriteOptions};
+use crate::table::{BinaryEncoding, TableSchema};
+use crate::{error, Result};
+
+/// Default number of records decoded into one batch
+const DEFAULT_INGEST_BATCH_SIZE: usize = 8192;
+
+/// Compression of an ingested file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileCompression {
+    /// Detect the compression from the file extension: `.gz` for gzip, `.zst` for
+    /// zstd, uncompressed otherwise
+    #[default]
+    Auto,
+    /// The file is not compressed
+    None,
+    /// The file is compressed with gzip; concatenated gzip members are read as one
+    Gzip,
+    /// The file is compressed with zstd
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl FileCompression {
+    /// Resolve [`FileCompression::Auto`] by the extension of `path`
+    fn resolve(self, path: &Path) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Self::Gzip,
+            #[cfg(feature = "zstd")]
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+}
+
+/// How an empty string read into a string column is stored
+///
+/// Files often write a missing value as an empty string, which is ambiguous with a
+/// value that is really empty. CSV cannot tell them apart at all: an empty field is
+/// read as [`EmptyStringPolicy::AsNull`] unless configured otherwise. JSON has its
+/// own `null`, so `""` is kept by default there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyStringPolicy {
+    /// Store an empty string; in CSV files, every empty field becomes `""`
+    AsEmpty,
+    /// Store null; fails with `NullInNonNullableColumn` on a non-nullable column
+    AsNull,
+}
+
+/// Options for reading CSV files
+///
+/// Columns are matched to the table schema by position.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub has_header: bool,
+    pub delimiter: u8,
+    pub batch_size: usize,
+    /// Encoding of binary columns by column name, raw bytes if absent
+    pub binary_encodings: HashMap<String, BinaryEncoding>,
+    /// Compression of the file, detected from its extension by default
+    pub compression: FileCompression,
+    /// Treatment of empty fields in string columns, null by default
+    pub empty_strings: EmptyStringPolicy,
+    /// Treatment of empty fields by column name, overriding `empty_strings`
+    pub column_empty_strings: HashMap<String, EmptyStringPolicy>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            has_header: true,
+            delimiter: b',',
+            batch_size: DEFAULT_INGEST_BATCH_SIZE,
+            binary_encodings: HashMap::new(),
+            compression: FileCompression::default(),
+            empty_strings: EmptyStringPolicy::AsNull,
+            column_empty_strings: HashMap::new(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Set whether the first line is a header to skip
+    #[must_use]
+    pub fn with_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Set the field delimiter
+    #[must_use]
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set the number of records sent per batch
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set how the binary column `column` is encoded in the file
+    #[must_use]
+    pub fn with_binary_encoding(
+        mut self,
+        column: impl Into<String>,
+        encoding: BinaryEncoding,
+    ) -> Self {
+        self.binary_encodings.insert(column.into(), encoding);
+        self
+    }
+
+    /// Set the compression of the file instead of detecting it from the extension
+    #[must_use]
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set how empty fields of all string columns are stored
+    #[must_use]
+    pub fn with_empty_strings(mut self, policy: EmptyStringPolicy) -> Self {
+        self.empty_strings = policy;
+        self
+    }
+
+    /// Set how empty fields of the string column `column` are stored
+    #[must_use]
+    pub fn with_column_empty_strings(
+        mut self,
+        column: impl Into<String>,
+        policy: EmptyStringPolicy,
+    ) -> Self {
+        self.column_empty_strings.insert(column.into(), policy);
+        self
+    }
+}
+
+/// Options for reading JSON Lines files
+///
+/// Object keys are matched to the table schema by column name.
+#[derive(Debug, Clone)]
+pub struct JsonlOptions {
+    pub batch_size: usize,
+    /// Encoding of binary columns by column name, raw bytes if absent
+    pub binary_encodings: HashMap<String, BinaryEncoding>,
+    /// Compression of the file, detected from its extension by default
+    pub compression: FileCompression,
+    /// Treatment of `""` in string columns, kept as an empty string by default
+    pub empty_strings: EmptyStringPolicy,
+    /// Treatment of `""` by column name, overriding `empty_strings`
+    pub column_empty_strings: HashMap<String, EmptyStringPolicy>,
+}
+
+impl Default for JsonlOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_INGEST_BATCH_SIZE,
+            binary_encodings: HashMap::new(),
+            compression: FileCompression::default(),
+            empty_strings: EmptyStringPolicy::AsEmpty,
+            column_empty_strings: HashMap::new(),
+        }
+    }
+}
+
+impl JsonlOptions {
+    /// Set the number of records sent per batch
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set how the binary column `column` is encoded in the file
+    #[must_use]
+    pub fn with_binary_encoding(
+        mut self,
+        column: impl Into<String>,
+        encoding: BinaryEncoding,
+    ) -> Self {
+        self.binary_encodings.insert(column.into(), encoding);
+        self
+    }
+
+    /// Set the compression of the file instead of detecting it from the extension
+    #[must_use]
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set how `""` in all string columns is stored
+    #[must_use]
+    pub fn with_empty_strings(mut self, policy: EmptyStringPolicy) -> Self {
+        self.empty_strings = policy;
+        self
+    }
+
+    /// Set how `""` in the string column `column` is stored
+    #[must_use]
+    pub fn with_column_empty_strings(
+        mut self,
+        column: impl Into<String>,
+        policy: EmptyStringPolicy,
+    ) -> Self {
+        self.column_empty_strings.insert(column.into(), policy);
+        self
+    }
+}
+
+/// Summary of a finished file ingestion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FinishReport {
+    /// Number of batches sent
+    pub batches: usize,
+    /// Number of records read from the file
+    pub rows: usize,
+    /// Number of rows the server reported as written
+    pub affected_rows: usize,
+}
+
+impl BulkInserter {
+    /// Stream a CSV file into `table_schema`'s table and wait for all batches.
+    ///
+    /// A compressed file is decompressed while reading, see [`CsvOptions::compression`].
+    /// Reading the file is blocking I/O, so prefer calling this from a task that
+    /// is allowed to block. Only column types the Arrow CSV reader can parse are
+    /// supported, which excludes JSON columns and binary columns without a
+    /// [`BinaryEncoding`].
+    pub async fn ingest_csv(
+        &self,
+        path: impl AsRef<Path>,
+        table_schema: &TableSchema,
+        csv_options: CsvOptions,
+        options: BulkWriteOptions,
+    ) -> Result<FinishReport> {
+        let file = open_source(path.as_ref(), csv_options.compression)?;
+        let mut writer = self
+            .create_bulk_stream_writer(table_schema, Some(options))
+            .await?;
+        let report = writer.write_csv(file, &csv_options).await?;
+        writer.finish_with_report(report).await
+    }
+
+    /// Stream a JSON Lines file into `table_schema`'s table and wait for all batches.
+    ///
+    /// A compressed file is decompressed while reading, see [`JsonlOptions::compression`].
+    /// Reading the file is blocking I/O, so prefer calling this from a task that
+    /// is allowed to block.
+    pub async fn ingest_jsonl(
+        &self,
+        path: impl AsRef<Path>,
+        table_schema: &TableSchema,
+        jsonl_options: JsonlOptions,
+        options: BulkWriteOptions,
+    ) -> Result<FinishReport> {
+        let file = open_source(path.as_ref(), jsonl_options.compression)?;
+        let mut writer = self
+            .create_bulk_stream_writer(table_schema, Some(options))
+            .await?;
+        let report = writer.write_jsonl(file, &jsonl_options).await?;
+        writer.finish_with_report(report).await
+    }
+}
+
+impl BulkStreamWriter {
+    /// Decode CSV records from `reader` and submit them batch by batch.
+    ///
+    /// Returns the number of batches and records submitted; `affected_rows` is
+    /// filled in by [`BulkStreamWriter::finish_with_report`].
+    pub async fn write_csv<R: Read>(
+        &mut self,
+        reader: R,
+        options: &CsvOptions,
+    ) -> Result<FinishReport> {
+        let (read_schema, binary_columns) = self.read_schema(&options.binary_encodings);
+        let batches = CsvReaderBuilder::new(read_schema)
+            .with_header(options.has_header)
+            .with_delimiter(options.delimiter)
+            .with_batch_size(options.batch_size)
+            .build(reader)
+            .context(error::DecodeRecordsSnafu)?;
+        let empty_strings = EmptyStrings {
+            columns: self
+                .empty_string_columns(options.empty_strings, &options.column_empty_strings),
+            nulls_are_empty: true,
+        };
+        self.write_decoded_batches(batches, &binary_columns, &empty_strings)
+            .await
+    }
+
+    /// Decode JSON Lines records from `reader` and submit them batch by batch.
+    ///
+    /// Returns the number of batches and records submitted; `affected_rows` is
+    /// filled in by [`BulkStreamWriter::finish_with_report`].
+    pub async fn write_jsonl<R: Read>(
+        &mut self,
+        reader: R,
+        options: &JsonlOptions,
+    ) -> Result<FinishReport> {
+        let (read_schema, binary_columns) = self.read_schema(&options.binary_encodings);
+        let batches = JsonReaderBuilder::new(read_schema)
+            .with_batch_size(options.batch_size)
+            .build(BufReader::new(reader))
+            .context(error::DecodeRecordsSnafu)?;
+        let empty_strings = EmptyStrings {
+            columns: self
+                .empty_string_columns(options.empty_strings, &options.column_empty_strings),
+            nulls_are_empty: false,
+        };
+        self.write_decoded_batches(batches, &binary_columns, &empty_strings)
+            .await
+    }
+
+    /// Finish the writer and complete `report` with the rows the server wrote
+    pub async fn finish_with_report(self, mut report: FinishReport) -> Result<FinishReport> {
+        let responses = self.finish_with_responses().await?;
+        report.affected_rows = responses
+            .iter()
+            .map(|response| response.affected_rows())
+            .sum();
+        Ok(report)
+    }
+
+    /// Get the schema to read records with, where encoded binary columns are read as
+    /// strings, along with those columns' indices and encodings
+    fn read_schema(
+        &self,
+        binary_encodings: &HashMap<String, BinaryEncoding>,
+    ) -> (SchemaRef, Vec<(usize, BinaryEncoding)>) {
+        let mut binary_columns = Vec::new();
+        let fields: Vec<Field> = self
+            .arrow_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| match binary_encodings.get(field.name()) {
+                Some(&encoding)
+                    if encoding != BinaryEncoding::Raw
+                        && field.data_type() == &DataType::Binary =>
+                {
+                    binary_columns.push((index, encoding));
+                    field.as_ref().clone().with_data_type(DataType::Utf8)
+                }
+                _ => field.as_ref().clone(),
+            })
+            .collect();
+
+        if binary_columns.is_empty() {
+            (self.arrow_schema.clone(), binary_columns)
+        } else {
+            (Arc::new(Schema::new(fields)), binary_columns)
+        }
+    }
+
+    /// Get the indices of the string columns along with their empty string policy
+    fn empty_string_columns(
+        &self,
+        policy: EmptyStringPolicy,
+        column_policies: &HashMap<String, EmptyStringPolicy>,
+    ) -> Vec<(usize, EmptyStringPolicy)> {
+        self.arrow_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.data_type() == &DataType::Utf8)
+            .map(|(index, field)| {
+                let policy = column_policies.get(field.name()).copied().unwrap_or(policy);
+                (index, policy)
+            })
+            .collect()
+    }
+
+    async fn write_decoded_batches(
+        &mut self,
+        batches: impl Iterator<Item = std::result::Result<RecordBatch, ArrowError>>,
+        binary_columns: &[(usize, BinaryEncoding)],
+        empty_strings: &EmptyStrings,
+    ) -> Result<FinishReport> {
+        let mut report = FinishReport::default();
+        for batch in batches {
+            let batch = batch.context(error::DecodeRecordsSnafu)?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let batch = if binary_columns.is_empty() {
+                batch
+            } else {
+                decode_binary_columns(batch, self.arrow_schema.clone(), binary_columns)?
+            };
+            let batch = empty_strings.apply(batch)?;
+            report.rows += batch.num_rows();
+            report.batches += 1;
+            self.submit_record_batch(batch).await?;
+        }
+        Ok(report)
+    }
+}
+
+/// The empty string policies of a read, see [`EmptyStringPolicy`]
+struct EmptyStrings {
+    columns: Vec<(usize, EmptyStringPolicy)>,
+    /// Whether the reader already turned empty strings into nulls, as the CSV one does
+    nulls_are_empty: bool,
+}
+
+impl EmptyStrings {
+    /// Rewrite the string columns of `batch` according to their policy
+    fn apply(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let mut columns = batch.columns().to_vec();
+        let mut changed = false;
+        for &(index, policy) in &self.columns {
+            let Some(strings) = columns[index].as_any().downcast_ref::<StringArray>() else {
+                continue;
+            };
+            let rewritten: StringArray = match policy {
+                EmptyStringPolicy::AsNull => {
+                    let Some(row) = strings.iter().position(|text| text == Some("")) else {
+                        continue;
+                    };
+                    let field = schema.field(index);
+                    ensure!(
+                        field.is_nullable(),
+                        error::NullInNonNullableColumnSnafu {
+                            column: field.name(),
+                            row,
+                        }
+                    );
+                    strings
+                        .iter()
+                        .map(|text| text.filter(|text| !text.is_empty()))
+                        .collect()
+                }
+                EmptyStringPolicy::AsEmpty if self.nulls_are_empty && strings.null_count() > 0 => {
+                    strings
+                        .iter()
+                        .map(|text| Some(text.unwrap_or("")))
+                        .collect()
+                }
+                EmptyStringPolicy::AsEmpty => continue,
+            };
+            columns[index] = Arc::new(rewritten) as ArrayRef;
+            changed = true;
+        }
+        if !changed {
+            return Ok(batch);
+        }
+        RecordBatch::try_new(schema, columns).context(error::CreateRecordBatchSnafu)
+    }
+}
+
+/// Replace the string columns at `binary_columns` by their decoded binary values
+fn decode_binary_columns(
+    batch: RecordBatch,
+    schema: SchemaRef,
+    binary_columns: &[(usize, BinaryEncoding)],
+) -> Result<RecordBatch> {
+    let mut columns = batch.columns().to_vec();
+    for &(index, encoding) in binary_columns {
+        let Some(strings) = columns[index].as_any().downcast_ref::<StringArray>() else {
+            continue;
+        };
+        let mut builder = BinaryBuilder::with_capacity(strings.len(), strings.value_data().len());
+        for text in strings {
+            match text {
+                Some(text) => builder.append_value(encoding.decode(text)?),
+                None => builder.append_null(),
+            }
+        }
+        columns[index] = Arc::new(builder.finish()) as ArrayRef;
+    }
+    RecordBatch::try_new(schema, columns).context(error::CreateRecordBatchSnafu)
+}
+
+fn open_file(path: &Path) -> Result<File> {
+    File::open(path).context(error::OpenFileSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+/// Open the file at `path`, decompressing it on the fly according to `compression`
+fn open_source(path: &Path, compression: FileCompression) -> Result<Box<dyn Read>> {
+    let file = BufReader::new(open_file(path)?);
+    Ok(match compression.resolve(path) {
+        FileCompression::Auto | FileCompression::None => Box::new(file),
+        FileCompression::Gzip => Box::new(MultiGzDecoder::new(file)),
+        #[cfg(feature = "zstd")]
+        FileCompression::Zstd => Box::new(zstd::Decoder::with_buffer(file).context(
+            error::OpenFileSnafu {
+                path: path.display().to_string(),
+            },
+        )?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use arrow_array::BinaryArray;
+    use arrow_flight::decode::FlightRecordBatchStream;
+    use arrow_flight::FlightData;
+    use futures::{StreamExt, TryStreamExt};
+
+    use super::*;
+    use crate::bulk::tests::{stub_writer, stub_writer_for};
+    use crate::flight::do_put::DoPutResponse;
+    use crate::ColumnDataType;
+
+    #[tokio::test]
+    async fn test_write_csv_from_temp_file() {
+        let path =
+            std::env::temp_dir().join(format!("greptimedb-ingester-{}.csv", uuid::Uuid::new_v4()));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "ts,value").unwrap();
+        for i in 0..5 {
+            writeln!(file, "2024-01-01T00:00:0{i}Z,{i}").unwrap();
+        }
+        drop(file);
+
+        let responses = vec![
+            Ok(DoPutResponse::new(0, 0)),
+            Ok(DoPutResponse::new(1, 2)),
+            Ok(DoPutResponse::new(2, 2)),
+            Ok(DoPutResponse::new(3, 1)),
+        ];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let options = CsvOptions::default().with_batch_size(2);
+        let report = writer
+            .write_csv(open_file(&path).unwrap(), &options)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.rows, 5);
+        assert_eq!(report.batches, 3);
+
+        let report = writer.finish_with_report(report).await.unwrap();
+        assert_eq!(report.affected_rows, 5);
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_from_gzip_file() {
+        let path = std::env::temp_dir().join(format!(
+            "greptimedb-ingester-{}.csv.gz",
+            uuid::Uuid::new_v4()
+        ));
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&path).unwrap(), Default::default());
+        writeln!(encoder, "ts,value").unwrap();
+        for i in 0..5 {
+            writeln!(encoder, "2024-01-01T00:00:0{i}Z,{i}").unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 5))];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        // The compression is detected from the `.gz` extension
+        let options = CsvOptions::default();
+        let source = open_source(&path, options.compression).unwrap();
+        let report = writer.write_csv(source, &options).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.rows, 5);
+        assert_eq!(report.batches, 1);
+
+        let report = writer.finish_with_report(report).await.unwrap();
+        assert_eq!(report.affected_rows, 5);
+    }
+
+    #[test]
+    fn test_file_compression_from_extension() {
+        let resolve = |path: &str| FileCompression::Auto.resolve(Path::new(path));
+        assert_eq!(resolve("export.csv.gz"), FileCompression::Gzip);
+        assert_eq!(resolve("export.csv"), FileCompression::None);
+        assert_eq!(
+            FileCompression::Gzip.resolve(Path::new("export.csv")),
+            FileCompression::Gzip
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_jsonl() {
+        let input = r#"{"ts": "2024-01-01T00:00:00Z", "value": 1}
+{"ts": "2024-01-01T00:00:01Z", "value": 2}
+{"ts": "2024-01-01T00:00:02Z"}
+"#;
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 3))];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let report = writer
+            .write_jsonl(input.as_bytes(), &JsonlOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report.rows, 3);
+        assert_eq!(report.batches, 1);
+
+        let report = writer.finish_with_report(report).await.unwrap();
+        assert_eq!(report.affected_rows, 3);
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_rejects_malformed_record() {
+        let input = "ts,value\n2024-01-01T00:00:00Z,not-a-number\n";
+        let responses = vec![Ok(DoPutResponse::new(0, 0))];
+        let (mut writer, _receiver) = stub_writer(BulkWriteOptions::default(), responses);
+
+        let result = writer
+            .write_csv(input.as_bytes(), &CsvOptions::default())
+            .await;
+        assert!(matches!(result, Err(crate::Error::DecodeRecords { .. })));
+    }
+
+    fn names_schema() -> TableSchema {
+        TableSchema::builder()
+            .name("people")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("name", ColumnDataType::String)
+            .add_field("nickname", ColumnDataType::String)
+    }
+
+    /// The `name` and `nickname` values of the batches a writer sent
+    async fn sent_names(
+        receiver: tokio::sync::mpsc::Receiver<FlightData>,
+    ) -> Vec<[Option<String>; 2]> {
+        let batches: Vec<RecordBatch> =
+            FlightRecordBatchStream::new_from_flight_data(receiver.map(Ok))
+                .try_collect()
+                .await
+                .unwrap();
+        let mut names = Vec::new();
+        for batch in batches {
+            let column = |index: usize| {
+                batch
+                    .column(index)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap()
+                    .iter()
+                    .map(|text| text.map(str::to_string))
+                    .collect::<Vec<_>>()
+            };
+            names.extend(column(1).into_iter().zip(column(2)).map(|(a, b)| [a, b]));
+        }
+        names
+    }
+
+    #[tokio::test]
+    async fn test_csv_empty_string_policies() {
+        let input = "ts,name,nickname\n2024-01-01T00:00:00Z,,\n2024-01-01T00:00:01Z,ann,a\n";
+        let write = |options: CsvOptions| async move {
+            let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 2))];
+            let (mut writer, receiver) =
+                stub_writer_for(&names_schema(), BulkWriteOptions::default(), responses);
+            writer.write_csv(input.as_bytes(), &options).await.unwrap();
+            writer.finish().await.unwrap();
+            sent_names(receiver).await
+        };
+        let some = |text: &str| Some(text.to_string());
+
+        // Empty fields are null by default
+        let names = write(CsvOptions::default()).await;
+        assert_eq!(names[0], [None, None]);
+        assert_eq!(names[1], [some("ann"), some("a")]);
+
+        let options = CsvOptions::default().with_empty_strings(EmptyStringPolicy::AsEmpty);
+        assert_eq!(write(options).await[0], [some(""), some("")]);
+
+        let options =
+            CsvOptions::default().with_column_empty_strings("nickname", EmptyStringPolicy::AsEmpty);
+        assert_eq!(write(options).await[0], [None, some("")]);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_empty_string_policies() {
+        let input = r#"{"ts": "2024-01-01T00:00:00Z", "name": "", "nickname": ""}
+{"ts": "2024-01-01T00:00:01Z", "name": null, "nickname": "a"}
+"#;
+        let write = |options: JsonlOptions| async move {
+            let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 2))];
+            let (mut writer, receiver) =
+                stub_writer_for(&names_schema(), BulkWriteOptions::default(), responses);
+            writer
+                .write_jsonl(input.as_bytes(), &options)
+                .await
+                .unwrap();
+            writer.finish().await.unwrap();
+            sent_names(receiver).await
+        };
+        let some = |text: &str| Some(text.to_string());
+
+        // JSON nulls stay null under either policy
+        let names = write(JsonlOptions::default()).await;
+        assert_eq!(names, [[some(""), some("")], [None, some("a")]]);
+
+        let options = JsonlOptions::default().with_empty_strings(EmptyStringPolicy::AsNull);
+        assert_eq!(write(options).await, [[None, None], [None, some("a")]]);
+
+        let options =
+            JsonlOptions::default().with_column_empty_strings("name", EmptyStringPolicy::AsNull);
+        assert_eq!(write(options).await[0], [None, some("")]);
+    }
+
+    #[test]
+    fn test_empty_string_as_null_in_non_nullable_column() {
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(StringArray::from(vec!["ann", ""])) as ArrayRef],
+        )
+        .unwrap();
+        let empty_strings = EmptyStrings {
+            columns: vec![(0, EmptyStringPolicy::AsNull)],
+            nulls_are_empty: false,
+        };
+        let error = empty_strings.apply(batch.clone()).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::NullInNonNullableColumn { ref column, row: 1, .. } if column == "name"
+        ));
+
+        let empty_strings = EmptyStrings {
+            columns: vec![(0, EmptyStringPolicy::AsEmpty)],
+            nulls_are_empty: false,
+        };
+        assert_eq!(empty_strings.apply(batch.clone()).unwrap(), batch);
+    }
+
+    fn binary_schema() -> TableSchema {
+        TableSchema::builder()
+            .name("blobs")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("hex", ColumnDataType::Binary)
+            .add_field("b64", ColumnDataType::Binary)
+    }
+
+    fn binary_options() -> CsvOptions {
+        CsvOptions::default()
+            .with_binary_encoding("hex", BinaryEncoding::Hex)
+            .with_binary_encoding("b64", BinaryEncoding::Base64)
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_with_encoded_binary_columns() {
+        let input = "ts,hex,b64\n\
+                     2024-01-01T00:00:00Z,0xdeadbeef,3q2+7w==\n\
+                     2024-01-01T00:00:01Z,00,AA==\n";
+        let responses = vec![Ok(DoPutResponse::new(0, 0)), Ok(DoPutResponse::new(1, 2))];
+        let (mut writer, receiver) =
+            stub_writer_for(&binary_schema(), BulkWriteOptions::default(), responses);
+
+        let report = writer
+            .write_csv(input.as_bytes(), &binary_options())
+            .await
+            .unwrap();
+        assert_eq!(report.rows, 2);
+        writer.finish().await.unwrap();
+
+        let batches: Vec<RecordBatch> =
+            FlightRecordBatchStream::new_from_flight_data(receiver.map(Ok))
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(batches.len(), 1);
+        for column in [1, 2] {
+            let binaries = batches[0]
+                .column(column)
+                .as_any()
+                .downcast_ref::<BinaryArray>()
+                .unwrap();
+            assert_eq!(binaries.value(0), [0xde, 0xad, 0xbe, 0xef]);
+            assert_eq!(binaries.value(1), [0x00]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_csv_rejects_malformed_binary() {
+        let input = "ts,hex,b64\n2024-01-01T00:00:00Z,0xdeadbeeg,3q2+7w==\n";
+        let responses = vec![Ok(DoPutResponse::new(0, 0))];
+        let (mut writer, _receiver) =
+            stub_writer_for(&binary_schema(), BulkWriteOptions::default(), responses);
+
+        let result = writer.write_csv(input.as_bytes(), &binary_options()).await;
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidBinaryEncoding { .. })
+        ));
+    }
+}
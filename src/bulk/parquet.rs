@@ -0,0 +1,141 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Archiving rows as Apache Parquet files, with the `parquet` feature
+//!
+//! Rows are converted with the same Arrow mapping the bulk writer sends over the
+//! wire, so a file holds exactly what an ingest of the rows would have written:
+//! decimal columns keep their precision and scale, and timestamp columns their unit.
+
+use std::io::Write;
+
+use arrow_array::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use snafu::ResultExt;
+
+use super::Rows;
+use crate::table::{Row, TableSchema};
+use crate::{error, Result};
+
+/// Number of rows added to the Arrow builders at once
+const ROW_BUFFER_SIZE: usize = 1024;
+
+/// Write `rows` of `schema` to `writer` as a Parquet file of one row group
+///
+/// Every row must have one value per column of the schema, as for the bulk writer.
+/// The file is complete, footer included, once this returns; standard tools such as
+/// DuckDB, pandas or `parquet-tools` can read it.
+pub fn write_rows_parquet<W: Write + Send>(
+    writer: W,
+    schema: &TableSchema,
+    rows: &[Row],
+) -> Result<()> {
+    let mut buffer = Rows::new(
+        schema.columns(),
+        rows.len(),
+        rows.len().clamp(1, ROW_BUFFER_SIZE),
+    )?;
+    for row in rows {
+        buffer.add_row(row.clone())?;
+    }
+    let batch = RecordBatch::try_from(buffer)?;
+
+    let mut writer =
+        ArrowWriter::try_new(writer, batch.schema(), None).context(error::WriteParquetSnafu)?;
+    writer.write(&batch).context(error::WriteParquetSnafu)?;
+    writer.close().context(error::WriteParquetSnafu)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal128Type, Int64Type, TimestampMillisecondType};
+    use arrow_array::Array;
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::basic::{LogicalType, TimeUnit};
+
+    use super::*;
+    use crate::table::Value;
+    use crate::ColumnDataType;
+
+    #[test]
+    fn test_write_rows_parquet_round_trip() {
+        let schema = TableSchema::builder()
+            .name("orders")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("region", ColumnDataType::String)
+            .add_field("quantity", ColumnDataType::Int64)
+            .add_decimal128_field("price", 10, 2);
+        let rows = vec![
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000),
+                Value::String("eu".to_string()),
+                Value::Int64(3),
+                Value::Decimal128(1999),
+            ]),
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_001_000),
+                Value::String("us".to_string()),
+                Value::Null,
+                Value::Decimal128(-250),
+            ]),
+        ];
+
+        let mut file = Vec::new();
+        write_rows_parquet(&mut file, &schema, &rows).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file)).unwrap();
+        let parquet_schema = builder.parquet_schema();
+        assert!(matches!(
+            parquet_schema.column(0).logical_type(),
+            Some(LogicalType::Timestamp {
+                unit: TimeUnit::MILLIS(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parquet_schema.column(3).logical_type(),
+            Some(LogicalType::Decimal {
+                precision: 10,
+                scale: 2
+            })
+        ));
+
+        let batches: Vec<RecordBatch> = builder
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+
+        let ts = batch.column(0).as_primitive::<TimestampMillisecondType>();
+        assert_eq!(
+            ts.values().to_vec(),
+            vec![1_700_000_000_000, 1_700_000_001_000]
+        );
+        let region = batch.column(1).as_string::<i32>();
+        assert_eq!((region.value(0), region.value(1)), ("eu", "us"));
+        let quantity = batch.column(2).as_primitive::<Int64Type>();
+        assert_eq!(quantity.value(0), 3);
+        assert!(quantity.is_null(1));
+        let price = batch.column(3).as_primitive::<Decimal128Type>();
+        assert_eq!(price.values().to_vec(), vec![1999, -250]);
+    }
+}
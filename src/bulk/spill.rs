@@ -0,0 +1,180 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Spilling large row buffers to disk, see [`super::BulkWriteOptions::with_spill`]
+//!
+//! Once the rows buffered by a [`super::Rows`] pass a size threshold, they are
+//! converted to a record batch and appended to an Arrow IPC stream in a temporary
+//! file. When the buffer is written, the spilled batches are read back one at a
+//! time, so at most one of them is held in memory besides the ones in flight.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::StreamReader;
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::ArrowError;
+use snafu::ResultExt;
+
+use crate::table::{Row, Value};
+use crate::{error, Result};
+
+/// Distinguishes the spill files of one process
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Rough size of a row once added to the Arrow builders
+fn estimated_row_size(row: &Row) -> usize {
+    row.iter()
+        .map(|value| match value {
+            Value::String(s) => s.len() + 4,
+            Value::Binary(b) => b.len() + 4,
+            Value::BinaryChunks(chunks) => {
+                chunks.iter().map(|chunk| chunk.len()).sum::<usize>() + 4
+            }
+            Value::Decimal128(_) => 16,
+            _ => 8,
+        })
+        .sum()
+}
+
+/// The spill state of a row buffer
+pub(crate) struct Spill {
+    threshold: usize,
+    dir: PathBuf,
+    buffered_bytes: usize,
+    file: Option<SpillFile>,
+}
+
+impl Spill {
+    pub(crate) fn new(threshold: usize, dir: PathBuf) -> Self {
+        Self {
+            threshold,
+            dir,
+            buffered_bytes: 0,
+            file: None,
+        }
+    }
+
+    /// Account for `row` being buffered, returning whether the buffered rows are due
+    /// to be spilled
+    pub(crate) fn record(&mut self, row: &Row) -> bool {
+        self.buffered_bytes += estimated_row_size(row);
+        self.buffered_bytes >= self.threshold
+    }
+
+    /// Append `batch` to the spill file, creating it on the first call
+    pub(crate) fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.buffered_bytes = 0;
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        if self.file.is_none() {
+            self.file = Some(SpillFile::create(&self.dir, batch)?);
+        }
+        let Some(file) = &mut self.file else {
+            unreachable!("the spill file was just created");
+        };
+        file.writer
+            .write(batch)
+            .with_context(|_| error::SpillRowsSnafu {
+                path: file.path.display().to_string(),
+            })?;
+        file.rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Number of rows spilled so far
+    pub(crate) fn rows(&self) -> usize {
+        self.file.as_ref().map_or(0, |file| file.rows)
+    }
+
+    /// Close the spill file and read the spilled batches back, `None` if nothing was
+    /// spilled
+    pub(crate) fn into_batches(self) -> Result<Option<SpilledBatches>> {
+        let Some(mut file) = self.file else {
+            return Ok(None);
+        };
+        let path = file.path.display().to_string();
+        file.writer
+            .finish()
+            .with_context(|_| error::SpillRowsSnafu { path: path.clone() })?;
+        let reader = File::open(&file.path)
+            .map_err(ArrowError::from)
+            .and_then(|reader| StreamReader::try_new(BufReader::new(reader), None))
+            .context(error::SpillRowsSnafu { path })?;
+        Ok(Some(SpilledBatches {
+            reader,
+            guard: file.guard,
+        }))
+    }
+}
+
+/// A spill file being written
+struct SpillFile {
+    path: PathBuf,
+    writer: StreamWriter<BufWriter<File>>,
+    rows: usize,
+    guard: RemoveOnDrop,
+}
+
+impl SpillFile {
+    fn create(dir: &Path, batch: &RecordBatch) -> Result<Self> {
+        let path = dir.join(format!(
+            "greptimedb-spill-{}-{}.arrows",
+            std::process::id(),
+            NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let writer = File::create(&path)
+            .map_err(ArrowError::from)
+            .and_then(|file| StreamWriter::try_new(BufWriter::new(file), &batch.schema()))
+            .with_context(|_| error::SpillRowsSnafu {
+                path: path.display().to_string(),
+            })?;
+        Ok(Self {
+            guard: RemoveOnDrop(path.clone()),
+            path,
+            writer,
+            rows: 0,
+        })
+    }
+}
+
+/// Deletes the spill file once it is neither written nor read anymore
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// The batches of a closed spill file, in the order they were spilled
+pub(crate) struct SpilledBatches {
+    reader: StreamReader<BufReader<File>>,
+    guard: RemoveOnDrop,
+}
+
+impl Iterator for SpilledBatches {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.reader.next()?;
+        Some(batch.context(error::SpillRowsSnafu {
+            path: self.guard.0.display().to_string(),
+        }))
+    }
+}
@@ -28,6 +28,10 @@ use tower::Service;
 
 use crate::error::{CreateChannelSnafu, InvalidConfigFilePathSnafu, InvalidTlsConfigSnafu, Result};
 
+mod insecure;
+
+use insecure::InsecureTlsConnector;
+
 const RECYCLE_CHANNEL_INTERVAL_SECS: u64 = 60;
 pub const DEFAULT_GRPC_REQUEST_TIMEOUT_SECS: u64 = 10;
 pub const DEFAULT_GRPC_CONNECT_TIMEOUT_SECS: u64 = 1;
@@ -48,6 +52,7 @@ struct Inner {
     id: u64,
     config: ChannelConfig,
     client_tls_config: Option<ClientTlsConfig>,
+    insecure_tls: Option<InsecureTlsConnector>,
     pool: Arc<Pool>,
     channel_recycle_started: AtomicBool,
     cancel: CancellationToken,
@@ -76,6 +81,7 @@ impl Inner {
             id,
             config,
             client_tls_config: None,
+            insecure_tls: None,
             pool,
             channel_recycle_started: AtomicBool::new(false),
             cancel,
@@ -107,30 +113,80 @@ impl ChannelManager {
             msg: "no config input",
         })?;
 
-        let server_root_ca_cert = std::fs::read_to_string(path_config.server_ca_cert_path)
+        if path_config.danger_accept_invalid_certs {
+            inner.insecure_tls = Some(InsecureTlsConnector::new(&path_config, config.tcp_nodelay)?);
+            return Ok(Self {
+                inner: Arc::new(inner),
+            });
+        }
+
+        let server_root_ca_cert = std::fs::read_to_string(&path_config.server_ca_cert_path)
             .context(InvalidConfigFilePathSnafu)?;
         let server_root_ca_cert = Certificate::from_pem(server_root_ca_cert);
-        let client_cert = std::fs::read_to_string(path_config.client_cert_path)
-            .context(InvalidConfigFilePathSnafu)?;
-        let client_key = std::fs::read_to_string(path_config.client_key_path)
-            .context(InvalidConfigFilePathSnafu)?;
-        let client_identity = Identity::from_pem(client_cert, client_key);
+        let mut client_tls_config = ClientTlsConfig::new().ca_certificate(server_root_ca_cert);
+
+        if let Some((cert_path, key_path)) = path_config.client_identity_paths()? {
+            let client_cert =
+                std::fs::read_to_string(cert_path).context(InvalidConfigFilePathSnafu)?;
+            let client_key =
+                std::fs::read_to_string(key_path).context(InvalidConfigFilePathSnafu)?;
+            client_tls_config =
+                client_tls_config.identity(Identity::from_pem(client_cert, client_key));
+        }
+        if let Some(domain_name) = &path_config.domain_name {
+            client_tls_config = client_tls_config.domain_name(domain_name);
+        }
 
-        inner.client_tls_config = Some(
-            ClientTlsConfig::new()
-                .ca_certificate(server_root_ca_cert)
-                .identity(client_identity),
-        );
+        inner.client_tls_config = Some(client_tls_config);
 
         Ok(Self {
             inner: Arc::new(inner),
         })
     }
 
+    /// Create a manager connecting over TLS that trusts the root certificates of the
+    /// operating system's trust store, instead of a CA certificate given by path
+    ///
+    /// Use this for servers with a publicly trusted certificate, e.g. managed
+    /// GreptimeDB. The store is loaded by tonic when a channel is built: the
+    /// certificates in `SSL_CERT_FILE`/`SSL_CERT_DIR` or the system location on Linux,
+    /// the keychain on macOS and the certificate store on Windows. No client identity
+    /// is presented.
+    ///
+    /// In a minimal container without a CA bundle the store is empty and every
+    /// handshake fails. Install the bundle, point `SSL_CERT_FILE` at one, or pass the
+    /// server's CA explicitly with [`ChannelManager::with_tls_config`] in that case.
+    pub fn with_native_roots(config: ChannelConfig) -> Self {
+        let mut inner = Inner::with_config(config);
+        inner.client_tls_config = Some(ClientTlsConfig::new().with_native_roots());
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
     pub fn config(&self) -> &ChannelConfig {
         &self.inner.config
     }
 
+    /// A manager with the same configuration and TLS setup, except for `TCP_NODELAY`
+    ///
+    /// The new manager starts with an empty pool, so no channel connected with the
+    /// previous setting is reused.
+    pub fn with_tcp_nodelay(&self, enabled: bool) -> Self {
+        let config = self.inner.config.clone().tcp_nodelay(enabled);
+        let mut inner = Inner::with_config(config);
+        inner.client_tls_config = self.inner.client_tls_config.clone();
+        inner.insecure_tls = self
+            .inner
+            .insecure_tls
+            .clone()
+            .map(|connector| connector.with_tcp_nodelay(enabled));
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
     fn pool(&self) -> &Arc<Pool> {
         &self.inner.pool
     }
@@ -152,12 +208,15 @@ impl ChannelManager {
             }
             Entry::Vacant(entry) => {
                 let endpoint = self.build_endpoint(addr)?;
-                let inner_channel = endpoint.connect_lazy();
+                let inner_channel = match &self.inner.insecure_tls {
+                    Some(connector) => endpoint.connect_with_connector_lazy(connector.clone()),
+                    None => endpoint.connect_lazy(),
+                };
 
                 let channel = Channel {
                     channel: inner_channel,
                     access: AtomicUsize::new(1),
-                    use_default_connector: true,
+                    use_default_connector: self.inner.insecure_tls.is_none(),
                 };
                 entry.insert(channel)
             }
@@ -267,11 +326,73 @@ impl ChannelManager {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// TLS settings of a client, see [`ChannelManager::with_tls_config`]
+///
+/// TLS support is always compiled in: tonic is built with its `tls` feature, backed
+/// by rustls, so no cargo feature needs to be enabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ClientTlsOption {
+    /// PEM file with the CA certificate the server certificate must be signed by
     pub server_ca_cert_path: String,
-    pub client_cert_path: String,
-    pub client_key_path: String,
+    /// PEM file with the client certificate presented for mutual TLS
+    pub client_cert_path: Option<String>,
+    /// PEM file with the private key of `client_cert_path`
+    pub client_key_path: Option<String>,
+    /// Name to verify the server certificate against, instead of the endpoint host.
+    /// Needed when connecting by IP or through a proxy under another name.
+    pub domain_name: Option<String>,
+    /// Accept any server certificate, including self-signed and expired ones, and
+    /// ignore `server_ca_cert_path`. Traffic is still encrypted, but the server is not
+    /// authenticated: only use this in development.
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl ClientTlsOption {
+    /// Trust the server if its certificate is signed by the CA in `server_ca_cert_path`
+    pub fn new(server_ca_cert_path: impl Into<String>) -> Self {
+        Self {
+            server_ca_cert_path: server_ca_cert_path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Present the certificate and key in the given PEM files, for mutual TLS
+    #[must_use]
+    pub fn with_client_identity(
+        mut self,
+        cert_path: impl Into<String>,
+        key_path: impl Into<String>,
+    ) -> Self {
+        self.client_cert_path = Some(cert_path.into());
+        self.client_key_path = Some(key_path.into());
+        self
+    }
+
+    /// Verify the server certificate against `domain_name` (SNI override)
+    #[must_use]
+    pub fn with_domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    /// Skip verification of the server certificate, see
+    /// [`ClientTlsOption::danger_accept_invalid_certs`]
+    #[must_use]
+    pub fn with_danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    fn client_identity_paths(&self) -> Result<Option<(&str, &str)>> {
+        match (&self.client_cert_path, &self.client_key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some((cert_path, key_path))),
+            (None, None) => Ok(None),
+            _ => InvalidTlsConfigSnafu {
+                msg: "client_cert_path and client_key_path must be set together",
+            }
+            .fail(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -580,11 +701,10 @@ mod tests {
             .http2_adaptive_window(true)
             .tcp_keepalive(Duration::from_secs(2))
             .tcp_nodelay(false)
-            .client_tls_config(ClientTlsOption {
-                server_ca_cert_path: "some_server_path".to_string(),
-                client_cert_path: "some_cert_path".to_string(),
-                client_key_path: "some_key_path".to_string(),
-            });
+            .client_tls_config(
+                ClientTlsOption::new("some_server_path")
+                    .with_client_identity("some_cert_path", "some_key_path"),
+            );
 
         assert_eq!(
             ChannelConfig {
@@ -602,8 +722,10 @@ mod tests {
                 tcp_nodelay: false,
                 client_tls: Some(ClientTlsOption {
                     server_ca_cert_path: "some_server_path".to_string(),
-                    client_cert_path: "some_cert_path".to_string(),
-                    client_key_path: "some_key_path".to_string(),
+                    client_cert_path: Some("some_cert_path".to_string()),
+                    client_key_path: Some("some_key_path".to_string()),
+                    domain_name: None,
+                    danger_accept_invalid_certs: false,
                 }),
                 max_recv_message_size: DEFAULT_MAX_GRPC_RECV_MESSAGE_SIZE,
                 max_send_message_size: DEFAULT_MAX_GRPC_SEND_MESSAGE_SIZE,
@@ -636,6 +758,86 @@ mod tests {
         let _ = res.unwrap();
     }
 
+    #[test]
+    fn test_with_tcp_nodelay() {
+        let mgr = ChannelManager::with_config(ChannelConfig::new().timeout(Duration::from_secs(3)));
+        assert!(mgr.config().tcp_nodelay);
+
+        let disabled = mgr.with_tcp_nodelay(false);
+        assert!(!disabled.config().tcp_nodelay);
+        assert_eq!(disabled.config().timeout, Some(Duration::from_secs(3)));
+        assert_ne!(disabled.id(), mgr.id());
+        let _ = disabled.build_endpoint("127.0.0.1:4001").unwrap();
+
+        // The insecure TLS connector sets the option itself
+        let config = ChannelConfig::new()
+            .client_tls_config(ClientTlsOption::default().with_danger_accept_invalid_certs(true));
+        let mgr = ChannelManager::with_tls_config(config).unwrap();
+        assert!(mgr.inner.insecure_tls.as_ref().unwrap().tcp_nodelay());
+        let disabled = mgr.with_tcp_nodelay(false);
+        assert!(!disabled.inner.insecure_tls.as_ref().unwrap().tcp_nodelay());
+        assert!(disabled.config().client_tls.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_insecure_tls_channel() {
+        let config = ChannelConfig::new().client_tls_config(
+            ClientTlsOption::default()
+                .with_domain_name("greptime.internal")
+                .with_danger_accept_invalid_certs(true),
+        );
+        let mgr = ChannelManager::with_tls_config(config).unwrap();
+
+        // TLS is done by the connector, the channel itself speaks plain HTTP/2 to it
+        let endpoint = mgr.build_endpoint("127.0.0.1:4001").unwrap();
+        assert_eq!(endpoint.uri().scheme_str(), Some("http"));
+        let _ = mgr.get("127.0.0.1:4001").unwrap();
+        let mut channels = 0;
+        mgr.retain_channel(|_, channel| {
+            assert!(!channel.use_default_connector());
+            channels += 1;
+            true
+        });
+        assert_eq!(channels, 1);
+    }
+
+    #[test]
+    fn test_tls_config_errors() {
+        // A missing CA file
+        let config = ChannelConfig::new().client_tls_config(ClientTlsOption::new("ca.pem"));
+        let err = ChannelManager::with_tls_config(config).unwrap_err();
+        assert!(matches!(err, crate::Error::InvalidConfigFilePath { .. }));
+
+        // A client certificate without its key, next to a readable CA file
+        let ca_path =
+            std::env::temp_dir().join(format!("greptimedb-ingester-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&ca_path, "-----BEGIN CERTIFICATE-----\n").unwrap();
+        let config = ChannelConfig::new().client_tls_config(ClientTlsOption {
+            client_cert_path: Some("client.pem".to_string()),
+            ..ClientTlsOption::new(ca_path.to_string_lossy())
+        });
+        let result = ChannelManager::with_tls_config(config);
+        std::fs::remove_file(&ca_path).unwrap();
+        assert!(matches!(result, Err(crate::Error::InvalidTlsConfig { .. })));
+
+        let config = ChannelConfig::new().client_tls_config(ClientTlsOption {
+            client_cert_path: Some("client.pem".to_string()),
+            danger_accept_invalid_certs: true,
+            ..Default::default()
+        });
+        let err = ChannelManager::with_tls_config(config).unwrap_err();
+        assert!(matches!(err, crate::Error::InvalidTlsConfig { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_native_roots_channel() {
+        let mgr = ChannelManager::with_native_roots(ChannelConfig::new());
+        let endpoint = mgr.build_endpoint("greptime.example.com:4001").unwrap();
+        assert_eq!(endpoint.uri().scheme_str(), Some("https"));
+        // The channel connects lazily, so no server is needed
+        assert!(mgr.get("greptime.example.com:4001").is_ok());
+    }
+
     #[tokio::test]
     async fn test_channel_with_connector() {
         let mgr = ChannelManager::new();
@@ -0,0 +1,191 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! TLS connector that skips server certificate verification, for development
+//! against servers with self-signed or otherwise untrusted certificates.
+
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper_util::rt::TokioIo;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use snafu::{OptionExt, ResultExt};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+use tonic::transport::Uri;
+use tower::Service;
+
+use super::ClientTlsOption;
+use crate::error::{InvalidConfigFilePathSnafu, InvalidTlsConfigSnafu, Result};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Connects over TLS to any server, accepting whatever certificate it presents
+///
+/// The handshake signatures are still checked, so the connection is encrypted, but
+/// nothing proves the server is the one it claims to be.
+#[derive(Clone)]
+pub(crate) struct InsecureTlsConnector {
+    config: Arc<ClientConfig>,
+    domain_name: Option<String>,
+    tcp_nodelay: bool,
+}
+
+impl std::fmt::Debug for InsecureTlsConnector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InsecureTlsConnector")
+            .field("domain_name", &self.domain_name)
+            .field("tcp_nodelay", &self.tcp_nodelay)
+            .finish()
+    }
+}
+
+impl InsecureTlsConnector {
+    pub(crate) fn new(option: &ClientTlsOption, tcp_nodelay: bool) -> Result<Self> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| InvalidTlsConfigSnafu { msg: e.to_string() }.build())?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyServerCert(provider)));
+
+        let mut config = match option.client_identity_paths()? {
+            Some((cert_path, key_path)) => builder
+                .with_client_auth_cert(load_certs(cert_path)?, load_private_key(key_path)?)
+                .map_err(|e| InvalidTlsConfigSnafu { msg: e.to_string() }.build())?,
+            None => builder.with_no_client_auth(),
+        };
+        // gRPC requires HTTP/2, which the server only speaks if negotiated
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        Ok(Self {
+            config: Arc::new(config),
+            domain_name: option.domain_name.clone(),
+            tcp_nodelay,
+        })
+    }
+
+    /// The same connector, setting `TCP_NODELAY` to `enabled` on new connections
+    pub(crate) fn with_tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    pub(crate) fn tcp_nodelay(&self) -> bool {
+        self.tcp_nodelay
+    }
+}
+
+impl Service<Uri> for InsecureTlsConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = BoxError;
+    type Future =
+        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = TlsConnector::from(self.config.clone());
+        let domain_name = self.domain_name.clone();
+        let tcp_nodelay = self.tcp_nodelay;
+
+        Box::pin(async move {
+            let host = uri
+                .host()
+                .ok_or("endpoint has no host")?
+                .trim_matches(|c| c == '[' || c == ']')
+                .to_string();
+            let port = uri.port_u16().unwrap_or(443);
+
+            let tcp = TcpStream::connect((host.as_str(), port)).await?;
+            tcp.set_nodelay(tcp_nodelay)?;
+            let server_name = ServerName::try_from(domain_name.unwrap_or(host))?;
+            let stream = connector.connect(server_name, tcp).await?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+#[derive(Debug)]
+struct AcceptAnyServerCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path).context(InvalidConfigFilePathSnafu)?);
+    rustls_pemfile::certs(&mut reader)
+        .collect::<std::io::Result<Vec<_>>>()
+        .context(InvalidConfigFilePathSnafu)
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path).context(InvalidConfigFilePathSnafu)?);
+    rustls_pemfile::private_key(&mut reader)
+        .context(InvalidConfigFilePathSnafu)?
+        .context(InvalidTlsConfigSnafu {
+            msg: format!("no private key found in {path}"),
+        })
+}
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use arrow_flight::flight_service_client::FlightServiceClient;
 use greptime_proto::v1::health_check_client::HealthCheckClient;
@@ -21,11 +23,17 @@ use parking_lot::RwLock;
 use snafu::OptionExt;
 use tonic::codec::CompressionEncoding;
 use tonic::transport::Channel;
+use tonic::{Code, Status};
 
 use crate::channel_manager::{ChannelConfig, ChannelManager, ClientTlsOption};
 use crate::load_balance::{LoadBalance, Loadbalancer};
+use crate::resolver::{DnsRefresh, Resolver, SystemResolver};
+use crate::retry::RetryBudget;
 use crate::{error, Result};
 
+/// How long a peer found unavailable is skipped when picking one for a request
+const UNAVAILABLE_PEER_COOLDOWN: Duration = Duration::from_secs(5);
+
 pub struct FlightClient {
     addr: String,
     client: FlightServiceClient<Channel>,
@@ -44,6 +52,10 @@ impl FlightClient {
 #[derive(Clone, Debug, Default)]
 pub struct Client {
     inner: Arc<Inner>,
+    transport_compression: Option<CompressionEncoding>,
+    dns_refresh: Option<Arc<DnsRefresh>>,
+    retry_budget: Option<Arc<RetryBudget>>,
+    reconnect: bool,
 }
 
 impl Client {
@@ -59,6 +71,11 @@ impl Client {
         Self::with_manager_and_urls(ChannelManager::new(), urls)
     }
 
+    /// Connect to `urls` over TLS, e.g. through a TLS-terminating proxy
+    ///
+    /// `client_tls` sets the CA to trust and optionally a client identity for mutual
+    /// TLS, the name to verify the server against and whether to skip verification
+    /// in development.
     pub fn with_tls_and_urls<U, A>(urls: A, client_tls: ClientTlsOption) -> Result<Self>
     where
         U: AsRef<str>,
@@ -69,6 +86,17 @@ impl Client {
         Ok(Self::with_manager_and_urls(channel_manager, urls))
     }
 
+    /// Connect to `urls` over TLS, trusting the root certificates of the operating
+    /// system, see [`ChannelManager::with_native_roots`]
+    pub fn with_tls_native_roots<U, A>(urls: A) -> Self
+    where
+        U: AsRef<str>,
+        A: AsRef<[U]>,
+    {
+        let channel_manager = ChannelManager::with_native_roots(ChannelConfig::default());
+        Self::with_manager_and_urls(channel_manager, urls)
+    }
+
     pub fn with_manager_and_urls<U, A>(channel_manager: ChannelManager, urls: A) -> Self
     where
         U: AsRef<str>,
@@ -83,9 +111,132 @@ impl Client {
         inner.set_peers(urls);
         Self {
             inner: Arc::new(inner),
+            transport_compression: None,
+            dns_refresh: None,
+            retry_budget: None,
+            reconnect: false,
         }
     }
 
+    /// Compress gRPC messages on the wire with `codec`, in both directions.
+    ///
+    /// This is HTTP/2 message-level compression and applies to every RPC, including
+    /// the regular `Database::insert` path. It is orthogonal to the Arrow IPC batch
+    /// compression configured by `BulkWriteOptions::with_compression`; enabling both
+    /// for bulk writes mostly costs CPU, as compressed batches rarely shrink further.
+    #[must_use]
+    pub fn with_transport_compression(mut self, codec: CompressionEncoding) -> Self {
+        self.transport_compression = Some(codec);
+        self
+    }
+
+    /// Re-resolve endpoints given as hostnames every `interval`, reconnecting to a
+    /// peer when the set of addresses it resolves to changes.
+    ///
+    /// Resolution goes through the operating system resolver (`getaddrinfo` on Unix),
+    /// so `/etc/hosts` and any local caching such as `nscd` or `systemd-resolved` apply,
+    /// and DNS TTLs are not visible to the client: the interval is the only trigger.
+    /// Endpoints given as IP addresses are never re-resolved. The refresh task starts
+    /// with the first request and stops when the last clone of the client is dropped.
+    #[must_use]
+    pub fn with_dns_refresh(self, interval: Duration) -> Self {
+        self.with_dns_refresh_resolver(interval, Arc::new(SystemResolver))
+    }
+
+    /// Like [`Client::with_dns_refresh`], resolving hostnames with `resolver`
+    #[must_use]
+    pub fn with_dns_refresh_resolver(
+        mut self,
+        interval: Duration,
+        resolver: Arc<dyn Resolver>,
+    ) -> Self {
+        self.dns_refresh = Some(Arc::new(DnsRefresh::new(interval, resolver)));
+        self
+    }
+
+    /// The interval hostname endpoints are re-resolved at, if enabled
+    pub fn dns_refresh_interval(&self) -> Option<Duration> {
+        self.dns_refresh.as_ref().map(|refresh| refresh.interval())
+    }
+
+    /// Share `budget` between all retries made through this client and its clones
+    #[must_use]
+    pub fn with_retry_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// The retry budget shared by this client, if any
+    pub fn retry_budget(&self) -> Option<&Arc<RetryBudget>> {
+        self.retry_budget.as_ref()
+    }
+
+    /// Set `TCP_NODELAY` on the connections to the peers, enabled by default
+    ///
+    /// With it enabled, small writes such as single-row inserts go out immediately
+    /// instead of waiting up to a round trip for Nagle's algorithm to coalesce them,
+    /// which lowers latency. Disabling it can save a few packets when many small
+    /// messages are in flight at once, at the cost of that delay; bulk writes send
+    /// large frames and see little difference either way. Connections already made
+    /// by this client are not reused after the change; the peers, including those
+    /// skipped as unavailable, stay shared with it.
+    #[must_use]
+    pub fn with_tcp_nodelay(mut self, enabled: bool) -> Self {
+        let channel_manager = self.inner.channel_manager.with_tcp_nodelay(enabled);
+        self.inner = Arc::new(self.inner.with_channel_manager(channel_manager));
+        // A running refresh evicts stale channels from the previous manager only
+        if let Some(dns_refresh) = &self.dns_refresh {
+            self.dns_refresh = Some(Arc::new(DnsRefresh::new(
+                dns_refresh.interval(),
+                dns_refresh.resolver(),
+            )));
+        }
+        self
+    }
+
+    /// Whether `TCP_NODELAY` is set on connections, see [`Client::with_tcp_nodelay`]
+    pub fn tcp_nodelay(&self) -> bool {
+        self.inner.channel_manager.config().tcp_nodelay
+    }
+
+    /// Rebuild the channel to a peer whose request failed as unavailable, e.g. after a
+    /// server restart, instead of keeping a broken connection
+    ///
+    /// The failing request still returns its error; the next request connects anew.
+    /// With several URLs, the failed peer is skipped for a few seconds so the next
+    /// requests go to the other ones, unless all of them failed.
+    #[must_use]
+    pub fn with_reconnect(mut self, enabled: bool) -> Self {
+        self.reconnect = enabled;
+        self
+    }
+
+    /// Whether broken channels are rebuilt, see [`Client::with_reconnect`]
+    pub fn reconnect(&self) -> bool {
+        self.reconnect
+    }
+
+    /// Pass through the result of an RPC to `addr`, noting a failure as unavailable
+    pub(crate) fn observe<T>(
+        &self,
+        addr: &str,
+        result: std::result::Result<T, Status>,
+    ) -> std::result::Result<T, Status> {
+        result.map_err(|status| self.observe_status(addr, status))
+    }
+
+    /// Drop the channel to `addr` if `status` shows it unavailable and reconnecting
+    /// is enabled
+    pub(crate) fn observe_status(&self, addr: &str, status: Status) -> Status {
+        if self.reconnect && status.code() == Code::Unavailable {
+            self.inner.mark_unavailable(addr);
+            self.inner
+                .channel_manager
+                .retain_channel(|peer, _| peer != addr);
+        }
+        status
+    }
+
     pub fn start<U, A>(&self, urls: A)
     where
         U: AsRef<str>,
@@ -108,6 +259,10 @@ impl Client {
                 err_msg: "No available peer found",
             })?;
 
+        if let Some(dns_refresh) = &self.dns_refresh {
+            dns_refresh.start(self.inner.peers.clone(), self.inner.channel_manager.clone());
+        }
+
         let channel = self.inner.channel_manager.get(&addr)?;
         Ok((addr, channel))
     }
@@ -121,7 +276,9 @@ impl Client {
     }
 
     pub fn send_compression(&self) -> Option<CompressionEncoding> {
-        if self.inner.channel_manager.config().send_compression {
+        if self.transport_compression.is_some() {
+            self.transport_compression
+        } else if self.inner.channel_manager.config().send_compression {
             Some(CompressionEncoding::Zstd)
         } else {
             None
@@ -129,7 +286,9 @@ impl Client {
     }
 
     pub fn accept_compression(&self) -> Option<CompressionEncoding> {
-        if self.inner.channel_manager.config().accept_compression {
+        if self.transport_compression.is_some() {
+            self.transport_compression
+        } else if self.inner.channel_manager.config().accept_compression {
             Some(CompressionEncoding::Zstd)
         } else {
             None
@@ -152,10 +311,14 @@ impl Client {
         Ok(FlightClient { addr, client })
     }
 
+    /// Ping a peer, failing if it is not serving
+    ///
+    /// With [`Client::with_reconnect`], a failed ping drops the broken channel like
+    /// any other request, so a later call reconnects.
     pub async fn health_check(&self) -> Result<()> {
-        let (_, channel) = self.find_channel()?;
+        let (addr, channel) = self.find_channel()?;
         let mut client = HealthCheckClient::new(channel);
-        let _ = client.health_check(HealthCheckRequest {}).await?;
+        let _ = self.observe(&addr, client.health_check(HealthCheckRequest {}).await)?;
         Ok(())
     }
 }
@@ -165,6 +328,7 @@ struct Inner {
     channel_manager: ChannelManager,
     peers: Arc<RwLock<Vec<String>>>,
     load_balance: Loadbalancer,
+    unavailable: Arc<RwLock<HashMap<String, Instant>>>,
 }
 
 impl Inner {
@@ -175,6 +339,16 @@ impl Inner {
         }
     }
 
+    /// Use `channel_manager`, sharing the peers and their availability with `self`
+    fn with_channel_manager(&self, channel_manager: ChannelManager) -> Self {
+        Self {
+            channel_manager,
+            peers: self.peers.clone(),
+            load_balance: Loadbalancer::default(),
+            unavailable: self.unavailable.clone(),
+        }
+    }
+
     fn set_peers(&self, peers: Vec<String>) {
         let mut guard = self.peers.write();
         *guard = peers;
@@ -182,6 +356,151 @@ impl Inner {
 
     fn get_peer(&self) -> Option<String> {
         let guard = self.peers.read();
+        let unavailable = self.unavailable.read();
+        if !unavailable.is_empty() {
+            let available: Vec<String> = guard
+                .iter()
+                .filter(|peer| {
+                    unavailable
+                        .get(*peer)
+                        .is_none_or(|since| since.elapsed() >= UNAVAILABLE_PEER_COOLDOWN)
+                })
+                .cloned()
+                .collect();
+            if let Some(peer) = self.load_balance.get_peer(&available) {
+                return Some(peer.clone());
+            }
+        }
         self.load_balance.get_peer(&guard).cloned()
     }
+
+    fn mark_unavailable(&self, peer: &str) {
+        let mut unavailable = self.unavailable.write();
+        unavailable.retain(|_, since| since.elapsed() < UNAVAILABLE_PEER_COOLDOWN);
+        unavailable.insert(peer.to_string(), Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use greptime_proto::v1::health_check_server::{HealthCheck, HealthCheckServer};
+    use greptime_proto::v1::HealthCheckResponse;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    use super::*;
+
+    struct StubHealthCheck;
+
+    #[tonic::async_trait]
+    impl HealthCheck for StubHealthCheck {
+        async fn health_check(
+            &self,
+            _request: tonic::Request<HealthCheckRequest>,
+        ) -> std::result::Result<tonic::Response<HealthCheckResponse>, Status> {
+            Ok(tonic::Response::new(HealthCheckResponse {}))
+        }
+    }
+
+    /// Serve health checks on `listener` until the returned sender is dropped
+    fn serve(listener: TcpListener) -> (oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(HealthCheckServer::new(StubHealthCheck))
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    let _ = stopped.await;
+                })
+                .await
+                .unwrap();
+        });
+        (stop, server)
+    }
+
+    fn channel_count(client: &Client) -> usize {
+        let mut count = 0;
+        client.inner.channel_manager.retain_channel(|_, _| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_after_server_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let client = Client::with_urls([&addr]).with_reconnect(true);
+
+        let (stop, server) = serve(listener);
+        client.health_check().await.unwrap();
+        assert_eq!(channel_count(&client), 1);
+
+        drop(stop);
+        server.await.unwrap();
+        assert!(client.health_check().await.is_err());
+        // The broken channel is gone rather than reused
+        assert_eq!(channel_count(&client), 0);
+
+        let (_stop, _server) = serve(TcpListener::bind(&addr).await.unwrap());
+        client.health_check().await.unwrap();
+        assert_eq!(channel_count(&client), 1);
+    }
+
+    #[test]
+    fn test_unavailable_peer_is_skipped() {
+        let client = Client::with_urls(["127.0.0.1:4001", "127.0.0.1:4002"]).with_reconnect(true);
+        let _ = client.observe_status("127.0.0.1:4001", Status::unavailable("connection refused"));
+        for _ in 0..20 {
+            assert_eq!(client.inner.get_peer().unwrap(), "127.0.0.1:4002");
+        }
+
+        // Other errors leave the peer in rotation, and without peers left all are used
+        let _ = client.observe_status("127.0.0.1:4002", Status::invalid_argument("bad row"));
+        assert_eq!(client.inner.get_peer().unwrap(), "127.0.0.1:4002");
+        let _ = client.observe_status("127.0.0.1:4002", Status::unavailable("connection refused"));
+        assert!(client.inner.get_peer().is_some());
+
+        let client = Client::with_urls(["127.0.0.1:4001", "127.0.0.1:4002"]);
+        let _ = client.observe_status("127.0.0.1:4001", Status::unavailable("connection refused"));
+        assert!(client.inner.unavailable.read().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_nodelay() {
+        let client = Client::with_urls(["127.0.0.1:4001", "127.0.0.1:4002"]);
+        assert!(client.tcp_nodelay());
+
+        let client = client.with_reconnect(true);
+        let _ = client.observe_status("127.0.0.1:4001", Status::unavailable("connection refused"));
+        let previous = client.clone();
+        let client = client.with_tcp_nodelay(false);
+        assert!(!client.tcp_nodelay());
+        assert_eq!(client.inner.peers.read().len(), 2);
+        assert!(client
+            .inner
+            .unavailable
+            .read()
+            .contains_key("127.0.0.1:4001"));
+
+        // Both clients see the peers refreshed through either of them
+        previous.inner.set_peers(vec!["127.0.0.1:4003".to_string()]);
+        assert_eq!(client.inner.get_peer().unwrap(), "127.0.0.1:4003");
+        let (_, _channel) = client.find_channel().unwrap();
+
+        let client = client.with_tcp_nodelay(true);
+        assert!(client.tcp_nodelay());
+    }
+
+    #[test]
+    fn test_transport_compression() {
+        let client = Client::with_urls(["127.0.0.1:4001"]);
+        assert_eq!(client.send_compression(), None);
+        assert_eq!(client.accept_compression(), None);
+
+        let client = client.with_transport_compression(CompressionEncoding::Gzip);
+        assert_eq!(client.send_compression(), Some(CompressionEncoding::Gzip));
+        assert_eq!(client.accept_compression(), Some(CompressionEncoding::Gzip));
+    }
 }
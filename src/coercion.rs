@@ -0,0 +1,469 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Classification of values against column types
+//!
+//! This module answers "what would it take to write this value into that column"
+//! without converting anything, which is useful to vet a large import before sending it.
+
+use arrow_schema::TimeUnit;
+
+use crate::api::v1::ColumnDataType;
+use crate::table::{Row, TableSchema, Value};
+
+/// Maximum number of failing row indices kept per column in a [`CoercionReport`]
+const MAX_FAILURE_EXAMPLES: usize = 10;
+
+/// How a value relates to the type of the column it is written into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coercion {
+    /// The value already has the column's type, or is null
+    Exact,
+    /// The value converts to the column's type without losing information
+    Widening,
+    /// The value converts to the column's type but may lose precision or range
+    Narrowing,
+    /// The value cannot be converted to the column's type
+    Incompatible,
+}
+
+/// Classify how `value` would be coerced into a column of `data_type`
+pub fn classify(value: &Value, data_type: ColumnDataType) -> Coercion {
+    if matches!(value, Value::Null) || is_exact(value, data_type) {
+        return Coercion::Exact;
+    }
+
+    if let (Some(source), Some(target)) = (integer_kind(value), integer_target(data_type)) {
+        return if integer_widens(source, target) {
+            Coercion::Widening
+        } else {
+            Coercion::Narrowing
+        };
+    }
+
+    if let Some(mantissa_bits) = float_target(data_type) {
+        if let Some((signed, bits)) = integer_kind(value) {
+            let magnitude_bits = if signed { bits - 1 } else { bits };
+            return if magnitude_bits <= mantissa_bits {
+                Coercion::Widening
+            } else {
+                Coercion::Narrowing
+            };
+        }
+        return match value {
+            Value::Float32(_) => Coercion::Widening,
+            Value::Float64(_) => Coercion::Narrowing,
+            _ => Coercion::Incompatible,
+        };
+    }
+
+    if integer_target(data_type).is_some() && matches!(value, Value::Float32(_) | Value::Float64(_))
+    {
+        return Coercion::Narrowing;
+    }
+
+    if data_type == ColumnDataType::Decimal128 {
+        return match value {
+            _ if integer_kind(value).is_some() => Coercion::Widening,
+            Value::Float32(_) | Value::Float64(_) => Coercion::Narrowing,
+            _ => Coercion::Incompatible,
+        };
+    }
+
+    if let (Some(source), Some(target)) = (timestamp_unit(value), timestamp_target(data_type)) {
+        return unit_coercion(source, target);
+    }
+
+    if let (Some(source), Some(target)) = (time_unit(value), time_target(data_type)) {
+        return unit_coercion(source, target);
+    }
+
+    match (value, data_type) {
+        (Value::String(_), ColumnDataType::Binary | ColumnDataType::Json) => Coercion::Widening,
+        (Value::Json(_), ColumnDataType::String | ColumnDataType::Binary) => Coercion::Widening,
+        _ => Coercion::Incompatible,
+    }
+}
+
+/// Coercion tallies for a single column
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnCoercion {
+    /// Column name
+    pub column: String,
+    /// Values that already have the column's type, including nulls
+    pub exact: usize,
+    /// Values that convert losslessly
+    pub widening: usize,
+    /// Values that convert with possible loss of precision or range
+    pub narrowing: usize,
+    /// Values that cannot be converted, including values missing from short rows
+    pub failures: usize,
+    /// Indices of the first failing rows, at most 10 are kept
+    pub failure_examples: Vec<usize>,
+}
+
+impl ColumnCoercion {
+    fn record(&mut self, row_index: usize, coercion: Coercion) {
+        match coercion {
+            Coercion::Exact => self.exact += 1,
+            Coercion::Widening => self.widening += 1,
+            Coercion::Narrowing => self.narrowing += 1,
+            Coercion::Incompatible => {
+                self.failures += 1;
+                if self.failure_examples.len() < MAX_FAILURE_EXAMPLES {
+                    self.failure_examples.push(row_index);
+                }
+            }
+        }
+    }
+}
+
+/// Per-column report of the coercions an import would need, see [`dry_run_coerce`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoercionReport {
+    /// One entry per schema column, in schema order
+    pub columns: Vec<ColumnCoercion>,
+}
+
+impl CoercionReport {
+    /// Get the tallies of a column by name
+    pub fn column(&self, name: &str) -> Option<&ColumnCoercion> {
+        self.columns.iter().find(|column| column.column == name)
+    }
+
+    /// Check whether any value cannot be converted
+    pub fn has_failures(&self) -> bool {
+        self.columns.iter().any(|column| column.failures > 0)
+    }
+
+    /// Check whether any value would lose precision or range
+    pub fn has_narrowing(&self) -> bool {
+        self.columns.iter().any(|column| column.narrowing > 0)
+    }
+}
+
+/// Tally the coercions needed to write `rows` into `schema` without sending anything.
+///
+/// Values are matched to columns by position. A row shorter than the schema counts
+/// a failure for every missing column.
+pub fn dry_run_coerce(rows: &[Row], schema: &TableSchema) -> CoercionReport {
+    let mut columns: Vec<ColumnCoercion> = schema
+        .columns()
+        .iter()
+        .map(|column| ColumnCoercion {
+            column: column.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+
+    for (row_index, row) in rows.iter().enumerate() {
+        for (column_index, (column, stats)) in
+            schema.columns().iter().zip(columns.iter_mut()).enumerate()
+        {
+            let coercion = row
+                .value(column_index)
+                .map_or(Coercion::Incompatible, |value| {
+                    classify(value, column.data_type)
+                });
+            stats.record(row_index, coercion);
+        }
+    }
+
+    CoercionReport { columns }
+}
+
+fn is_exact(value: &Value, data_type: ColumnDataType) -> bool {
+    matches!(
+        (value, data_type),
+        (Value::Boolean(_), ColumnDataType::Boolean)
+            | (Value::Int8(_), ColumnDataType::Int8)
+            | (Value::Int16(_), ColumnDataType::Int16)
+            | (Value::Int32(_), ColumnDataType::Int32)
+            | (Value::Int64(_), ColumnDataType::Int64)
+            | (Value::Uint8(_), ColumnDataType::Uint8)
+            | (Value::Uint16(_), ColumnDataType::Uint16)
+            | (Value::Uint32(_), ColumnDataType::Uint32)
+            | (Value::Uint64(_), ColumnDataType::Uint64)
+            | (Value::Float32(_), ColumnDataType::Float32)
+            | (Value::Float64(_), ColumnDataType::Float64)
+            | (Value::Binary(_), ColumnDataType::Binary)
+            | (Value::BinaryChunks(_), ColumnDataType::Binary)
+            | (Value::String(_), ColumnDataType::String)
+            | (Value::Date(_), ColumnDataType::Date)
+            | (Value::Datetime(_), ColumnDataType::Datetime)
+            | (Value::TimestampSecond(_), ColumnDataType::TimestampSecond)
+            | (
+                Value::TimestampMillisecond(_),
+                ColumnDataType::TimestampMillisecond
+            )
+            | (
+                Value::TimestampMicrosecond(_),
+                ColumnDataType::TimestampMicrosecond
+            )
+            | (
+                Value::TimestampNanosecond(_),
+                ColumnDataType::TimestampNanosecond
+            )
+            | (Value::TimeSecond(_), ColumnDataType::TimeSecond)
+            | (Value::TimeMillisecond(_), ColumnDataType::TimeMillisecond)
+            | (Value::TimeMicrosecond(_), ColumnDataType::TimeMicrosecond)
+            | (Value::TimeNanosecond(_), ColumnDataType::TimeNanosecond)
+            | (Value::Decimal128(_), ColumnDataType::Decimal128)
+            | (Value::Json(_), ColumnDataType::Json)
+    )
+}
+
+/// Signedness and bit width of an integer value
+fn integer_kind(value: &Value) -> Option<(bool, u32)> {
+    Some(match value {
+        Value::Int8(_) => (true, 8),
+        Value::Int16(_) => (true, 16),
+        Value::Int32(_) => (true, 32),
+        Value::Int64(_) => (true, 64),
+        Value::Uint8(_) => (false, 8),
+        Value::Uint16(_) => (false, 16),
+        Value::Uint32(_) => (false, 32),
+        Value::Uint64(_) => (false, 64),
+        _ => return None,
+    })
+}
+
+/// Signedness and bit width of an integer column type
+fn integer_target(data_type: ColumnDataType) -> Option<(bool, u32)> {
+    Some(match data_type {
+        ColumnDataType::Int8 => (true, 8),
+        ColumnDataType::Int16 => (true, 16),
+        ColumnDataType::Int32 => (true, 32),
+        ColumnDataType::Int64 => (true, 64),
+        ColumnDataType::Uint8 => (false, 8),
+        ColumnDataType::Uint16 => (false, 16),
+        ColumnDataType::Uint32 => (false, 32),
+        ColumnDataType::Uint64 => (false, 64),
+        _ => return None,
+    })
+}
+
+fn integer_widens(
+    (source_signed, source_bits): (bool, u32),
+    (target_signed, target_bits): (bool, u32),
+) -> bool {
+    match (source_signed, target_signed) {
+        (true, true) | (false, false) => source_bits <= target_bits,
+        // An unsigned value needs one extra bit for the sign
+        (false, true) => source_bits < target_bits,
+        // A signed value may be negative
+        (true, false) => false,
+    }
+}
+
+/// Mantissa bits of a float column type
+fn float_target(data_type: ColumnDataType) -> Option<u32> {
+    match data_type {
+        ColumnDataType::Float32 => Some(24),
+        ColumnDataType::Float64 => Some(53),
+        _ => None,
+    }
+}
+
+fn timestamp_unit(value: &Value) -> Option<TimeUnit> {
+    match value {
+        Value::TimestampSecond(_) => Some(TimeUnit::Second),
+        Value::TimestampMillisecond(_) => Some(TimeUnit::Millisecond),
+        Value::TimestampMicrosecond(_) | Value::Datetime(_) => Some(TimeUnit::Microsecond),
+        Value::TimestampNanosecond(_) => Some(TimeUnit::Nanosecond),
+        _ => None,
+    }
+}
+
+fn timestamp_target(data_type: ColumnDataType) -> Option<TimeUnit> {
+    match data_type {
+        ColumnDataType::TimestampSecond => Some(TimeUnit::Second),
+        ColumnDataType::TimestampMillisecond => Some(TimeUnit::Millisecond),
+        // DateTime is an alias of TIMESTAMP_MICROSECOND
+        ColumnDataType::TimestampMicrosecond | ColumnDataType::Datetime => {
+            Some(TimeUnit::Microsecond)
+        }
+        ColumnDataType::TimestampNanosecond => Some(TimeUnit::Nanosecond),
+        _ => None,
+    }
+}
+
+fn time_unit(value: &Value) -> Option<TimeUnit> {
+    match value {
+        Value::TimeSecond(_) => Some(TimeUnit::Second),
+        Value::TimeMillisecond(_) => Some(TimeUnit::Millisecond),
+        Value::TimeMicrosecond(_) => Some(TimeUnit::Microsecond),
+        Value::TimeNanosecond(_) => Some(TimeUnit::Nanosecond),
+        _ => None,
+    }
+}
+
+fn time_target(data_type: ColumnDataType) -> Option<TimeUnit> {
+    match data_type {
+        ColumnDataType::TimeSecond => Some(TimeUnit::Second),
+        ColumnDataType::TimeMillisecond => Some(TimeUnit::Millisecond),
+        ColumnDataType::TimeMicrosecond => Some(TimeUnit::Microsecond),
+        ColumnDataType::TimeNanosecond => Some(TimeUnit::Nanosecond),
+        _ => None,
+    }
+}
+
+/// Converting to a finer unit keeps the instant, converting to a coarser one truncates it
+fn unit_coercion(source: TimeUnit, target: TimeUnit) -> Coercion {
+    fn rank(unit: TimeUnit) -> u8 {
+        match unit {
+            TimeUnit::Second => 0,
+            TimeUnit::Millisecond => 1,
+            TimeUnit::Microsecond => 2,
+            TimeUnit::Nanosecond => 3,
+        }
+    }
+
+    match rank(source).cmp(&rank(target)) {
+        std::cmp::Ordering::Equal => Coercion::Exact,
+        std::cmp::Ordering::Less => Coercion::Widening,
+        std::cmp::Ordering::Greater => Coercion::Narrowing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(&Value::Int64(1), ColumnDataType::Int64),
+            Coercion::Exact
+        );
+        assert_eq!(
+            classify(&Value::Null, ColumnDataType::Int64),
+            Coercion::Exact
+        );
+        assert_eq!(
+            classify(&Value::Int32(1), ColumnDataType::Int64),
+            Coercion::Widening
+        );
+        assert_eq!(
+            classify(&Value::Uint32(1), ColumnDataType::Int64),
+            Coercion::Widening
+        );
+        assert_eq!(
+            classify(&Value::Uint64(1), ColumnDataType::Int64),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(&Value::Int8(-1), ColumnDataType::Uint64),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(&Value::Int32(1), ColumnDataType::Float64),
+            Coercion::Widening
+        );
+        assert_eq!(
+            classify(&Value::Int64(1), ColumnDataType::Float64),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(&Value::Float64(1.0), ColumnDataType::Float32),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(&Value::Float64(1.0), ColumnDataType::Int64),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(
+                &Value::TimestampSecond(1),
+                ColumnDataType::TimestampMillisecond
+            ),
+            Coercion::Widening
+        );
+        assert_eq!(
+            classify(
+                &Value::TimestampNanosecond(1),
+                ColumnDataType::TimestampMillisecond
+            ),
+            Coercion::Narrowing
+        );
+        assert_eq!(
+            classify(&Value::String("a".to_string()), ColumnDataType::Json),
+            Coercion::Widening
+        );
+        assert_eq!(
+            classify(&Value::String("a".to_string()), ColumnDataType::Int64),
+            Coercion::Incompatible
+        );
+        assert_eq!(
+            classify(&Value::Boolean(true), ColumnDataType::Int8),
+            Coercion::Incompatible
+        );
+    }
+
+    #[test]
+    fn test_dry_run_coerce() {
+        let schema = TableSchema::builder()
+            .name("metrics")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String)
+            .add_field("value", ColumnDataType::Float32);
+
+        let rows = vec![
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1),
+                Value::String("a".to_string()),
+                Value::Float32(1.0),
+            ]),
+            Row::from_values(vec![
+                Value::TimestampSecond(2),
+                Value::Null,
+                Value::Float64(2.0),
+            ]),
+            Row::from_values(vec![
+                Value::TimestampNanosecond(3),
+                Value::Int64(1),
+                Value::Int8(3),
+            ]),
+            // Short row: `value` is missing
+            Row::from_values(vec![Value::TimestampMillisecond(4), Value::Boolean(true)]),
+        ];
+
+        let report = dry_run_coerce(&rows, &schema);
+        assert_eq!(report.columns.len(), 3);
+        assert!(report.has_failures());
+        assert!(report.has_narrowing());
+
+        let ts = report.column("ts").unwrap();
+        assert_eq!(
+            (ts.exact, ts.widening, ts.narrowing, ts.failures),
+            (2, 1, 1, 0)
+        );
+
+        let host = report.column("host").unwrap();
+        assert_eq!(
+            (host.exact, host.widening, host.narrowing, host.failures),
+            (2, 0, 0, 2)
+        );
+        assert_eq!(host.failure_examples, vec![2, 3]);
+
+        let value = report.column("value").unwrap();
+        assert_eq!(
+            (value.exact, value.widening, value.narrowing, value.failures),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(value.failure_examples, vec![3]);
+    }
+}
@@ -14,8 +14,12 @@
 
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use arrow_flight::FlightData;
+use arrow_array::RecordBatch;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::error::FlightError;
+use arrow_flight::{FlightData, Ticket};
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use futures::future;
@@ -23,23 +27,84 @@ use futures_util::{Stream, StreamExt, TryStreamExt};
 use greptime_proto::v1::auth_header::AuthScheme;
 use greptime_proto::v1::greptime_database_client::GreptimeDatabaseClient;
 use greptime_proto::v1::greptime_request::Request;
+use greptime_proto::v1::query_request::Query;
 use greptime_proto::v1::{
     greptime_response, AffectedRows, AuthHeader, Basic, DeleteRequests, GreptimeRequest,
-    RequestHeader, RowInsertRequests,
+    QueryRequest, RequestHeader, RowDeleteRequests, RowInsertRequests, SemanticType, Token,
 };
-use snafu::{OptionExt, ResultExt};
+use prost::Message;
+use snafu::{ensure, OptionExt, ResultExt};
 use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap, MetadataValue};
 use tonic::transport::Channel;
 
 use crate::client::Client;
 use crate::error::{self, IllegalDatabaseResponseSnafu};
 use crate::flight::do_put::DoPutResponse;
-use crate::Result;
+use crate::{Error, Result};
 
 type FlightDataStream = Pin<Box<dyn Stream<Item = FlightData> + Send>>;
 
 type DoPutResponseStream = Pin<Box<dyn Stream<Item = Result<DoPutResponse>>>>;
 
+/// Bulk inserts through Arrow Flight's "`DoPut`" arrived in GreptimeDB v0.15
+const BULK_INSERT_FEATURE: &str = "Bulk insert (Arrow Flight DoPut)";
+const BULK_INSERT_MIN_VERSION: &str = "v0.15";
+
+/// How a write treats rows with the same primary key and timestamp as an existing row
+///
+/// The mode is sent as a hint with every request and only takes effect on the server
+/// when the write creates the table: an existing table keeps the mode it was created
+/// with, through its `append_mode` table option. Upserting into an append-mode table
+/// keeps every duplicate, and appending to a regular table still merges them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Keep every row, including duplicates, as tables with `append_mode = 'true'` do
+    Append,
+    /// Replace the row with the same primary key and timestamp, the default of regular
+    /// tables
+    ///
+    /// As rows are identified by their primary key, the client rejects rows with a
+    /// null tag column before sending them.
+    Upsert,
+}
+
+impl WriteMode {
+    /// The request hint declaring this mode
+    pub fn hint(self) -> (&'static str, &'static str) {
+        match self {
+            WriteMode::Append => ("append_mode", "true"),
+            WriteMode::Upsert => ("append_mode", "false"),
+        }
+    }
+}
+
+/// Supplies the credentials sent with the requests of a [`Database`]
+///
+/// The provider is asked for every request and every (re)opened bulk stream, so a
+/// provider returning the current token of a refreshed, short-lived credential rotates
+/// it without rebuilding the client. It runs on the request path: return a cached
+/// value and refresh it elsewhere. Closures returning an [`AuthScheme`] implement it.
+pub trait AuthProvider: Send + Sync {
+    /// The credentials to send with the next request
+    fn auth_scheme(&self) -> AuthScheme;
+}
+
+impl<F> AuthProvider for F
+where
+    F: Fn() -> AuthScheme + Send + Sync,
+{
+    fn auth_scheme(&self) -> AuthScheme {
+        self()
+    }
+}
+
+impl std::fmt::Debug for dyn AuthProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the credentials
+        f.write_str("AuthProvider")
+    }
+}
+
 /// The Client for GreptimeDB Database API.
 #[derive(Clone, Debug, Default)]
 pub struct Database {
@@ -48,15 +113,15 @@ pub struct Database {
     dbname: String,
 
     client: Client,
-    auth_header: Option<AuthHeader>,
+    auth: Option<Arc<dyn AuthProvider>>,
 }
 
 pub struct DatabaseClient {
     pub inner: GreptimeDatabaseClient<Channel>,
 }
 
-fn make_database_client(client: &Client) -> Result<DatabaseClient> {
-    let (_, channel) = client.find_channel()?;
+fn make_database_client(client: &Client) -> Result<(String, DatabaseClient)> {
+    let (addr, channel) = client.find_channel()?;
     let mut inner = GreptimeDatabaseClient::new(channel)
         .max_decoding_message_size(client.max_grpc_recv_message_size())
         .max_encoding_message_size(client.max_grpc_send_message_size());
@@ -66,7 +131,7 @@ fn make_database_client(client: &Client) -> Result<DatabaseClient> {
     if let Some(accept_compression) = client.accept_compression() {
         inner = inner.accept_compressed(accept_compression);
     }
-    Ok(DatabaseClient { inner })
+    Ok((addr, DatabaseClient { inner }))
 }
 
 impl Database {
@@ -81,10 +146,15 @@ impl Database {
         Self {
             dbname: dbname.into(),
             client,
-            auth_header: None,
+            auth: None,
         }
     }
 
+    /// Get the underlying client
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
     /// Get associated dbname of this client
     pub fn dbname(&self) -> &String {
         &self.dbname
@@ -97,9 +167,36 @@ impl Database {
 
     /// Set authentication information
     pub fn set_auth(&mut self, auth: AuthScheme) {
-        self.auth_header = Some(AuthHeader {
-            auth_scheme: Some(auth),
-        });
+        self.set_auth_provider(move || auth.clone());
+    }
+
+    /// Fetch the credentials of every request from `provider`, see [`AuthProvider`]
+    pub fn set_auth_provider(&mut self, provider: impl AuthProvider + 'static) {
+        self.auth = Some(Arc::new(provider));
+    }
+
+    /// Authenticate every insert, delete, query and bulk write with `auth`
+    ///
+    /// Bulk writers created from this database send the credentials when they open
+    /// their stream.
+    #[must_use]
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.set_auth(auth);
+        self
+    }
+
+    /// Authenticate with the credentials of `provider`, fetched for every request
+    #[must_use]
+    pub fn with_auth_provider(mut self, provider: impl AuthProvider + 'static) -> Self {
+        self.set_auth_provider(provider);
+        self
+    }
+
+    /// The current credentials, asking the provider if any
+    fn auth_header(&self) -> Option<AuthHeader> {
+        self.auth.as_ref().map(|provider| AuthHeader {
+            auth_scheme: Some(provider.auth_scheme()),
+        })
     }
 
     /// Write Row based insert requests to GreptimeDB and get rows written
@@ -116,25 +213,86 @@ impl Database {
         self.handle(Request::RowInserts(requests), hints).await
     }
 
+    /// Write Row based insert requests declaring `mode`, see [`WriteMode`]
+    ///
+    /// With [`WriteMode::Upsert`], every row must have a value in each tag column,
+    /// otherwise nothing is sent and the first offending row is reported.
+    pub async fn insert_with_mode(
+        &self,
+        requests: RowInsertRequests,
+        mode: WriteMode,
+    ) -> Result<u32> {
+        if mode == WriteMode::Upsert {
+            validate_primary_keys(&requests)?;
+        }
+        self.insert_with_hints(requests, &[mode.hint()]).await
+    }
+
+    /// Write Row based insert requests into `dbname` instead of this handle's database
+    ///
+    /// The override applies to this call only, over the same connection; the default
+    /// dbname of the handle is left unchanged for every other call.
+    pub async fn insert_into_db(&self, dbname: &str, requests: RowInsertRequests) -> Result<u32> {
+        validate_dbname(dbname)?;
+        self.handle_in(dbname, Request::RowInserts(requests), &[])
+            .await
+    }
+
     /// Issue a delete to database
     pub async fn delete(&self, request: DeleteRequests) -> Result<u32> {
         self.handle(Request::Deletes(request), &[]).await
     }
 
+    /// Delete rows given in the same row based shape as inserts and get rows deleted
+    ///
+    /// Rows are identified by their primary key and time index, so each request only
+    /// needs the tag columns and the timestamp column; field columns are ignored.
+    /// Every row matching the given tags and timestamp is deleted, see
+    /// [`TableSchema::into_delete_request`](crate::table::TableSchema::into_delete_request).
+    pub async fn delete_rows(&self, requests: RowDeleteRequests) -> Result<u32> {
+        self.handle(Request::RowDeletes(requests), &[]).await
+    }
+
+    /// Run a SQL statement and collect the resulting [RecordBatch]es
+    ///
+    /// The query is sent through Arrow Flight's "`DoGet`" method, so results are
+    /// streamed in Arrow format rather than as rows.
+    pub async fn sql(&self, sql: impl Into<String>) -> Result<Vec<RecordBatch>> {
+        let request = self.to_rpc_request(Request::Query(QueryRequest {
+            query: Some(Query::Sql(sql.into())),
+        }));
+        let ticket = Ticket {
+            ticket: request.encode_to_vec().into(),
+        };
+
+        let mut client = self.client.make_flight_client()?;
+        let addr = client.addr().to_string();
+        let response = self
+            .client
+            .observe(&addr, client.mut_inner().do_get(ticket).await)?;
+        let stream = response.into_inner().map_err(FlightError::from);
+        FlightRecordBatchStream::new_from_flight_data(stream)
+            .try_collect()
+            .await
+            .context(error::DecodeFlightDataSnafu)
+    }
+
     /// Ingest a stream of [RecordBatch]es that belong to a table, using Arrow Flight's "`DoPut`"
     /// method. The return value is also a stream, produces [DoPutResponse]s.
     pub async fn do_put(&self, stream: FlightDataStream) -> Result<DoPutResponseStream> {
-        let mut request = tonic::Request::new(stream);
+        self.do_put_with_hints(stream, &[]).await
+    }
 
-        if let Some(AuthHeader {
-            auth_scheme: Some(AuthScheme::Basic(Basic { username, password })),
-        }) = &self.auth_header
-        {
-            let auth_str = format!("Basic {username}:{password}");
-            let encoded = BASE64_STANDARD.encode(auth_str);
-            let value =
-                MetadataValue::from_str(&encoded).context(error::InvalidTonicMetadataValueSnafu)?;
-            request.metadata_mut().insert("x-greptime-auth", value);
+    /// Like [`Database::do_put`], sending `hints` along with the stream
+    pub async fn do_put_with_hints(
+        &self,
+        stream: FlightDataStream,
+        hints: &[(&str, &str)],
+    ) -> Result<DoPutResponseStream> {
+        let mut request = tonic::Request::new(stream);
+        Self::put_hints(request.metadata_mut(), hints)?;
+        if let Some(auth_header) = self.auth_header() {
+            Self::put_auth(request.metadata_mut(), &auth_header)?;
         }
 
         request.metadata_mut().insert(
@@ -143,27 +301,41 @@ impl Database {
         );
 
         let mut client = self.client.make_flight_client()?;
-        let response = client.mut_inner().do_put(request).await?;
+        let addr = client.addr().to_string();
+        let response = self
+            .client
+            .observe(&addr, client.mut_inner().do_put(request).await)
+            .map_err(bulk_insert_error)?;
+        // A stream broken mid-way also marks the peer, so reopening it reconnects
+        let observer = self.client.clone();
         let response = response
             .into_inner()
-            .map_err(Into::into)
+            .map_err(move |status| bulk_insert_error(observer.observe_status(&addr, status)))
             .and_then(|x| future::ready(DoPutResponse::try_from(x)))
             .boxed();
         Ok(response)
     }
 
     async fn handle(&self, request: Request, hints: &[(&str, &str)]) -> Result<u32> {
-        let mut client = make_database_client(&self.client)?;
-        let request = self.to_rpc_request(request);
+        self.handle_in(&self.dbname, request, hints).await
+    }
+
+    async fn handle_in(
+        &self,
+        dbname: &str,
+        request: Request,
+        hints: &[(&str, &str)],
+    ) -> Result<u32> {
+        let (addr, mut client) = make_database_client(&self.client)?;
+        let request = self.to_rpc_request_in(dbname, request);
         let mut request = tonic::Request::new(request);
         if !hints.is_empty() {
             Self::put_hints(request.metadata_mut(), hints)?;
         }
 
-        let response = client
-            .inner
-            .handle(request)
-            .await?
+        let response = self
+            .client
+            .observe(&addr, client.inner.handle(request).await)?
             .into_inner()
             .response
             .context(IllegalDatabaseResponseSnafu {
@@ -175,16 +347,36 @@ impl Database {
 
     #[inline]
     fn to_rpc_request(&self, request: Request) -> GreptimeRequest {
+        self.to_rpc_request_in(&self.dbname, request)
+    }
+
+    #[inline]
+    fn to_rpc_request_in(&self, dbname: &str, request: Request) -> GreptimeRequest {
         GreptimeRequest {
             header: Some(RequestHeader {
-                authorization: self.auth_header.clone(),
-                dbname: self.dbname.clone(),
+                authorization: self.auth_header(),
+                dbname: dbname.to_string(),
                 ..Default::default()
             }),
             request: Some(request),
         }
     }
 
+    /// Bulk writes carry no request header, so their credentials go into metadata
+    fn put_auth(metadata: &mut MetadataMap, auth_header: &AuthHeader) -> Result<()> {
+        let value = match &auth_header.auth_scheme {
+            Some(AuthScheme::Basic(Basic { username, password })) => {
+                BASE64_STANDARD.encode(format!("Basic {username}:{password}"))
+            }
+            Some(AuthScheme::Token(Token { token })) => format!("Bearer {token}"),
+            None => return Ok(()),
+        };
+        let value =
+            MetadataValue::from_str(&value).context(error::InvalidTonicMetadataValueSnafu)?;
+        metadata.insert("x-greptime-auth", value);
+        Ok(())
+    }
+
     fn put_hints(metadata: &mut MetadataMap, hints: &[(&str, &str)]) -> Result<()> {
         if hints.is_empty() {
             return Ok(());
@@ -200,3 +392,203 @@ impl Database {
         Ok(())
     }
 }
+
+/// Convert the status of a bulk insert RPC, explaining `Unimplemented` from older servers
+fn bulk_insert_error(status: tonic::Status) -> Error {
+    Error::from_feature_status(status, BULK_INSERT_FEATURE, BULK_INSERT_MIN_VERSION)
+}
+
+/// Check that every row of `requests` has a value in each tag column
+fn validate_primary_keys(requests: &RowInsertRequests) -> Result<()> {
+    for request in &requests.inserts {
+        let Some(rows) = &request.rows else {
+            continue;
+        };
+        for (index, column) in rows.schema.iter().enumerate() {
+            if column.semantic_type != SemanticType::Tag as i32 {
+                continue;
+            }
+            let missing = rows.rows.iter().position(|row| {
+                row.values
+                    .get(index)
+                    .is_none_or(|value| value.value_data.is_none())
+            });
+            if let Some(row) = missing {
+                return error::MissingPrimaryKeySnafu {
+                    table: &request.table_name,
+                    column: &column.column_name,
+                    row,
+                }
+                .fail();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that `dbname` can be sent as a database name
+fn validate_dbname(dbname: &str) -> Result<()> {
+    ensure!(
+        !dbname.is_empty() && !dbname.chars().any(|c| c.is_whitespace() || c.is_control()),
+        error::InvalidDatabaseNameSnafu { dbname }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbname_override_is_per_request() {
+        let database = Database::new_with_dbname("public", Client::new());
+        let dbname_of = |request: GreptimeRequest| request.header.unwrap().dbname;
+
+        let overridden = database
+            .to_rpc_request_in("metrics", Request::RowInserts(RowInsertRequests::default()));
+        assert_eq!(dbname_of(overridden), "metrics");
+
+        let default = database.to_rpc_request(Request::RowInserts(RowInsertRequests::default()));
+        assert_eq!(dbname_of(default), "public");
+        assert_eq!(database.dbname(), "public");
+    }
+
+    #[test]
+    fn test_auth_provider_is_asked_per_request() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let refreshes = Arc::new(AtomicU32::new(0));
+        let counter = refreshes.clone();
+        let database =
+            Database::new_with_dbname("public", Client::new()).with_auth_provider(move || {
+                let n = counter.fetch_add(1, Ordering::Relaxed);
+                AuthScheme::Token(Token {
+                    token: format!("token-{n}"),
+                })
+            });
+        let token_of = |request: GreptimeRequest| match request
+            .header
+            .unwrap()
+            .authorization
+            .unwrap()
+            .auth_scheme
+        {
+            Some(AuthScheme::Token(Token { token })) => token,
+            other => panic!("expected a token, got {other:?}"),
+        };
+
+        let request = Request::RowInserts(RowInsertRequests::default());
+        assert_eq!(
+            token_of(database.to_rpc_request(request.clone())),
+            "token-0"
+        );
+        assert_eq!(token_of(database.to_rpc_request(request)), "token-1");
+        assert_eq!(refreshes.load(Ordering::Relaxed), 2);
+
+        let unauthenticated = Database::new_with_dbname("public", Client::new());
+        let request = unauthenticated.to_rpc_request(Request::RowInserts(Default::default()));
+        assert!(request.header.unwrap().authorization.is_none());
+    }
+
+    #[test]
+    fn test_put_auth_metadata() {
+        let auth_value = |auth_scheme| {
+            let mut metadata = MetadataMap::new();
+            let auth_header = AuthHeader {
+                auth_scheme: Some(auth_scheme),
+            };
+            Database::put_auth(&mut metadata, &auth_header).unwrap();
+            metadata
+                .get("x-greptime-auth")
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let basic = auth_value(AuthScheme::Basic(Basic {
+            username: "greptime".to_string(),
+            password: "secret".to_string(),
+        }));
+        assert_eq!(
+            BASE64_STANDARD.decode(basic).unwrap(),
+            b"Basic greptime:secret"
+        );
+        let token = auth_value(AuthScheme::Token(Token {
+            token: "abc".to_string(),
+        }));
+        assert_eq!(token, "Bearer abc");
+    }
+
+    #[tokio::test]
+    async fn test_unimplemented_bulk_insert_is_unsupported_by_server() {
+        // A stub response stream of a server that predates bulk inserts
+        let responses = futures::stream::iter(vec![
+            Err(tonic::Status::unimplemented("unknown method DoPut")),
+            Err(tonic::Status::unavailable("server is down")),
+        ]);
+        let errors: Vec<Error> = responses
+            .map_err(bulk_insert_error)
+            .map(|result: Result<()>| result.unwrap_err())
+            .collect()
+            .await;
+
+        let Error::UnsupportedByServer {
+            feature,
+            min_version,
+            ..
+        } = &errors[0]
+        else {
+            panic!("expected UnsupportedByServer, got {:?}", errors[0]);
+        };
+        assert_eq!(feature, BULK_INSERT_FEATURE);
+        assert_eq!(min_version, BULK_INSERT_MIN_VERSION);
+        assert!(errors[0].to_string().contains("upgrade the server"));
+        assert!(!errors[0].is_retriable());
+
+        assert!(matches!(errors[1], Error::Server { .. }));
+    }
+
+    #[test]
+    fn test_upsert_requires_primary_keys() {
+        use greptime_proto::v1::value::ValueData;
+        use greptime_proto::v1::{ColumnSchema, Row, RowInsertRequest, Rows, Value};
+
+        let host = |value: Option<&str>| Value {
+            value_data: value.map(|host| ValueData::StringValue(host.to_string())),
+        };
+        let requests = |hosts: Vec<Option<&str>>| RowInsertRequests {
+            inserts: vec![RowInsertRequest {
+                table_name: "cpu".to_string(),
+                rows: Some(Rows {
+                    schema: vec![ColumnSchema {
+                        column_name: "host".to_string(),
+                        semantic_type: SemanticType::Tag as i32,
+                        ..Default::default()
+                    }],
+                    rows: hosts
+                        .into_iter()
+                        .map(|value| Row {
+                            values: vec![host(value)],
+                        })
+                        .collect(),
+                }),
+            }],
+        };
+
+        assert!(validate_primary_keys(&requests(vec![Some("a"), Some("b")])).is_ok());
+        let error = validate_primary_keys(&requests(vec![Some("a"), None])).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::MissingPrimaryKey { ref column, row: 1, .. } if column == "host"
+        ));
+    }
+
+    #[test]
+    fn test_validate_dbname() {
+        assert!(validate_dbname("greptime-metrics").is_ok());
+        assert!(validate_dbname("").is_err());
+        assert!(validate_dbname("my db").is_err());
+        assert!(validate_dbname("db\n").is_err());
+    }
+}
@@ -14,9 +14,11 @@
 
 use std::io;
 
-use snafu::{Location, Snafu};
+use snafu::{IntoError, Location, Snafu};
 use tonic::{metadata::errors::InvalidMetadataValue, Status};
 
+use crate::api::v1::ColumnDataType;
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
 pub enum Error {
@@ -57,7 +59,33 @@ pub enum Error {
 
     // Server error carried in Tonic Status's metadata.
     #[snafu(display("{}", msg))]
-    Server { status: Box<Status>, msg: String },
+    Server {
+        #[snafu(source)]
+        status: BoxedStatus,
+        msg: String,
+    },
+
+    #[snafu(display("Invalid database name: {:?}", dbname))]
+    InvalidDatabaseName {
+        dbname: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "{} is not supported by the server, it requires GreptimeDB {} or later; \
+         upgrade the server or fall back to the regular insert API",
+        feature,
+        min_version
+    ))]
+    UnsupportedByServer {
+        feature: String,
+        min_version: String,
+        #[snafu(source(from(Status, BoxedStatus::from)))]
+        status: BoxedStatus,
+        #[snafu(implicit)]
+        location: Location,
+    },
 
     #[snafu(display("Illegal Database response: {err_msg}"))]
     IllegalDatabaseResponse {
@@ -97,6 +125,14 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Invalid schema of column '{}': {}", column, reason))]
+    InvalidColumnSchema {
+        column: String,
+        reason: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Failed to serialize metadata"))]
     SerializeMetadata {
         #[snafu(source)]
@@ -140,6 +176,13 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Request {} was not acknowledged", request_id))]
+    AckDropped {
+        request_id: i64,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display(
         "Schema mismatch: BulkStreamWriter expects schema {} but got {}",
         expected,
@@ -160,6 +203,107 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display(
+        "Invalid value for column `{}`: expected {:?}, got {}",
+        column,
+        expected,
+        actual
+    ))]
+    InvalidColumnValue {
+        column: String,
+        expected: ColumnDataType,
+        actual: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Unknown column `{}`", column))]
+    UnknownColumn {
+        column: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Table {} is not a metric table: expected one field and one timestamp column, got {} and {}",
+        table,
+        fields,
+        timestamps
+    ))]
+    InvalidMetricSchema {
+        table: String,
+        fields: usize,
+        timestamps: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Table {} needs exactly one timestamp column, found {:?}",
+        table,
+        columns
+    ))]
+    InvalidTimeIndex {
+        table: String,
+        columns: Vec<String>,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Table {} can have at most one primary value column, found {:?}",
+        table,
+        columns
+    ))]
+    MultiplePrimaryValues {
+        table: String,
+        columns: Vec<String>,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Empty name of column {}", index))]
+    EmptyColumnName {
+        index: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Duplicate column name `{}`", column))]
+    DuplicateColumn {
+        column: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Too many values, the schema has {} columns", column_count))]
+    TooManyValues {
+        column_count: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Row {} of table '{}' has no value for primary key column '{}', which upserts require",
+        row,
+        table,
+        column
+    ))]
+    MissingPrimaryKey {
+        table: String,
+        column: String,
+        row: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Value for column '{}' is set twice", column))]
+    ColumnAlreadySet {
+        column: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Invalid column index: {}, total columns: {}", index, total))]
     InvalidColumnIndex {
         index: usize,
@@ -168,6 +312,148 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display(
+        "Implausible timestamp {} in column `{}` for unit {:?}, expected a value in [{}, {}]",
+        value,
+        column,
+        unit,
+        min,
+        max
+    ))]
+    ImplausibleTimestamp {
+        column: String,
+        value: i64,
+        unit: arrow_schema::TimeUnit,
+        min: i64,
+        max: i64,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Value {} is out of range for {}", value, target))]
+    ValueOutOfRange {
+        value: String,
+        target: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid {} value: {}", encoding, reason))]
+    InvalidBinaryEncoding {
+        encoding: String,
+        reason: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Null value in row {} of non-nullable column `{}`", row, column))]
+    NullInNonNullableColumn {
+        column: String,
+        row: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to open file {}", path))]
+    OpenFile {
+        path: String,
+        source: io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to decode records"))]
+    DecodeRecords {
+        #[snafu(source)]
+        error: arrow_schema::ArrowError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[cfg(feature = "parquet")]
+    #[snafu(display("Failed to write Parquet file"))]
+    WriteParquet {
+        #[snafu(source)]
+        error: parquet::errors::ParquetError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to spill rows to {}", path))]
+    SpillRows {
+        path: String,
+        #[snafu(source)]
+        error: arrow_schema::ArrowError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to decode Flight data"))]
+    DecodeFlightData {
+        #[snafu(source)]
+        error: arrow_flight::error::FlightError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Row count mismatch in table {}: expected {}, got {}",
+        table,
+        expected,
+        actual
+    ))]
+    RowCountMismatch {
+        table: String,
+        expected: u64,
+        actual: u64,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Cannot render column `{}` as line protocol: {}", column, reason))]
+    LineProtocol {
+        column: String,
+        reason: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Record batch column {} does not match the table schema: expected {}, got {}",
+        index,
+        expected,
+        actual
+    ))]
+    RecordBatchColumnMismatch {
+        index: usize,
+        expected: String,
+        actual: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid line protocol `{}`: {}", line, reason))]
+    ParseLineProtocol {
+        line: String,
+        reason: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Column `{}` has {} values, expected {} like the other columns",
+        column,
+        actual,
+        expected
+    ))]
+    ColumnLengthMismatch {
+        column: String,
+        expected: usize,
+        actual: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Cannot write empty rows"))]
     EmptyRows {
         #[snafu(implicit)]
@@ -177,6 +463,63 @@ pub enum Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Broad category of an [`Error`], to decide how to handle it without matching on
+/// every variant, see [`Error::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The server could not be reached or did not answer in time; the request may
+    /// succeed on another attempt
+    Transport,
+    /// The server answered with an error or an unexpected response, see
+    /// [`Error::status_code`]
+    Server,
+    /// Rows, values or schemas rejected on the client before anything was sent,
+    /// including input that could not be parsed into rows
+    Schema,
+    /// Encoding, compressing or decoding Arrow and Flight data failed
+    Encoding,
+    /// The stream or channel of a bulk writer was closed before a request completed
+    Closed,
+    /// Invalid client configuration, such as TLS settings or a database name
+    Config,
+    /// Reading or writing local files failed
+    Io,
+}
+
+/// A [`Status`] kept on the heap so that [`Error`] stays small
+///
+/// It dereferences to the status, and the errors carrying it return the status
+/// itself as their `source`, so it can be downcast to [`Status`].
+#[derive(Debug)]
+pub struct BoxedStatus(Box<Status>);
+
+impl BoxedStatus {
+    pub fn into_inner(self) -> Status {
+        *self.0
+    }
+}
+
+impl From<Status> for BoxedStatus {
+    fn from(status: Status) -> Self {
+        Self(Box::new(status))
+    }
+}
+
+impl std::ops::Deref for BoxedStatus {
+    type Target = Status;
+
+    fn deref(&self) -> &Status {
+        &self.0
+    }
+}
+
+impl snafu::AsErrorSource for BoxedStatus {
+    fn as_error_source(&self) -> &(dyn std::error::Error + 'static) {
+        &*self.0
+    }
+}
+
 pub const INNER_ERROR_MSG: &str = "INNER_ERROR_MSG";
 
 impl From<Status> for Error {
@@ -190,20 +533,196 @@ impl From<Status> for Error {
         let msg = get_metadata_value(&e, INNER_ERROR_MSG).unwrap_or(e.to_string());
 
         Self::Server {
-            status: Box::new(e),
+            status: e.into(),
             msg,
         }
     }
 }
 
 impl Error {
+    /// Convert the status of an RPC used by `feature`, telling an `Unimplemented`
+    /// status of a server older than `min_version` apart from other failures
+    pub(crate) fn from_feature_status(status: Status, feature: &str, min_version: &str) -> Self {
+        if status.code() == tonic::Code::Unimplemented {
+            UnsupportedByServerSnafu {
+                feature,
+                min_version,
+            }
+            .into_error(status)
+        } else {
+            status.into()
+        }
+    }
+
+    /// The category of the error
+    ///
+    /// Server errors with an `Unavailable` or `DeadlineExceeded` status are
+    /// [`ErrorKind::Transport`]: they tell the server could not be reached or did not
+    /// answer, not that it rejected the request.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::CreateChannel { .. } | Self::RequestTimeout { .. } => ErrorKind::Transport,
+            Self::Server { status, .. } => match status.code() {
+                tonic::Code::Unavailable | tonic::Code::DeadlineExceeded => ErrorKind::Transport,
+                _ => ErrorKind::Server,
+            },
+            Self::MissingField { .. }
+            | Self::UnsupportedByServer { .. }
+            | Self::IllegalDatabaseResponse { .. }
+            | Self::RowCountMismatch { .. } => ErrorKind::Server,
+            Self::UnsupportedDataType { .. }
+            | Self::InvalidColumnSchema { .. }
+            | Self::SchemaMismatch { .. }
+            | Self::InvalidColumnCount { .. }
+            | Self::InvalidColumnValue { .. }
+            | Self::UnknownColumn { .. }
+            | Self::InvalidMetricSchema { .. }
+            | Self::InvalidTimeIndex { .. }
+            | Self::MultiplePrimaryValues { .. }
+            | Self::EmptyColumnName { .. }
+            | Self::DuplicateColumn { .. }
+            | Self::TooManyValues { .. }
+            | Self::MissingPrimaryKey { .. }
+            | Self::ColumnAlreadySet { .. }
+            | Self::InvalidColumnIndex { .. }
+            | Self::ImplausibleTimestamp { .. }
+            | Self::ValueOutOfRange { .. }
+            | Self::InvalidBinaryEncoding { .. }
+            | Self::NullInNonNullableColumn { .. }
+            | Self::LineProtocol { .. }
+            | Self::RecordBatchColumnMismatch { .. }
+            | Self::ParseLineProtocol { .. }
+            | Self::ColumnLengthMismatch { .. }
+            | Self::EmptyRows { .. } => ErrorKind::Schema,
+            Self::SerdeJson { .. }
+            | Self::CreateRecordBatch { .. }
+            | Self::SerializeMetadata { .. }
+            | Self::DecodeRecords { .. }
+            | Self::DecodeFlightData { .. } => ErrorKind::Encoding,
+            Self::SendData { .. }
+            | Self::StreamEnded { .. }
+            | Self::StreamEndedWithPendingRequests { .. }
+            | Self::AckDropped { .. } => ErrorKind::Closed,
+            Self::InvalidTlsConfig { .. }
+            | Self::InvalidConfigFilePath { .. }
+            | Self::IllegalGrpcClientState { .. }
+            | Self::InvalidDatabaseName { .. }
+            | Self::InvalidTonicMetadataValue { .. } => ErrorKind::Config,
+            Self::OpenFile { .. } | Self::SpillRows { .. } => ErrorKind::Io,
+            #[cfg(feature = "parquet")]
+            Self::WriteParquet { .. } => ErrorKind::Io,
+        }
+    }
+
+    /// The gRPC status code returned by the server, if the error carries one
+    pub fn status_code(&self) -> Option<tonic::Code> {
+        match self {
+            Self::Server { status, .. } | Self::UnsupportedByServer { status, .. } => {
+                Some(status.code())
+            }
+            _ => None,
+        }
+    }
+
     /// Indicate if the error is retriable
+    ///
+    /// Transport failures and closed streams are, as are server errors whose status
+    /// does not say the request itself is at fault. Schema, encoding, configuration
+    /// and I/O errors repeat on every attempt and are not.
     pub fn is_retriable(&self) -> bool {
-        !matches!(
-            self,
-            Self::InvalidTlsConfig { .. }
-                | Self::MissingField { .. }
-                | Self::InvalidConfigFilePath { .. }
-        )
+        match self.kind() {
+            ErrorKind::Transport | ErrorKind::Closed => true,
+            ErrorKind::Server => self.status_code().is_some_and(|code| {
+                !matches!(
+                    code,
+                    tonic::Code::InvalidArgument
+                        | tonic::Code::NotFound
+                        | tonic::Code::AlreadyExists
+                        | tonic::Code::PermissionDenied
+                        | tonic::Code::Unauthenticated
+                        | tonic::Code::FailedPrecondition
+                        | tonic::Code::OutOfRange
+                        | tonic::Code::Unimplemented
+                )
+            }),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use tonic::Code;
+
+    use super::*;
+    use crate::channel_manager::ChannelManager;
+
+    #[tokio::test]
+    async fn test_transport_error_source() {
+        let err = ChannelManager::new()
+            .get("not a valid address")
+            .unwrap_err();
+        assert!(matches!(err, Error::CreateChannel { .. }));
+
+        let source = err.source().expect("transport error should be chained");
+        assert!(source.downcast_ref::<tonic::transport::Error>().is_some());
+    }
+
+    #[test]
+    fn test_server_error_source() {
+        let err = Error::from(Status::unavailable("server is down"));
+
+        let source = err.source().expect("status should be chained");
+        let status = source
+            .downcast_ref::<Status>()
+            .expect("source should be the tonic status");
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(status.message(), "server is down");
+
+        let err = Error::from_feature_status(Status::unimplemented("no"), "bulk insert", "0.15");
+        assert!(matches!(err, Error::UnsupportedByServer { .. }));
+        let status = err
+            .source()
+            .and_then(|source| source.downcast_ref::<Status>())
+            .expect("source should be the tonic status");
+        assert_eq!(status.code(), Code::Unimplemented);
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let unavailable = Error::from(Status::unavailable("server is down"));
+        assert_eq!(unavailable.kind(), ErrorKind::Transport);
+        assert_eq!(unavailable.status_code(), Some(Code::Unavailable));
+        assert!(unavailable.is_retriable());
+
+        let internal = Error::from(Status::internal("region is busy"));
+        assert_eq!(internal.kind(), ErrorKind::Server);
+        assert!(internal.is_retriable());
+
+        let rejected = Error::from(Status::invalid_argument("column type mismatch"));
+        assert_eq!(rejected.kind(), ErrorKind::Server);
+        assert_eq!(rejected.to_string(), "column type mismatch");
+        assert!(!rejected.is_retriable());
+
+        let invalid = UnknownColumnSnafu { column: "host" }.build();
+        assert_eq!(invalid.kind(), ErrorKind::Schema);
+        assert_eq!(invalid.status_code(), None);
+        assert!(!invalid.is_retriable());
+
+        let closed = StreamEndedSnafu.build();
+        assert_eq!(closed.kind(), ErrorKind::Closed);
+        assert!(closed.is_retriable());
+
+        let config = InvalidDatabaseNameSnafu { dbname: "" }.build();
+        assert_eq!(config.kind(), ErrorKind::Config);
+        assert!(!config.is_retriable());
+    }
+
+    #[test]
+    fn test_leaf_error_has_no_source() {
+        let err = StreamEndedSnafu.build();
+        assert!(err.source().is_none());
     }
 }
@@ -12,9 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use arrow_flight::PutResult;
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
+use tonic::Code;
 
 use crate::error;
 
@@ -39,12 +42,18 @@ impl DoPutMetadata {
 }
 
 /// The response in the "DoPut" returned stream.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DoPutResponse {
     /// The same "request_id" in the request; see the [DoPutMetadata].
     request_id: i64,
     /// The successfully ingested rows number.
     affected_rows: usize,
+    /// The table the request was written to, filled in by the client.
+    #[serde(skip)]
+    table_name: Option<Arc<str>>,
+    /// The server's error detail if the request failed, filled in by the client.
+    #[serde(skip)]
+    error: Option<ResponseError>,
 }
 
 impl DoPutResponse {
@@ -52,9 +61,33 @@ impl DoPutResponse {
         Self {
             request_id,
             affected_rows,
+            table_name: None,
+            error: None,
         }
     }
 
+    /// Create the response of a request the server failed.
+    pub fn failed(request_id: i64, error: ResponseError) -> Self {
+        Self {
+            request_id,
+            affected_rows: 0,
+            table_name: None,
+            error: Some(error),
+        }
+    }
+
+    #[must_use]
+    pub fn with_table_name(mut self, table_name: Arc<str>) -> Self {
+        self.table_name = Some(table_name);
+        self
+    }
+
+    /// Add the affected rows of the batches sent before this one from the same buffer
+    pub(crate) fn with_earlier_affected_rows(mut self, affected_rows: usize) -> Self {
+        self.affected_rows += affected_rows;
+        self
+    }
+
     pub fn request_id(&self) -> i64 {
         self.request_id
     }
@@ -62,6 +95,44 @@ impl DoPutResponse {
     pub fn affected_rows(&self) -> usize {
         self.affected_rows
     }
+
+    /// The table the request was written to, if known.
+    pub fn table_name(&self) -> Option<&str> {
+        self.table_name.as_deref()
+    }
+
+    /// The server's error detail if the request failed.
+    pub fn error(&self) -> Option<&ResponseError> {
+        self.error.as_ref()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The status code and message of a failed "DoPut" request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseError {
+    code: Code,
+    message: String,
+}
+
+impl ResponseError {
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 impl TryFrom<PutResult> for DoPutResponse {
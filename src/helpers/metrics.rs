@@ -0,0 +1,180 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rows for metric tables, such as samples scraped from Prometheus
+//!
+//! A metric table holds one sample per row: a timestamp column, a single field
+//! column for the sample value and one tag column per label:
+//!
+//! ```text
+//! ts TIMESTAMP(3) TIME INDEX, job STRING TAG, instance STRING TAG, value DOUBLE FIELD
+//! ```
+
+use snafu::{ensure, OptionExt};
+
+use crate::api::v1::{ColumnDataType, SemanticType};
+use crate::table::{Row, TableSchema, Value};
+use crate::{error, Result};
+
+/// Build a row of the metric table `schema` from a sample
+///
+/// `timestamp_millis` is scaled to the unit of the timestamp column, and each label
+/// is placed in the tag column of the same name. Labels missing from `labels` are
+/// written as nulls. The schema must have one timestamp column and exactly one
+/// field column of type `Float64` for the value: either its only field column or
+/// the one marked with [`TableSchema::add_primary_field`], in which case the other
+/// fields are written as nulls. A label without a tag column is rejected.
+pub fn metric_row(
+    schema: &TableSchema,
+    value: f64,
+    timestamp_millis: i64,
+    labels: &[(&str, &str)],
+) -> Result<Row> {
+    let mut values = vec![Value::Null; schema.columns().len()];
+    let mut fields = 0;
+    let mut timestamps = 0;
+    let primary_value = schema.primary_value_index();
+
+    for (index, column) in schema.columns().iter().enumerate() {
+        match column.semantic_type {
+            SemanticType::Field if primary_value.is_some_and(|primary| primary != index) => {}
+            SemanticType::Field => {
+                ensure!(
+                    column.data_type == ColumnDataType::Float64,
+                    error::InvalidColumnValueSnafu {
+                        column: &column.name,
+                        expected: column.data_type,
+                        actual: "Float64",
+                    }
+                );
+                values[index] = Value::Float64(value);
+                fields += 1;
+            }
+            SemanticType::Timestamp => {
+                values[index] = timestamp_value(column.data_type, timestamp_millis)?;
+                timestamps += 1;
+            }
+            SemanticType::Tag => {}
+        }
+    }
+    ensure!(
+        fields == 1 && timestamps == 1,
+        error::InvalidMetricSchemaSnafu {
+            table: schema.name(),
+            fields,
+            timestamps,
+        }
+    );
+
+    for (name, label) in labels {
+        let index = schema
+            .columns()
+            .iter()
+            .position(|column| column.semantic_type == SemanticType::Tag && column.name == *name)
+            .context(error::UnknownColumnSnafu { column: *name })?;
+        values[index] = Value::String(label.to_string());
+    }
+
+    Ok(Row::from_values(values))
+}
+
+fn timestamp_value(data_type: ColumnDataType, millis: i64) -> Result<Value> {
+    let out_of_range = || {
+        error::ValueOutOfRangeSnafu {
+            value: millis.to_string(),
+            target: format!("{data_type:?}"),
+        }
+        .build()
+    };
+    Ok(match data_type {
+        ColumnDataType::TimestampSecond => Value::TimestampSecond(millis.div_euclid(1_000)),
+        ColumnDataType::TimestampMillisecond => Value::TimestampMillisecond(millis),
+        ColumnDataType::TimestampMicrosecond => {
+            Value::TimestampMicrosecond(millis.checked_mul(1_000).ok_or_else(out_of_range)?)
+        }
+        ColumnDataType::TimestampNanosecond => {
+            Value::TimestampNanosecond(millis.checked_mul(1_000_000).ok_or_else(out_of_range)?)
+        }
+        _ => return Err(out_of_range()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric_schema() -> TableSchema {
+        TableSchema::builder()
+            .name("http_requests_total")
+            .build()
+            .unwrap()
+            .add_tag("job", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMicrosecond)
+            .add_tag("instance", ColumnDataType::String)
+            .add_field("value", ColumnDataType::Float64)
+    }
+
+    #[test]
+    fn test_metric_row() {
+        let row = metric_row(
+            &metric_schema(),
+            42.5,
+            1_700_000_000_123,
+            &[("instance", "web-1:9100"), ("job", "node")],
+        )
+        .unwrap();
+
+        assert_eq!(row.get_string(0), Some("node".to_string()));
+        assert!(matches!(
+            row.value(1),
+            Some(Value::TimestampMicrosecond(1_700_000_000_123_000))
+        ));
+        assert_eq!(row.get_string(2), Some("web-1:9100".to_string()));
+        assert_eq!(row.get_f64(3), Some(42.5));
+
+        // Missing labels are nulls
+        let row = metric_row(&metric_schema(), 1.0, 0, &[("job", "node")]).unwrap();
+        assert_eq!(row.get_string(2), None);
+    }
+
+    #[test]
+    fn test_metric_row_rejects_unknown_labels_and_layouts() {
+        let unknown = metric_row(&metric_schema(), 1.0, 0, &[("pod", "a")]);
+        assert!(matches!(unknown, Err(crate::Error::UnknownColumn { .. })));
+
+        // A label can only go to a tag column
+        let field = metric_row(&metric_schema(), 1.0, 0, &[("value", "a")]);
+        assert!(matches!(field, Err(crate::Error::UnknownColumn { .. })));
+
+        let two_fields = metric_schema().add_field("other", ColumnDataType::Float64);
+        let result = metric_row(&two_fields, 1.0, 0, &[]);
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidMetricSchema { fields: 2, .. })
+        ));
+
+        // Marking the value field picks it among several fields
+        let primary = TableSchema::builder()
+            .name("http_requests_total")
+            .build()
+            .unwrap()
+            .add_tag("job", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("exemplar", ColumnDataType::String)
+            .add_primary_field("value", ColumnDataType::Float64);
+        let row = metric_row(&primary, 2.5, 0, &[]).unwrap();
+        assert_eq!(row.get_string(2), None);
+        assert_eq!(row.get_f64(3), Some(2.5));
+    }
+}
@@ -39,6 +39,18 @@ pub fn none_value() -> crate::api::v1::Value {
     crate::api::v1::Value { value_data: None }
 }
 
+/// A null value, the same as [`none_value`]
+#[inline]
+pub fn null_value() -> crate::api::v1::Value {
+    none_value()
+}
+
+/// A value for a JSON column, sent as its JSON text like GreptimeDB expects
+#[inline]
+pub fn json_value(v: impl Into<String>) -> crate::api::v1::Value {
+    string_value(v.into())
+}
+
 define_value_fn!(i8_value, i8, I8Value, i32);
 define_value_fn!(i16_value, i16, I16Value, i32);
 define_value_fn!(i32_value, i32, I32Value);
@@ -98,3 +110,42 @@ pub fn decimal128_value(v: i128) -> crate::api::v1::Value {
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::v1::value::ValueData;
+
+    use super::*;
+
+    #[test]
+    fn test_value_constructors() {
+        assert_eq!(null_value().value_data, None);
+        assert_eq!(
+            json_value(r#"{"a":1}"#).value_data,
+            Some(ValueData::StringValue(r#"{"a":1}"#.to_string()))
+        );
+        assert_eq!(
+            decimal128_value(-1).value_data,
+            Some(ValueData::Decimal128Value(Decimal128 { hi: -1, lo: -1 }))
+        );
+        assert_eq!(
+            date_value(19359).value_data,
+            Some(ValueData::DateValue(19359))
+        );
+        let times = [
+            (time_second_value(1), ValueData::TimeSecondValue(1)),
+            (
+                time_millisecond_value(1),
+                ValueData::TimeMillisecondValue(1),
+            ),
+            (
+                time_microsecond_value(1),
+                ValueData::TimeMicrosecondValue(1),
+            ),
+            (time_nanosecond_value(1), ValueData::TimeNanosecondValue(1)),
+        ];
+        for (value, expected) in times {
+            assert_eq!(value.value_data, Some(expected));
+        }
+    }
+}
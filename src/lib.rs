@@ -18,19 +18,27 @@ pub mod api;
 pub mod bulk;
 pub mod channel_manager;
 pub mod client;
+pub mod coercion;
 pub mod database;
 mod error;
 pub mod flight;
 pub mod helpers;
+pub mod line_protocol;
 pub mod load_balance;
+pub mod otel;
+pub mod resolver;
+pub mod retry;
 pub mod table;
 
 pub use self::channel_manager::{ChannelConfig, ChannelManager, ClientTlsOption};
-pub use self::error::{Error, Result};
+pub use self::database::{AuthProvider, WriteMode};
+pub use self::error::{BoxedStatus, Error, ErrorKind, Result};
 
 // Re-export bulk module components for easier access
 pub use self::bulk::{
-    BulkInserter, BulkStreamWriter, BulkWriteOptions, ColumnType, CompressionType, RowBuilder, Rows,
+    Ack, BulkInserter, BulkInserterBuilder, BulkMetricsSnapshot, BulkStreamWriter,
+    BulkWriteOptions, BulkWriteStats, CancelSummary, ColumnType, ColumnarBatch, CompressionType,
+    RowBuilder, Rows, SharedRowCollector, StreamResetInterval,
 };
 
 // Re-export arrow types for easier access
@@ -38,7 +46,11 @@ pub use arrow_array;
 pub use arrow_schema;
 
 // Re-export table module components for easier access
-pub use self::table::{Column, DataTypeExtension, Row, TableSchema, TableSchemaBuilder, Value};
+pub use self::table::{
+    random_row, BinaryEncoding, CaseMode, Column, ColumnOptions, DataTypeExtension, DecimalError,
+    DecimalErrorKind, FromValue, FulltextAnalyzer, FulltextOptions, Row, RowKey, SparseRow,
+    TableSchema, TableSchemaBuilder, TimestampRange, Value, ValueError,
+};
 
 // Re-export API types for direct access
 pub use self::api::v1::{ColumnDataType, SemanticType};
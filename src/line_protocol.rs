@@ -0,0 +1,758 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering and parsing of InfluxDB line protocol
+//!
+//! GreptimeDB accepts line protocol on its InfluxDB-compatible write endpoint,
+//! which lets collectors that only speak line protocol share a table schema with
+//! the gRPC ingestion paths. In the other direction, [`LineProtocolBatch`] turns
+//! existing line protocol data into table schemas and rows for this crate.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use snafu::{ensure, OptionExt};
+
+use crate::api::v1::{ColumnDataType, SemanticType};
+use crate::table::{Column, ColumnOptions, Row, TableSchema, Value};
+use crate::{error, Result};
+
+/// Name of the timestamp column of the tables built by [`LineProtocolBatch`], the
+/// same name GreptimeDB uses for tables created by its InfluxDB endpoint
+pub const TIMESTAMP_COLUMN: &str = "greptime_timestamp";
+
+/// A parsed line: measurement, tags, fields and timestamp in nanoseconds
+pub type ParsedLine = (String, Vec<(String, Value)>, Vec<(String, Value)>, i64);
+
+/// Render `rows` as line protocol for `measurement`, one line per row
+///
+/// Tag columns become tags, field columns become fields and the timestamp column
+/// becomes the line timestamp. Null tags and fields are left out, and a null or
+/// missing timestamp leaves the timestamp to the server. Timestamps are written in
+/// the unit of the timestamp column, so the write request must use the precision
+/// returned by [`precision`].
+///
+/// Binary and decimal values have no line protocol representation and are
+/// rejected, as are non-finite floats and rows without any non-null field.
+pub fn rows_to_line_protocol(
+    schema: &TableSchema,
+    measurement: &str,
+    rows: &[Row],
+) -> Result<String> {
+    let mut out = String::new();
+    for row in rows {
+        ensure!(
+            row.len() == schema.columns().len(),
+            error::InvalidColumnCountSnafu {
+                expected: schema.columns().len(),
+                actual: row.len(),
+            }
+        );
+        write_line(&mut out, schema, measurement, row)?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The line protocol precision matching the timestamp column of `schema`
+///
+/// Returns one of `s`, `ms`, `us` and `ns`, or `None` if the schema has no single
+/// timestamp column.
+pub fn precision(schema: &TableSchema) -> Option<&'static str> {
+    let column = schema.timestamp_column()?;
+    match column.data_type {
+        ColumnDataType::TimestampSecond => Some("s"),
+        ColumnDataType::TimestampMillisecond => Some("ms"),
+        ColumnDataType::TimestampMicrosecond => Some("us"),
+        ColumnDataType::TimestampNanosecond => Some("ns"),
+        _ => None,
+    }
+}
+
+fn write_line(out: &mut String, schema: &TableSchema, measurement: &str, row: &Row) -> Result<()> {
+    escape_into(out, measurement, &[',', ' ']);
+
+    for (index, column) in schema.columns().iter().enumerate() {
+        if column.semantic_type != SemanticType::Tag {
+            continue;
+        }
+        let Some(tag) = tag_value(&column.name, row.value(index))? else {
+            continue;
+        };
+        out.push(',');
+        escape_into(out, &column.name, &[',', '=', ' ']);
+        out.push('=');
+        escape_into(out, &tag, &[',', '=', ' ']);
+    }
+
+    let mut separator = ' ';
+    for (index, column) in schema.columns().iter().enumerate() {
+        if column.semantic_type != SemanticType::Field {
+            continue;
+        }
+        let Some(value) = row
+            .value(index)
+            .filter(|value| !matches!(value, Value::Null))
+        else {
+            continue;
+        };
+        out.push(separator);
+        separator = ',';
+        escape_into(out, &column.name, &[',', '=', ' ']);
+        out.push('=');
+        write_field_value(out, &column.name, value)?;
+    }
+    ensure!(
+        separator == ',',
+        error::LineProtocolSnafu {
+            column: measurement,
+            reason: "a line needs at least one non-null field",
+        }
+    );
+
+    let timestamp = schema
+        .timestamp_index()
+        .and_then(|index| row.get_timestamp(index));
+    if let Some(timestamp) = timestamp {
+        let _ = write!(out, " {timestamp}");
+    }
+    Ok(())
+}
+
+fn tag_value(column: &str, value: Option<&Value>) -> Result<Option<String>> {
+    let tag = match value {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(v)) | Some(Value::Json(v)) => v.clone(),
+        Some(Value::Boolean(v)) => v.to_string(),
+        Some(Value::Int8(v)) => v.to_string(),
+        Some(Value::Int16(v)) => v.to_string(),
+        Some(Value::Int32(v)) => v.to_string(),
+        Some(Value::Int64(v)) => v.to_string(),
+        Some(Value::Uint8(v)) => v.to_string(),
+        Some(Value::Uint16(v)) => v.to_string(),
+        Some(Value::Uint32(v)) => v.to_string(),
+        Some(Value::Uint64(v)) => v.to_string(),
+        Some(other) => {
+            return error::LineProtocolSnafu {
+                column,
+                reason: format!("{other:?} cannot be used as a tag"),
+            }
+            .fail()
+        }
+    };
+    // Tag values cannot be empty, so an empty string is treated like a null
+    Ok((!tag.is_empty()).then_some(tag))
+}
+
+fn write_field_value(out: &mut String, column: &str, value: &Value) -> Result<()> {
+    let float = match value {
+        Value::Float32(v) => Some(*v as f64),
+        Value::Float64(v) => Some(*v),
+        _ => None,
+    };
+    if let Some(v) = float {
+        ensure!(
+            v.is_finite(),
+            error::LineProtocolSnafu {
+                column,
+                reason: format!("{v} is not a finite float"),
+            }
+        );
+    }
+
+    let _ = match value {
+        Value::Boolean(v) => write!(out, "{v}"),
+        Value::Int8(v) => write!(out, "{v}i"),
+        Value::Int16(v) => write!(out, "{v}i"),
+        Value::Int32(v) | Value::Date(v) | Value::TimeSecond(v) | Value::TimeMillisecond(v) => {
+            write!(out, "{v}i")
+        }
+        Value::Int64(v)
+        | Value::Datetime(v)
+        | Value::TimestampSecond(v)
+        | Value::TimestampMillisecond(v)
+        | Value::TimestampMicrosecond(v)
+        | Value::TimestampNanosecond(v)
+        | Value::TimeMicrosecond(v)
+        | Value::TimeNanosecond(v) => write!(out, "{v}i"),
+        Value::Uint8(v) => write!(out, "{v}u"),
+        Value::Uint16(v) => write!(out, "{v}u"),
+        Value::Uint32(v) => write!(out, "{v}u"),
+        Value::Uint64(v) => write!(out, "{v}u"),
+        Value::Float32(v) => write!(out, "{v}"),
+        Value::Float64(v) => write!(out, "{v}"),
+        Value::String(v) | Value::Json(v) => {
+            out.push('"');
+            escape_into(out, v, &['"', '\\']);
+            out.push('"');
+            Ok(())
+        }
+        Value::Binary(_) | Value::BinaryChunks(_) | Value::Decimal128(_) | Value::Null => {
+            return error::LineProtocolSnafu {
+                column,
+                reason: format!("{value:?} has no line protocol representation"),
+            }
+            .fail()
+        }
+    };
+    Ok(())
+}
+
+fn escape_into(out: &mut String, text: &str, special: &[char]) {
+    for c in text.chars() {
+        if special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Parse one line of line protocol
+///
+/// Tag values are returned as strings. Field values are `Int64` for an `i` suffix,
+/// `Uint64` for a `u` suffix, `String` for a quoted value, `Boolean` for `t`,
+/// `true`, `f`, `false` and their capitalized forms, and `Float64` otherwise.
+/// Timestamps are taken to be in nanoseconds, the line protocol default, and a
+/// line without one is stamped with the current time.
+pub fn parse_line(line: &str) -> Result<ParsedLine> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let fail = |reason: &str| {
+        error::ParseLineProtocolSnafu {
+            line,
+            reason: reason.to_string(),
+        }
+        .build()
+    };
+
+    let (series, rest) = take_until(line, &[' '], false);
+    let (measurement, mut tag_set) = take_until(series, &[','], false);
+    ensure!(
+        !measurement.is_empty(),
+        error::ParseLineProtocolSnafu {
+            line,
+            reason: "missing measurement",
+        }
+    );
+
+    let mut tags = Vec::new();
+    while let Some(rest) = tag_set.strip_prefix(',') {
+        let (pair, next) = take_until(rest, &[','], false);
+        let (key, value) = split_pair(pair).ok_or_else(|| fail("tag without a value"))?;
+        tags.push((unescape(key), Value::String(unescape(value))));
+        tag_set = next;
+    }
+
+    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+    let (mut field_set, rest) = take_until(rest, &[' '], true);
+    let mut fields = Vec::new();
+    while !field_set.is_empty() {
+        let (pair, next) = take_until(field_set, &[','], true);
+        let (key, value) = split_pair(pair).ok_or_else(|| fail("field without a value"))?;
+        let value = field_value(value).ok_or_else(|| fail("invalid field value"))?;
+        fields.push((unescape(key), value));
+        field_set = next.strip_prefix(',').unwrap_or(next);
+    }
+    ensure!(
+        !fields.is_empty(),
+        error::ParseLineProtocolSnafu {
+            line,
+            reason: "a line needs at least one field",
+        }
+    );
+
+    let timestamp = match rest.trim() {
+        "" => now_nanos(),
+        timestamp => timestamp
+            .parse()
+            .ok()
+            .with_context(|| error::ParseLineProtocolSnafu {
+                line,
+                reason: "invalid timestamp",
+            })?,
+    };
+
+    Ok((unescape(measurement), tags, fields, timestamp))
+}
+
+/// Accumulates lines of line protocol into one table per measurement
+///
+/// Each table has the tags of its lines as tag columns, a nanosecond timestamp
+/// column named [`TIMESTAMP_COLUMN`] and the fields as field columns, in the order
+/// they first appear. A line without some of the tags or fields of its measurement
+/// gets nulls in those columns.
+#[derive(Debug, Default)]
+pub struct LineProtocolBatch {
+    tables: Vec<MeasurementTable>,
+    index: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+struct MeasurementTable {
+    name: String,
+    tags: Vec<String>,
+    fields: Vec<(String, ColumnDataType)>,
+    lines: Vec<(Vec<(String, Value)>, Vec<(String, Value)>, i64)>,
+}
+
+impl LineProtocolBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse and add one line
+    ///
+    /// Fails if the line is malformed, if a field changes type within its
+    /// measurement, or if a name is used both as a tag and as a field.
+    pub fn add_line(&mut self, line: &str) -> Result<()> {
+        let (measurement, tags, fields, timestamp) = parse_line(line)?;
+        let mut new_table = None;
+        let table = match self.index.get(&measurement) {
+            Some(&index) => &mut self.tables[index],
+            None => new_table.insert(MeasurementTable::new(measurement.clone())),
+        };
+        table.add(tags, fields, timestamp).map_err(|reason| {
+            error::ParseLineProtocolSnafu {
+                line: line.trim_end(),
+                reason,
+            }
+            .build()
+        })?;
+        if let Some(table) = new_table {
+            self.index.insert(measurement, self.tables.len());
+            self.tables.push(table);
+        }
+        Ok(())
+    }
+
+    /// Add every line of `input`, skipping blank lines and `#` comments, and return
+    /// the number of lines added
+    pub fn add_lines(&mut self, input: &str) -> Result<usize> {
+        let mut added = 0;
+        for line in input.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            self.add_line(line)?;
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// The schema and rows of every measurement, in the order they first appear
+    pub fn finish(self) -> Result<Vec<(TableSchema, Vec<Row>)>> {
+        self.tables
+            .into_iter()
+            .map(MeasurementTable::into_schema_and_rows)
+            .collect()
+    }
+}
+
+impl MeasurementTable {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            tags: Vec::new(),
+            fields: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Add a parsed line, or leave the table untouched and return why the line does
+    /// not fit it
+    fn add(
+        &mut self,
+        tags: Vec<(String, Value)>,
+        fields: Vec<(String, Value)>,
+        timestamp: i64,
+    ) -> std::result::Result<(), String> {
+        let field_types: Vec<ColumnDataType> = fields
+            .iter()
+            .map(|(_, value)| match value {
+                Value::Int64(_) => ColumnDataType::Int64,
+                Value::Uint64(_) => ColumnDataType::Uint64,
+                Value::Boolean(_) => ColumnDataType::Boolean,
+                Value::String(_) => ColumnDataType::String,
+                _ => ColumnDataType::Float64,
+            })
+            .collect();
+
+        for (key, _) in &tags {
+            if key == TIMESTAMP_COLUMN
+                || self.fields.iter().any(|(name, _)| name == key)
+                || fields.iter().any(|(name, _)| name == key)
+            {
+                return Err(format!("tag `{key}` clashes with another column"));
+            }
+        }
+        for (index, ((key, _), data_type)) in fields.iter().zip(&field_types).enumerate() {
+            if key == TIMESTAMP_COLUMN || self.tags.contains(key) {
+                return Err(format!("field `{key}` clashes with another column"));
+            }
+            // A key repeated within the line is checked against its first occurrence
+            let earlier = fields[..index]
+                .iter()
+                .zip(&field_types)
+                .find(|((name, _), _)| name == key)
+                .map(|(_, data_type)| data_type);
+            if let Some(existing) = earlier {
+                if existing != data_type {
+                    return Err(format!(
+                        "field `{key}` is {data_type:?} but was {existing:?} before in the line"
+                    ));
+                }
+            }
+            if let Some((_, existing)) = self.fields.iter().find(|(name, _)| name == key) {
+                if existing != data_type {
+                    return Err(format!(
+                        "field `{key}` is {data_type:?} but was {existing:?} before"
+                    ));
+                }
+            }
+        }
+
+        for (key, _) in &tags {
+            if !self.tags.contains(key) {
+                self.tags.push(key.clone());
+            }
+        }
+        for ((key, _), data_type) in fields.iter().zip(field_types) {
+            if !self.fields.iter().any(|(name, _)| name == key) {
+                self.fields.push((key.clone(), data_type));
+            }
+        }
+        self.lines.push((tags, fields, timestamp));
+        Ok(())
+    }
+
+    fn into_schema_and_rows(self) -> Result<(TableSchema, Vec<Row>)> {
+        let column = |name: &str, data_type, semantic_type| Column {
+            name: name.to_string(),
+            data_type,
+            semantic_type,
+            data_type_extension: None,
+            is_primary_value: false,
+            options: ColumnOptions::default(),
+        };
+        let columns: Vec<Column> = self
+            .tags
+            .iter()
+            .map(|tag| column(tag, ColumnDataType::String, SemanticType::Tag))
+            .chain(std::iter::once(column(
+                TIMESTAMP_COLUMN,
+                ColumnDataType::TimestampNanosecond,
+                SemanticType::Timestamp,
+            )))
+            .chain(
+                self.fields
+                    .iter()
+                    .map(|(field, data_type)| column(field, *data_type, SemanticType::Field)),
+            )
+            .collect();
+        let schema = TableSchema::builder()
+            .name(self.name.clone())
+            .columns(columns)
+            .build()
+            .map_err(|e| {
+                error::ParseLineProtocolSnafu {
+                    line: self.name.clone(),
+                    reason: e.to_string(),
+                }
+                .build()
+            })?;
+
+        let timestamp_index = self.tags.len();
+        let rows = self
+            .lines
+            .into_iter()
+            .map(|(tags, fields, timestamp)| {
+                let mut values = vec![Value::Null; schema.columns().len()];
+                values[timestamp_index] = Value::TimestampNanosecond(timestamp);
+                for (key, value) in tags.into_iter().chain(fields) {
+                    if let Some(index) = schema.column_index(&key) {
+                        values[index] = value;
+                    }
+                }
+                Row::from_values(values)
+            })
+            .collect();
+        Ok((schema, rows))
+    }
+}
+
+/// Split `text` at the first of `stops` that is neither escaped nor, with
+/// `quoted`, inside double quotes
+fn take_until<'a>(text: &'a str, stops: &[char], quoted: bool) -> (&'a str, &'a str) {
+    let mut escaped = false;
+    let mut in_quotes = false;
+    for (index, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if quoted && c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && stops.contains(&c) {
+            return text.split_at(index);
+        }
+    }
+    (text, "")
+}
+
+/// Split `key=value` at the first unescaped `=`, failing on an empty key or value
+fn split_pair(pair: &str) -> Option<(&str, &str)> {
+    let (key, value) = take_until(pair, &['='], false);
+    let value = value.strip_prefix('=')?;
+    (!key.is_empty() && !value.is_empty()).then_some((key, value))
+}
+
+/// Remove the backslashes escaping commas, equal signs and spaces
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next_if(|next| matches!(next, ',' | '=' | ' ')) {
+                out.push(next);
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn field_value(text: &str) -> Option<Value> {
+    if let Some(quoted) = text.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"')?;
+        let mut out = String::with_capacity(quoted.len());
+        let mut chars = quoted.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                if let Some(next) = chars.next_if(|next| matches!(next, '"' | '\\')) {
+                    out.push(next);
+                    continue;
+                }
+            }
+            out.push(c);
+        }
+        return Some(Value::String(out));
+    }
+    match text {
+        "t" | "T" | "true" | "True" | "TRUE" => return Some(Value::Boolean(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+    if let Some(integer) = text.strip_suffix('i') {
+        return integer.parse().ok().map(Value::Int64);
+    }
+    if let Some(unsigned) = text.strip_suffix('u') {
+        return unsigned.parse().ok().map(Value::Uint64);
+    }
+    text.parse().ok().map(Value::Float64)
+}
+
+fn now_nanos() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> TableSchema {
+        TableSchema::builder()
+            .name("cpu")
+            .build()
+            .unwrap()
+            .add_tag("host name", ColumnDataType::String)
+            .add_tag("region", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("usage", ColumnDataType::Float64)
+            .add_field("cores", ColumnDataType::Int32)
+            .add_field("ticks", ColumnDataType::Uint64)
+            .add_field("up", ColumnDataType::Boolean)
+            .add_field("note", ColumnDataType::String)
+    }
+
+    #[test]
+    fn test_rows_to_line_protocol() {
+        let rows = vec![
+            Row::new().add_values(vec![
+                Value::String("web,1".to_string()),
+                Value::String("us=west".to_string()),
+                Value::TimestampMillisecond(1_700_000_000_000),
+                Value::Float64(0.5),
+                Value::Int32(8),
+                Value::Uint64(42),
+                Value::Boolean(true),
+                Value::String("say \"hi\" \\o/".to_string()),
+            ]),
+            Row::new().add_values(vec![
+                Value::String("db 2".to_string()),
+                Value::Null,
+                Value::Null,
+                Value::Float64(1.0),
+                Value::Null,
+                Value::Null,
+                Value::Boolean(false),
+                Value::Null,
+            ]),
+        ];
+
+        let lines = rows_to_line_protocol(&schema(), "cpu load,total", &rows).unwrap();
+        assert_eq!(
+            lines,
+            "cpu\\ load\\,total,host\\ name=web\\,1,region=us\\=west \
+             usage=0.5,cores=8i,ticks=42u,up=true,note=\"say \\\"hi\\\" \\\\o/\" 1700000000000\n\
+             cpu\\ load\\,total,host\\ name=db\\ 2 usage=1,up=false\n"
+        );
+        assert_eq!(precision(&schema()), Some("ms"));
+    }
+
+    #[test]
+    fn test_rows_to_line_protocol_rejects_unrepresentable_rows() {
+        let schema = schema();
+        let no_fields = Row::new().add_values(vec![
+            Value::String("web".to_string()),
+            Value::Null,
+            Value::TimestampMillisecond(0),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ]);
+        assert!(rows_to_line_protocol(&schema, "cpu", &[no_fields]).is_err());
+
+        let nan = Row::new().add_values(vec![
+            Value::Null,
+            Value::Null,
+            Value::TimestampMillisecond(0),
+            Value::Float64(f64::NAN),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ]);
+        assert!(rows_to_line_protocol(&schema, "cpu", &[nan]).is_err());
+
+        let short = Row::new().add_value(Value::Null);
+        assert!(rows_to_line_protocol(&schema, "cpu", &[short]).is_err());
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let (measurement, tags, fields, timestamp) = parse_line(
+            "cpu\\ load\\,total,host\\ name=web\\,1,region=us\\=west \
+             usage=0.5,cores=8i,ticks=42u,up=t,note=\"say \\\"hi\\\", \\\\o/\" 1700000000000000000",
+        )
+        .unwrap();
+        assert_eq!(measurement, "cpu load,total");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].0, "host name");
+        assert!(matches!(&tags[0].1, Value::String(v) if v == "web,1"));
+        assert!(matches!(&tags[1].1, Value::String(v) if v == "us=west"));
+        let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["usage", "cores", "ticks", "up", "note"]);
+        assert!(matches!(fields[0].1, Value::Float64(v) if v == 0.5));
+        assert!(matches!(fields[1].1, Value::Int64(8)));
+        assert!(matches!(fields[2].1, Value::Uint64(42)));
+        assert!(matches!(fields[3].1, Value::Boolean(true)));
+        assert!(matches!(&fields[4].1, Value::String(v) if v == "say \"hi\", \\o/"));
+        assert_eq!(timestamp, 1_700_000_000_000_000_000);
+
+        // Without a timestamp the line is stamped with the current time
+        let before = now_nanos();
+        let (_, tags, _, timestamp) = parse_line("mem free=1024i").unwrap();
+        assert!(tags.is_empty());
+        assert!(timestamp >= before && timestamp <= now_nanos());
+
+        for invalid in [
+            "",
+            "cpu",
+            "cpu,host=web",
+            "cpu,host value=1",
+            "cpu value=1x",
+            "cpu value=\"open",
+            "cpu value=1 soon",
+        ] {
+            assert!(
+                matches!(
+                    parse_line(invalid),
+                    Err(crate::Error::ParseLineProtocol { .. })
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_protocol_batch() {
+        let mut batch = LineProtocolBatch::new();
+        let added = batch
+            .add_lines(
+                "# exported from InfluxDB\n\
+                 cpu,host=a usage=0.5 1000\n\
+                 \n\
+                 mem,host=a free=1i 1000\n\
+                 cpu,host=b,region=eu usage=0.7,cores=4i 2000\n",
+            )
+            .unwrap();
+        assert_eq!(added, 3);
+
+        assert!(batch.add_line("cpu usage=1i 3000").is_err());
+        assert!(batch.add_line("cpu,usage=high cores=1i 3000").is_err());
+        assert!(batch.add_line("disk,used=1 used=1i").is_err());
+        // A key repeated with another type within one line
+        assert!(matches!(
+            batch.add_line("m a=1i,a=1"),
+            Err(crate::Error::ParseLineProtocol { .. })
+        ));
+
+        let tables = batch.finish().unwrap();
+        assert_eq!(tables.len(), 2);
+        let (schema, rows) = &tables[0];
+        assert_eq!(schema.name(), "cpu");
+        let columns: Vec<(&str, SemanticType)> = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name.as_str(), column.semantic_type))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("host", SemanticType::Tag),
+                ("region", SemanticType::Tag),
+                (TIMESTAMP_COLUMN, SemanticType::Timestamp),
+                ("usage", SemanticType::Field),
+                ("cores", SemanticType::Field),
+            ]
+        );
+        assert_eq!(rows.len(), 2);
+        assert!(matches!(rows[0].value(1), Some(Value::Null)));
+        assert!(matches!(
+            rows[0].value(2),
+            Some(Value::TimestampNanosecond(1000))
+        ));
+        assert!(matches!(rows[0].value(4), Some(Value::Null)));
+        assert!(matches!(rows[1].value(1), Some(Value::String(v)) if v == "eu"));
+        assert!(matches!(rows[1].value(4), Some(Value::Int64(4))));
+        assert_eq!(tables[1].0.name(), "mem");
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rows for log tables fed from OpenTelemetry logs
+//!
+//! [`LogRecord`] carries the fields of an OTLP `LogRecord` together with the
+//! attributes of its resource, so logs decoded from OTLP requests can be written
+//! through the bulk path without depending on a particular OpenTelemetry crate.
+//! [`LogMapping`] turns a slice of records into a table schema and rows:
+//!
+//! ```text
+//! ts TIMESTAMP(9) TIME INDEX, <pinned attributes> STRING TAG,
+//! severity_text STRING, severity_number INT, body STRING, trace_id STRING,
+//! span_id STRING, <other attributes> FIELD
+//! ```
+
+use std::fmt::Write;
+
+use snafu::{ensure, OptionExt};
+
+use crate::api::v1::{ColumnDataType, SemanticType};
+use crate::table::{Row, TableSchema, Value};
+use crate::{error, Result};
+
+/// Name of the timestamp column of log tables
+pub const TIMESTAMP_COLUMN: &str = "ts";
+
+const SEVERITY_TEXT_COLUMN: &str = "severity_text";
+const SEVERITY_NUMBER_COLUMN: &str = "severity_number";
+const BODY_COLUMN: &str = "body";
+const TRACE_ID_COLUMN: &str = "trace_id";
+const SPAN_ID_COLUMN: &str = "span_id";
+
+/// A scalar attribute value, the non-nested cases of the OTLP `AnyValue`
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Bytes(Vec<u8>),
+}
+
+impl AttributeValue {
+    fn data_type(&self) -> ColumnDataType {
+        match self {
+            AttributeValue::String(_) => ColumnDataType::String,
+            AttributeValue::Bool(_) => ColumnDataType::Boolean,
+            AttributeValue::Int(_) => ColumnDataType::Int64,
+            AttributeValue::Double(_) => ColumnDataType::Float64,
+            AttributeValue::Bytes(_) => ColumnDataType::Binary,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            AttributeValue::String(v) => Value::String(v.clone()),
+            AttributeValue::Bool(v) => Value::Boolean(*v),
+            AttributeValue::Int(v) => Value::Int64(*v),
+            AttributeValue::Double(v) => Value::Float64(*v),
+            AttributeValue::Bytes(v) => Value::Binary(v.clone()),
+        }
+    }
+
+    /// The value as a tag, which is always a string
+    fn to_tag(&self) -> String {
+        match self {
+            AttributeValue::String(v) => v.clone(),
+            AttributeValue::Bool(v) => v.to_string(),
+            AttributeValue::Int(v) => v.to_string(),
+            AttributeValue::Double(v) => v.to_string(),
+            AttributeValue::Bytes(v) => to_hex(v),
+        }
+    }
+}
+
+/// An OTLP log record, with the attributes of the resource that emitted it
+///
+/// Unset OTLP fields are left at their defaults: a zero timestamp, an empty body
+/// and empty trace and span ids.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogRecord {
+    pub time_unix_nano: u64,
+    pub observed_time_unix_nano: u64,
+    pub severity_number: i32,
+    pub severity_text: String,
+    pub body: String,
+    pub trace_id: Vec<u8>,
+    pub span_id: Vec<u8>,
+    pub resource_attributes: Vec<(String, AttributeValue)>,
+    pub attributes: Vec<(String, AttributeValue)>,
+}
+
+impl LogRecord {
+    /// Resource and record attributes, flattened into column names
+    ///
+    /// A record attribute replaces a resource attribute of the same key, as the
+    /// more specific of the two.
+    fn flattened_attributes(&self) -> Vec<(String, &AttributeValue)> {
+        let mut flattened: Vec<(String, &AttributeValue)> = Vec::new();
+        for (key, value) in self.resource_attributes.iter().chain(&self.attributes) {
+            let column = column_name(key);
+            match flattened.iter_mut().find(|(name, _)| *name == column) {
+                Some(existing) => existing.1 = value,
+                None => flattened.push((column, value)),
+            }
+        }
+        flattened
+    }
+
+    /// The event time, or the observed time for records without one, as the OTLP
+    /// data model prescribes
+    fn timestamp(&self) -> Result<i64> {
+        let nanos = match self.time_unix_nano {
+            0 => self.observed_time_unix_nano,
+            nanos => nanos,
+        };
+        i64::try_from(nanos)
+            .ok()
+            .context(error::ValueOutOfRangeSnafu {
+                value: nanos.to_string(),
+                target: "TimestampNanosecond",
+            })
+    }
+}
+
+/// Mapping of OTLP log records to a log table
+///
+/// Attribute keys are flattened into column names by replacing dots with
+/// underscores, so `service.name` becomes `service_name`. Attributes pinned with
+/// [`LogMapping::with_tags`] become string tag columns and take part in the primary
+/// key; every other attribute becomes a field column typed after its value. Keep
+/// high-cardinality attributes, like request ids, out of the tags.
+#[derive(Debug, Clone)]
+pub struct LogMapping {
+    table: String,
+    tags: Vec<String>,
+}
+
+impl LogMapping {
+    /// A mapping to table `table` without tags
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Store the attributes with the given keys in tag columns
+    #[must_use]
+    pub fn with_tags<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tags = keys
+            .into_iter()
+            .map(|key| column_name(key.as_ref()))
+            .collect();
+        self
+    }
+
+    /// The schema of a table holding `records`
+    ///
+    /// Field columns are added for the attributes of all records, in the order they
+    /// first appear. Fails if an attribute changes type between records or clashes
+    /// with one of the fixed columns.
+    pub fn schema(&self, records: &[LogRecord]) -> Result<TableSchema> {
+        let mut fields: Vec<(String, ColumnDataType)> = Vec::new();
+        for record in records {
+            for (column, value) in record.flattened_attributes() {
+                if self.tags.contains(&column) {
+                    continue;
+                }
+                match fields.iter().find(|(name, _)| *name == column) {
+                    Some((_, data_type)) if *data_type != value.data_type() => {
+                        return error::InvalidColumnValueSnafu {
+                            column,
+                            expected: *data_type,
+                            actual: format!("{value:?}"),
+                        }
+                        .fail()
+                    }
+                    Some(_) => {}
+                    None => fields.push((column, value.data_type())),
+                }
+            }
+        }
+
+        let mut schema = TableSchema::builder()
+            .name(self.table.clone())
+            .build()
+            .expect("a schema without columns is valid")
+            .add_timestamp(TIMESTAMP_COLUMN, ColumnDataType::TimestampNanosecond);
+        for tag in &self.tags {
+            schema = schema.add_tag(tag, ColumnDataType::String);
+        }
+        schema = schema
+            .add_field(SEVERITY_TEXT_COLUMN, ColumnDataType::String)
+            .add_field(SEVERITY_NUMBER_COLUMN, ColumnDataType::Int32)
+            .add_field(BODY_COLUMN, ColumnDataType::String)
+            .add_field(TRACE_ID_COLUMN, ColumnDataType::String)
+            .add_field(SPAN_ID_COLUMN, ColumnDataType::String);
+        for (field, data_type) in fields {
+            schema = schema.add_field(field, data_type);
+        }
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// The rows of `records` in a table of schema `schema`
+    ///
+    /// Columns of attributes a record does not have are null. Fails if a record has
+    /// an attribute without a column in `schema` or of a different type.
+    pub fn rows(&self, schema: &TableSchema, records: &[LogRecord]) -> Result<Vec<Row>> {
+        records
+            .iter()
+            .map(|record| self.row(schema, record))
+            .collect()
+    }
+
+    /// The schema of `records` together with their rows
+    pub fn convert(&self, records: &[LogRecord]) -> Result<(TableSchema, Vec<Row>)> {
+        let schema = self.schema(records)?;
+        let rows = self.rows(&schema, records)?;
+        Ok((schema, rows))
+    }
+
+    fn row(&self, schema: &TableSchema, record: &LogRecord) -> Result<Row> {
+        let mut values = vec![Value::Null; schema.columns().len()];
+        let mut set = |column: &str, value: Value| -> Result<()> {
+            let index = schema
+                .column_index(column)
+                .context(error::UnknownColumnSnafu { column })?;
+            values[index] = value;
+            Ok(())
+        };
+
+        set(
+            TIMESTAMP_COLUMN,
+            Value::TimestampNanosecond(record.timestamp()?),
+        )?;
+        set(
+            SEVERITY_TEXT_COLUMN,
+            Value::String(record.severity_text.clone()),
+        )?;
+        set(SEVERITY_NUMBER_COLUMN, Value::Int32(record.severity_number))?;
+        set(BODY_COLUMN, Value::String(record.body.clone()))?;
+        if !record.trace_id.is_empty() {
+            set(TRACE_ID_COLUMN, Value::String(to_hex(&record.trace_id)))?;
+        }
+        if !record.span_id.is_empty() {
+            set(SPAN_ID_COLUMN, Value::String(to_hex(&record.span_id)))?;
+        }
+
+        for (column, value) in record.flattened_attributes() {
+            if self.tags.contains(&column) {
+                set(&column, Value::String(value.to_tag()))?;
+                continue;
+            }
+            let expected = schema
+                .column(&column)
+                .context(error::UnknownColumnSnafu { column: &column })?
+                .data_type;
+            ensure!(
+                expected == value.data_type(),
+                error::InvalidColumnValueSnafu {
+                    column: &column,
+                    expected,
+                    actual: format!("{value:?}"),
+                }
+            );
+            set(&column, value.to_value())?;
+        }
+
+        Ok(Row::from_values(values))
+    }
+}
+
+fn column_name(key: &str) -> String {
+    key.replace('.', "_")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> LogRecord {
+        LogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            observed_time_unix_nano: 1_700_000_000_500_000_000,
+            severity_number: 17,
+            severity_text: "ERROR".to_string(),
+            body: "connection reset".to_string(),
+            trace_id: vec![0xab; 16],
+            span_id: vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+            resource_attributes: vec![
+                (
+                    "service.name".to_string(),
+                    AttributeValue::String("checkout".to_string()),
+                ),
+                (
+                    "host.name".to_string(),
+                    AttributeValue::String("web-1".to_string()),
+                ),
+            ],
+            attributes: vec![
+                ("http.status_code".to_string(), AttributeValue::Int(502)),
+                ("retry".to_string(), AttributeValue::Bool(true)),
+                (
+                    "host.name".to_string(),
+                    AttributeValue::String("web-1a".to_string()),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_log_record_to_row() {
+        let mapping = LogMapping::new("otel_logs").with_tags(["service.name"]);
+        let (schema, rows) = mapping.convert(&[record()]).unwrap();
+
+        let columns: Vec<(&str, ColumnDataType, SemanticType)> = schema
+            .columns()
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type, c.semantic_type))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                (
+                    "ts",
+                    ColumnDataType::TimestampNanosecond,
+                    SemanticType::Timestamp
+                ),
+                ("service_name", ColumnDataType::String, SemanticType::Tag),
+                ("severity_text", ColumnDataType::String, SemanticType::Field),
+                (
+                    "severity_number",
+                    ColumnDataType::Int32,
+                    SemanticType::Field
+                ),
+                ("body", ColumnDataType::String, SemanticType::Field),
+                ("trace_id", ColumnDataType::String, SemanticType::Field),
+                ("span_id", ColumnDataType::String, SemanticType::Field),
+                ("host_name", ColumnDataType::String, SemanticType::Field),
+                (
+                    "http_status_code",
+                    ColumnDataType::Int64,
+                    SemanticType::Field
+                ),
+                ("retry", ColumnDataType::Boolean, SemanticType::Field),
+            ]
+        );
+
+        let row = &rows[0];
+        assert_eq!(row.get_timestamp(0), Some(1_700_000_000_000_000_000));
+        assert_eq!(row.get_string(1).as_deref(), Some("checkout"));
+        assert_eq!(row.get_string(2).as_deref(), Some("ERROR"));
+        assert_eq!(row.get_i32(3), Some(17));
+        assert_eq!(row.get_string(4).as_deref(), Some("connection reset"));
+        assert_eq!(row.get_string(5), Some("ab".repeat(16)));
+        assert_eq!(row.get_string(6).as_deref(), Some("0102030405060708"));
+        // The record attribute wins over the resource attribute
+        assert_eq!(row.get_string(7).as_deref(), Some("web-1a"));
+        assert_eq!(row.get_i64(8), Some(502));
+        assert_eq!(row.get_bool(9), Some(true));
+    }
+
+    #[test]
+    fn test_log_mapping_rejects_inconsistent_attributes() {
+        let mut other = record();
+        other.time_unix_nano = 0;
+        other.attributes[0].1 = AttributeValue::String("bad gateway".to_string());
+        let mapping = LogMapping::new("otel_logs");
+        assert!(matches!(
+            mapping.schema(&[record(), other.clone()]),
+            Err(crate::Error::InvalidColumnValue { .. })
+        ));
+
+        // Without an event time the observed time is used
+        other.attributes.clear();
+        let schema = mapping.schema(&[record()]).unwrap();
+        let rows = mapping.rows(&schema, &[other]).unwrap();
+        assert_eq!(rows[0].get_timestamp(0), Some(1_700_000_000_500_000_000));
+
+        let mut body = record();
+        body.attributes = vec![("body".to_string(), AttributeValue::Int(1))];
+        assert!(matches!(
+            mapping.schema(&[body]),
+            Err(crate::Error::DuplicateColumn { .. })
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! DNS re-resolution for endpoints given as hostnames
+//!
+//! A gRPC channel resolves its endpoint once per connection, so a long-lived connection
+//! keeps talking to the same IP after the hostname has moved (e.g. a Kubernetes service
+//! or a load balancer). The refresh task re-resolves hostname peers periodically and
+//! evicts the pooled channel of every peer whose addresses changed, so that the next
+//! request connects anew.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::channel_manager::ChannelManager;
+
+/// Resolves an endpoint address (`host:port`) to socket addresses
+#[async_trait]
+pub trait Resolver: Send + Sync + Debug {
+    async fn resolve(&self, addr: &str) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// Resolver backed by the operating system, see [`tokio::net::lookup_host`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+#[async_trait]
+impl Resolver for SystemResolver {
+    async fn resolve(&self, addr: &str) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio::net::lookup_host(addr).await?.collect())
+    }
+}
+
+/// Periodic DNS refresh shared by all clones of a client
+///
+/// The refresh task is started on first use and stopped when the last clone is dropped.
+#[derive(Debug)]
+pub(crate) struct DnsRefresh {
+    interval: Duration,
+    resolver: Arc<dyn Resolver>,
+    started: AtomicBool,
+    cancel: CancellationToken,
+}
+
+impl Drop for DnsRefresh {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+impl DnsRefresh {
+    pub(crate) fn new(interval: Duration, resolver: Arc<dyn Resolver>) -> Self {
+        Self {
+            interval,
+            resolver,
+            started: AtomicBool::new(false),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub(crate) fn resolver(&self) -> Arc<dyn Resolver> {
+        self.resolver.clone()
+    }
+
+    /// Start the refresh task if it is not running yet
+    pub(crate) fn start(&self, peers: Arc<RwLock<Vec<String>>>, channel_manager: ChannelManager) {
+        if self
+            .started
+            .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        let interval = self.interval;
+        let resolver = self.resolver.clone();
+        let cancel = self.cancel.clone();
+        let _handle = tokio::spawn(async move {
+            refresh_in_loop(interval, resolver, peers, channel_manager, cancel).await;
+        });
+    }
+}
+
+async fn refresh_in_loop(
+    interval: Duration,
+    resolver: Arc<dyn Resolver>,
+    peers: Arc<RwLock<Vec<String>>>,
+    channel_manager: ChannelManager,
+    cancel: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(interval);
+    let mut resolved = ResolvedPeers::default();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                break;
+            },
+            _ = interval.tick() => {}
+        }
+
+        let peers = peers.read().clone();
+        let _ = resolved
+            .refresh(&peers, resolver.as_ref(), &channel_manager)
+            .await;
+    }
+}
+
+/// The addresses each hostname peer resolved to on the last refresh
+#[derive(Debug, Default)]
+pub(crate) struct ResolvedPeers {
+    addrs: HashMap<String, Vec<SocketAddr>>,
+}
+
+impl ResolvedPeers {
+    /// Re-resolve the hostname peers and evict the channels of those whose addresses changed
+    ///
+    /// Peers given as IP addresses are skipped, and a failed lookup keeps the current
+    /// channel. Returns the evicted peers.
+    pub(crate) async fn refresh(
+        &mut self,
+        peers: &[String],
+        resolver: &dyn Resolver,
+        channel_manager: &ChannelManager,
+    ) -> Vec<String> {
+        let mut changed = Vec::new();
+        for peer in peers {
+            if peer.parse::<SocketAddr>().is_ok() {
+                continue;
+            }
+            let Ok(mut addrs) = resolver.resolve(peer).await else {
+                continue;
+            };
+            addrs.sort_unstable();
+            addrs.dedup();
+
+            if let Some(previous) = self.addrs.insert(peer.clone(), addrs) {
+                if previous != self.addrs[peer] {
+                    changed.push(peer.clone());
+                }
+            }
+        }
+        self.addrs.retain(|peer, _| peers.contains(peer));
+
+        if !changed.is_empty() {
+            channel_manager.retain_channel(|addr, _| !changed.contains(addr));
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct StubResolver {
+        addrs: parking_lot::Mutex<Vec<SocketAddr>>,
+    }
+
+    impl StubResolver {
+        fn set(&self, addrs: &[&str]) {
+            *self.addrs.lock() = addrs.iter().map(|addr| addr.parse().unwrap()).collect();
+        }
+    }
+
+    #[async_trait]
+    impl Resolver for StubResolver {
+        async fn resolve(&self, _addr: &str) -> io::Result<Vec<SocketAddr>> {
+            Ok(self.addrs.lock().clone())
+        }
+    }
+
+    fn pooled_addrs(channel_manager: &ChannelManager) -> Vec<String> {
+        let mut addrs = Vec::new();
+        channel_manager.retain_channel(|addr, _| {
+            addrs.push(addr.clone());
+            true
+        });
+        addrs.sort();
+        addrs
+    }
+
+    #[tokio::test]
+    async fn test_refresh_evicts_channel_when_addresses_change() {
+        let channel_manager = ChannelManager::new();
+        let peers = vec!["127.0.0.1:4001".to_string(), "db.internal:4001".to_string()];
+        for peer in &peers {
+            let _ = channel_manager.get(peer).unwrap();
+        }
+
+        let resolver = StubResolver::default();
+        let mut resolved = ResolvedPeers::default();
+
+        resolver.set(&["10.0.0.1:4001", "10.0.0.2:4001"]);
+        let changed = resolved.refresh(&peers, &resolver, &channel_manager).await;
+        assert!(changed.is_empty());
+
+        // Same set in a different order is not a change
+        resolver.set(&["10.0.0.2:4001", "10.0.0.1:4001"]);
+        let changed = resolved.refresh(&peers, &resolver, &channel_manager).await;
+        assert!(changed.is_empty());
+        assert_eq!(pooled_addrs(&channel_manager), peers);
+
+        resolver.set(&["10.0.0.3:4001"]);
+        let changed = resolved.refresh(&peers, &resolver, &channel_manager).await;
+        assert_eq!(changed, vec!["db.internal:4001".to_string()]);
+        assert_eq!(
+            pooled_addrs(&channel_manager),
+            vec!["127.0.0.1:4001".to_string()]
+        );
+
+        // The next lookup connects anew
+        let _ = channel_manager.get("db.internal:4001").unwrap();
+        assert_eq!(pooled_addrs(&channel_manager), peers);
+    }
+}
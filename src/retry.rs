@@ -0,0 +1,151 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared accounting that keeps retries from piling up on a struggling server
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// A budget of retries shared by every request of a client
+///
+/// Each request earns `ratio` retries and each retry spends one; on top of that,
+/// `min_retries` are always available so that a client sending few requests can
+/// still retry. Earnings are counted over a fixed `window` and reset afterwards.
+/// Once the budget is spent, [`RetryBudget::try_retry`] denies retries and errors
+/// propagate immediately until the next window.
+///
+/// A ratio of `0.1` to `0.2` is recommended: while the server is healthy, retries
+/// add at most 10 to 20 percent to the request load, no matter how many requests
+/// fail at once.
+#[derive(Debug)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_retries: u64,
+    window: Duration,
+    state: Mutex<BudgetState>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    window_start: Instant,
+    requests: u64,
+    retries: u64,
+}
+
+impl Default for RetryBudget {
+    /// Allow retries for 10% of the requests plus 10 retries per 10 seconds
+    fn default() -> Self {
+        Self::new(0.1, 10, Duration::from_secs(10))
+    }
+}
+
+impl RetryBudget {
+    /// Create a budget allowing `ratio` retries per request plus `min_retries`
+    /// retries per `window`
+    pub fn new(ratio: f64, min_retries: u64, window: Duration) -> Self {
+        Self {
+            ratio: ratio.max(0.0),
+            min_retries,
+            window,
+            state: Mutex::new(BudgetState {
+                window_start: Instant::now(),
+                requests: 0,
+                retries: 0,
+            }),
+        }
+    }
+
+    /// Record an original request, earning `ratio` retries
+    pub fn record_request(&self) {
+        let mut state = self.current_state();
+        state.requests += 1;
+    }
+
+    /// Spend one retry if the budget allows it
+    ///
+    /// Returns `false` if the budget is exhausted, in which case the caller should
+    /// give up and surface the error instead of retrying.
+    pub fn try_retry(&self) -> bool {
+        let mut state = self.current_state();
+        if state.retries < self.allowed(&state) {
+            state.retries += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the number of retries left in the current window
+    pub fn remaining(&self) -> u64 {
+        let state = self.current_state();
+        self.allowed(&state).saturating_sub(state.retries)
+    }
+
+    fn allowed(&self, state: &BudgetState) -> u64 {
+        self.min_retries + (state.requests as f64 * self.ratio) as u64
+    }
+
+    /// Lock the state, starting a new window if the current one has elapsed
+    fn current_state(&self) -> parking_lot::MutexGuard<'_, BudgetState> {
+        let mut state = self.state.lock();
+        if state.window_start.elapsed() >= self.window {
+            state.window_start = Instant::now();
+            state.requests = 0;
+            state.retries = 0;
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retries_stop_once_budget_is_spent() {
+        let budget = RetryBudget::new(0.2, 1, Duration::from_secs(60));
+
+        // 100 requests earn 20 retries on top of the minimum of 1
+        for _ in 0..100 {
+            budget.record_request();
+        }
+        assert_eq!(budget.remaining(), 21);
+
+        // Every request fails and wants to retry
+        let granted = (0..100).filter(|_| budget.try_retry()).count();
+        assert_eq!(granted, 21);
+        assert_eq!(budget.remaining(), 0);
+        assert!(!budget.try_retry());
+
+        // New requests earn new retries
+        for _ in 0..5 {
+            budget.record_request();
+        }
+        assert!(budget.try_retry());
+        assert!(!budget.try_retry());
+    }
+
+    #[test]
+    fn test_budget_refills_after_window() {
+        let budget = RetryBudget::new(0.0, 2, Duration::from_millis(20));
+        assert!(budget.try_retry());
+        assert!(budget.try_retry());
+        assert!(!budget.try_retry());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(budget.remaining(), 2);
+        assert!(budget.try_retry());
+    }
+}
@@ -14,26 +14,302 @@
 
 //! Table schema and data structures for GreptimeDB bulk insert operations
 
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use arrow_schema::TimeUnit;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use bytes::Bytes;
 use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde::{Deserializer, Serializer};
+use sha2::{Digest, Sha256};
+use snafu::{ensure, OptionExt};
 
+use crate::api::v1 as api;
 use crate::api::v1::{ColumnDataType, SemanticType};
+use crate::coercion::{classify, Coercion};
+use crate::helpers::values;
+use crate::{error, Result};
 
 /// Extended data type information for columns that need additional parameters
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DataTypeExtension {
     /// Decimal128 with specific precision and scale
     Decimal128 { precision: u8, scale: i8 },
 }
 
+/// Text analyzer of a fulltext index, see [`FulltextOptions`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FulltextAnalyzer {
+    /// Splits text on whitespace and punctuation
+    #[default]
+    English,
+    /// Segments Chinese text into words
+    Chinese,
+}
+
+impl FulltextAnalyzer {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FulltextAnalyzer::English => "English",
+            FulltextAnalyzer::Chinese => "Chinese",
+        }
+    }
+}
+
+/// Settings of a fulltext index on a string column, see [`ColumnOptions::with_fulltext`]
+///
+/// A fulltext index lets the server answer `matches` queries on log messages and
+/// similar free text without scanning every row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub struct FulltextOptions {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub analyzer: FulltextAnalyzer,
+    /// Whether searches distinguish upper and lower case, disabled by default
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_sensitive: bool,
+}
+
+impl FulltextOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_analyzer(mut self, analyzer: FulltextAnalyzer) -> Self {
+        self.analyzer = analyzer;
+        self
+    }
+
+    #[must_use]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+}
+
+/// Key of the fulltext settings in the options of a gRPC column schema
+const FULLTEXT_GRPC_KEY: &str = "fulltext";
+
+/// Key of the inverted index flag in the options of a gRPC column schema
+const INVERTED_INDEX_GRPC_KEY: &str = "inverted_index";
+
+/// The fulltext settings as the server reads them from a gRPC column schema
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FulltextGrpcOptions {
+    enable: bool,
+    #[serde(default)]
+    analyzer: FulltextAnalyzer,
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
+/// Index and other server-side settings of a column
+///
+/// They are sent with the column schema of the regular insert API, so a table
+/// created automatically by an insert gets them, and are part of the column
+/// definition generated by [`TableSchema::to_create_table_sql`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ColumnOptions {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub fulltext: Option<FulltextOptions>,
+    /// Whether the column has an inverted index, disabled by default
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub inverted_index: bool,
+}
+
+impl ColumnOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a fulltext index on the column, which must be a string column
+    #[must_use]
+    pub fn with_fulltext(mut self, fulltext: FulltextOptions) -> Self {
+        self.fulltext = Some(fulltext);
+        self
+    }
+
+    /// Create an inverted index on the column
+    ///
+    /// An inverted index speeds up equality and `IN` filters on columns such as a
+    /// trace id without making them part of the primary key, which a tag would.
+    #[must_use]
+    pub fn with_inverted_index(mut self, enabled: bool) -> Self {
+        self.inverted_index = enabled;
+        self
+    }
+
+    /// Whether no option is set
+    pub fn is_empty(&self) -> bool {
+        self.fulltext.is_none() && !self.inverted_index
+    }
+
+    /// Convert into the column options of the regular insert API, `None` if no
+    /// option is set
+    pub fn to_api(&self) -> Option<api::ColumnOptions> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut options = HashMap::new();
+        if let Some(fulltext) = &self.fulltext {
+            let grpc = FulltextGrpcOptions {
+                enable: true,
+                analyzer: fulltext.analyzer,
+                case_sensitive: fulltext.case_sensitive,
+            };
+            options.insert(
+                FULLTEXT_GRPC_KEY.to_string(),
+                serde_json::to_string(&grpc).expect("fulltext options serialize to JSON"),
+            );
+        }
+        if self.inverted_index {
+            options.insert(INVERTED_INDEX_GRPC_KEY.to_string(), "true".to_string());
+        }
+        Some(api::ColumnOptions { options })
+    }
+
+    /// Convert from the column options of the regular insert API, ignoring options
+    /// this client does not model
+    fn from_api(options: Option<&api::ColumnOptions>) -> std::result::Result<Self, String> {
+        let mut result = Self::default();
+        let Some(options) = options else {
+            return Ok(result);
+        };
+        if let Some(fulltext) = options.options.get(FULLTEXT_GRPC_KEY) {
+            let grpc: FulltextGrpcOptions = serde_json::from_str(fulltext)
+                .map_err(|e| format!("fulltext options {fulltext:?}: {e}"))?;
+            if grpc.enable {
+                result.fulltext = Some(FulltextOptions {
+                    analyzer: grpc.analyzer,
+                    case_sensitive: grpc.case_sensitive,
+                });
+            }
+        }
+        if let Some(inverted_index) = options.options.get(INVERTED_INDEX_GRPC_KEY) {
+            result.inverted_index = inverted_index
+                .parse()
+                .map_err(|_| format!("inverted index flag {inverted_index:?}"))?;
+        }
+        Ok(result)
+    }
+
+    /// The index clauses of the column definition in DDL, with a leading space
+    fn sql_clauses(&self) -> String {
+        let mut sql = String::new();
+        if self.inverted_index {
+            sql.push_str(" INVERTED INDEX");
+        }
+        if let Some(fulltext) = &self.fulltext {
+            sql.push_str(&format!(
+                " FULLTEXT INDEX WITH(analyzer = '{}', case_sensitive = '{}')",
+                fulltext.analyzer.as_str(),
+                fulltext.case_sensitive
+            ));
+        }
+        sql
+    }
+}
+
+/// How column names are normalized when a schema is built
+///
+/// GreptimeDB lowercases unquoted identifiers, so a column declared as
+/// `ResponseTimeMs` is created as `responsetimems` by SQL DDL. Normalizing to
+/// [`CaseMode::Lower`] keeps the names used by the client in line with the server.
+/// The mode applies to the columns given to the builder as well as to columns
+/// added later with `add_tag`, `add_field` and the like.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CaseMode {
+    /// Keep column names as declared
+    #[default]
+    Preserve,
+    /// Lowercase column names
+    Lower,
+    /// Uppercase column names
+    Upper,
+}
+
+impl CaseMode {
+    /// Normalize `name` according to this mode
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            CaseMode::Preserve => name.to_string(),
+            CaseMode::Lower => name.to_lowercase(),
+            CaseMode::Upper => name.to_uppercase(),
+        }
+    }
+}
+
 /// Represents a time-series data table with schema
 #[derive(Debug, Clone, Builder)]
-#[builder(setter(into))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[builder(setter(into), build_fn(private, name = "build_raw"))]
 pub struct TableSchema {
     /// Table name
     name: String,
     /// Table columns
     #[builder(default)]
     columns: Vec<Column>,
+    /// Case normalization of column names, [`CaseMode::Preserve`] by default
+    #[builder(default, setter(name = "normalize_case"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    case_mode: CaseMode,
+    /// Default values by column name, see [`TableSchema::with_column_default`]
+    #[builder(setter(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    column_defaults: HashMap<String, Value>,
+    /// Column name to index, built on the first lookup by name
+    #[builder(setter(skip))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    column_index: OnceLock<HashMap<String, usize>>,
+}
+
+impl PartialEq for TableSchema {
+    fn eq(&self, other: &Self) -> bool {
+        // The lookup index is a cache derived from the columns
+        self.name == other.name
+            && self.columns == other.columns
+            && self.case_mode == other.case_mode
+            && self.column_defaults == other.column_defaults
+    }
+}
+
+impl Eq for TableSchema {}
+
+impl TableSchemaBuilder {
+    /// Build the schema, normalizing column names with the configured [`CaseMode`]
+    ///
+    /// Fails if a column name is empty or if two columns end up with the same name,
+    /// for example `Host` and `host` under [`CaseMode::Lower`].
+    pub fn build(&self) -> std::result::Result<TableSchema, TableSchemaBuilderError> {
+        let mut schema = self.build_raw()?;
+        for column in &mut schema.columns {
+            column.name = schema.case_mode.apply(&column.name);
+        }
+        schema
+            .validate()
+            .map_err(|e| TableSchemaBuilderError::ValidationError(e.to_string()))?;
+        Ok(schema)
+    }
 }
 
 impl TableSchema {
@@ -42,6 +318,27 @@ impl TableSchema {
         TableSchemaBuilder::default()
     }
 
+    /// Create the schema of table `name` from its columns in the wire format, inverting
+    /// [`TableSchema::to_api_schema`]
+    ///
+    /// Use this to work with a schema reported by the server. Fails on a data or
+    /// semantic type this client does not know, on a decimal extension out of range,
+    /// and on the same conditions as [`TableSchema::validate`].
+    pub fn from_api_schema(name: impl Into<String>, columns: &[api::ColumnSchema]) -> Result<Self> {
+        let schema = Self {
+            name: name.into(),
+            columns: columns
+                .iter()
+                .map(Column::from_api)
+                .collect::<Result<_>>()?,
+            case_mode: CaseMode::default(),
+            column_defaults: HashMap::new(),
+            column_index: OnceLock::new(),
+        };
+        schema.validate()?;
+        Ok(schema)
+    }
+
     /// Get the table name
     pub fn name(&self) -> &str {
         &self.name
@@ -52,39 +349,399 @@ impl TableSchema {
         &self.columns
     }
 
+    /// Get the case normalization applied to column names
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    /// Get the index of the column named `name`
+    ///
+    /// `name` is normalized with the schema's [`CaseMode`] first, so under
+    /// [`CaseMode::Lower`] `Host` finds the column `host`; under the default
+    /// [`CaseMode::Preserve`] lookups are case-sensitive. The name to index map is
+    /// built lazily on the first lookup and reset whenever a column is added, so
+    /// repeated lookups on wide schemas are cheap.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        let index = self.column_index_map();
+        match self.case_mode {
+            CaseMode::Preserve => index.get(name).copied(),
+            _ => index.get(&self.case_mode.apply(name)).copied(),
+        }
+    }
+
+    /// Get the column named `name`, see [`TableSchema::column_index`]
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.column_index(name).map(|index| &self.columns[index])
+    }
+
+    /// Set the value that replaces nulls in the column named `column`
+    ///
+    /// Defaults are applied on the client by [`Row::fill_defaults`]; they are not part
+    /// of the schema sent to the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the schema has no column `column`.
+    pub fn with_column_default(mut self, column: &str, value: Value) -> Result<Self> {
+        let index = self
+            .column_index(column)
+            .context(error::MissingFieldSnafu { field: column })?;
+        let name = self.columns[index].name.clone();
+        self.column_defaults.insert(name, value);
+        Ok(self)
+    }
+
+    /// Get the default value of the column named `column`, if it has one
+    pub fn column_default(&self, column: &str) -> Option<&Value> {
+        let column = self.column(column)?;
+        self.column_defaults.get(&column.name)
+    }
+
+    /// The name to index map behind [`TableSchema::column_index`], matching names exactly
+    pub(crate) fn column_index_map(&self) -> &HashMap<String, usize> {
+        self.column_index.get_or_init(|| {
+            let mut index = HashMap::with_capacity(self.columns.len());
+            for (i, column) in self.columns.iter().enumerate() {
+                // Keep the first of duplicate names, like a linear scan would
+                index.entry(column.name.clone()).or_insert(i);
+            }
+            index
+        })
+    }
+
+    fn push_column(&mut self, column: Column) {
+        self.column_index = OnceLock::new();
+        self.columns.push(column);
+    }
+
+    /// Check that no column name is empty, that no two columns share a name and that
+    /// at most one column is marked as the primary value
+    ///
+    /// The `add_*` methods stay infallible so that schemas can be chained together,
+    /// which means this check cannot run as columns are added. It runs when the
+    /// builder builds a schema, and `BulkStreamWriter::new` runs it again before
+    /// opening a stream, so a mistake surfaces as a client error instead of a
+    /// confusing server rejection.
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::with_capacity(self.columns.len());
+        for (index, column) in self.columns.iter().enumerate() {
+            ensure!(
+                !column.name.is_empty(),
+                error::EmptyColumnNameSnafu { index }
+            );
+            ensure!(
+                seen.insert(column.name.as_str()),
+                error::DuplicateColumnSnafu {
+                    column: &column.name
+                }
+            );
+        }
+        let primary_values: Vec<String> = self
+            .columns
+            .iter()
+            .filter(|column| column.is_primary_value)
+            .map(|column| column.name.clone())
+            .collect();
+        ensure!(
+            primary_values.len() <= 1,
+            error::MultiplePrimaryValuesSnafu {
+                table: &self.name,
+                columns: primary_values,
+            }
+        );
+        Ok(())
+    }
+
+    /// Get the field column holding the primary value, if one is marked
+    ///
+    /// See [`TableSchema::add_primary_field`].
+    pub fn primary_value_column(&self) -> Option<&Column> {
+        self.primary_value_index().map(|index| &self.columns[index])
+    }
+
+    /// Get the position of the primary value column, if one is marked
+    pub fn primary_value_index(&self) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.is_primary_value)
+    }
+
+    /// Get the timestamp column, if the schema has exactly one
+    pub fn timestamp_column(&self) -> Option<&Column> {
+        self.timestamp_index().map(|index| &self.columns[index])
+    }
+
+    /// Get the position of the timestamp column, if the schema has exactly one
+    ///
+    /// This is the index of the time index value in the rows of this schema.
+    pub fn timestamp_index(&self) -> Option<usize> {
+        let mut timestamps = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.semantic_type == SemanticType::Timestamp)
+            .map(|(index, _)| index);
+        let index = timestamps.next()?;
+        timestamps.next().is_none().then_some(index)
+    }
+
+    /// Check that the schema has exactly one timestamp column
+    ///
+    /// GreptimeDB tables have a single TIME INDEX column. The error lists every
+    /// timestamp column found, if any.
+    pub fn validate_time_index(&self) -> Result<()> {
+        ensure!(
+            self.timestamp_column().is_some(),
+            error::InvalidTimeIndexSnafu {
+                table: &self.name,
+                columns: self
+                    .columns
+                    .iter()
+                    .filter(|column| column.semantic_type == SemanticType::Timestamp)
+                    .map(|column| column.name.clone())
+                    .collect::<Vec<_>>(),
+            }
+        );
+        Ok(())
+    }
+
+    /// Check that `row` has one value per column and that every value has its column's type
+    ///
+    /// Nulls are accepted in any column. Values that would need a conversion, even a
+    /// lossless one such as `Int32` into an `Int64` column, are rejected.
+    pub fn validate_row(&self, row: &Row) -> Result<()> {
+        ensure!(
+            row.len() == self.columns.len(),
+            error::InvalidColumnCountSnafu {
+                expected: self.columns.len(),
+                actual: row.len(),
+            }
+        );
+
+        for (column, value) in self.columns.iter().zip(&row.values) {
+            ensure!(
+                classify(value, column.data_type) == Coercion::Exact,
+                error::InvalidColumnValueSnafu {
+                    column: &column.name,
+                    expected: column.data_type,
+                    actual: format!("{value:?}"),
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Generate the `CREATE TABLE IF NOT EXISTS` statement of this table
+    ///
+    /// Tag columns make up the primary key and the timestamp column the time index.
+    /// `table_options` end up in the `WITH` clause, e.g. `("append_mode", "true")` or
+    /// `("skip_wal", "true")`. Identifiers are quoted, so names keep their case.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` for a column data type without a SQL equivalent.
+    pub fn to_create_table_sql(&self, table_options: &[(&str, &str)]) -> Result<String> {
+        let mut definitions = Vec::with_capacity(self.columns.len() + 2);
+        for column in &self.columns {
+            let nullability = if column.semantic_type == SemanticType::Timestamp {
+                "NOT NULL"
+            } else {
+                "NULL"
+            };
+            definitions.push(format!(
+                "  {} {} {nullability}{}",
+                quote_identifier(&column.name),
+                column.sql_type()?,
+                column.options.sql_clauses()
+            ));
+        }
+
+        let columns_of = |semantic_type: SemanticType| {
+            self.columns
+                .iter()
+                .filter(|column| column.semantic_type == semantic_type)
+                .map(|column| quote_identifier(&column.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        definitions.push(format!(
+            "  TIME INDEX ({})",
+            columns_of(SemanticType::Timestamp)
+        ));
+        let primary_key = columns_of(SemanticType::Tag);
+        if !primary_key.is_empty() {
+            definitions.push(format!("  PRIMARY KEY ({primary_key})"));
+        }
+
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n{}\n)\nENGINE=mito",
+            quote_identifier(&self.name),
+            definitions.join(",\n")
+        );
+        if !table_options.is_empty() {
+            let options: Vec<String> = table_options
+                .iter()
+                .map(|(key, value)| format!("  {key} = '{}'", value.replace('\'', "''")))
+                .collect();
+            sql.push_str(&format!("\nWITH(\n{}\n)", options.join(",\n")));
+        }
+        Ok(sql)
+    }
+
+    /// Get the column schemas of the regular insert API
+    pub fn to_api_schema(&self) -> Vec<api::ColumnSchema> {
+        self.columns.iter().map(Column::to_api).collect()
+    }
+
+    /// Build a ready-to-send insert request for this table from `rows`
+    ///
+    /// Every row must have one value per column of the schema.
+    pub fn into_insert_request(&self, rows: Vec<Row>) -> Result<api::RowInsertRequests> {
+        Ok(api::RowInsertRequests {
+            inserts: vec![api::RowInsertRequest {
+                table_name: self.name.clone(),
+                rows: Some(api_rows(self.columns.iter(), rows)?),
+            }],
+        })
+    }
+
+    /// Build a ready-to-send delete request for this table from `rows`
+    ///
+    /// Rows to delete are identified by their primary key and time index only, so
+    /// every row holds one value per tag and timestamp column, in schema order, and
+    /// no field values.
+    pub fn into_delete_request(&self, rows: Vec<Row>) -> Result<api::RowDeleteRequests> {
+        let key_columns = self
+            .columns
+            .iter()
+            .filter(|column| column.semantic_type != SemanticType::Field);
+        Ok(api::RowDeleteRequests {
+            deletes: vec![api::RowDeleteRequest {
+                table_name: self.name.clone(),
+                rows: Some(api_rows(key_columns, rows)?),
+            }],
+        })
+    }
+
     /// Add a tag column (for indexing and grouping)
     pub fn add_tag<T: Into<String>>(mut self, name: T, data_type: ColumnDataType) -> Self {
-        self.columns.push(Column {
-            name: name.into(),
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
+            data_type,
+            semantic_type: SemanticType::Tag,
+            data_type_extension: None,
+            is_primary_value: false,
+            options: ColumnOptions::default(),
+        });
+        self
+    }
+
+    /// Add a tag column with index or other server-side settings, see [`ColumnOptions`]
+    pub fn add_tag_with_options<T: Into<String>>(
+        mut self,
+        name: T,
+        data_type: ColumnDataType,
+        options: ColumnOptions,
+    ) -> Self {
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
             data_type,
             semantic_type: SemanticType::Tag,
             data_type_extension: None,
+            is_primary_value: false,
+            options,
         });
         self
     }
 
     /// Add a timestamp column (timeline for time series)
     pub fn add_timestamp<T: Into<String>>(mut self, name: T, data_type: ColumnDataType) -> Self {
-        self.columns.push(Column {
-            name: name.into(),
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
             data_type,
             semantic_type: SemanticType::Timestamp,
             data_type_extension: None,
+            is_primary_value: false,
+            options: ColumnOptions::default(),
         });
         self
     }
 
     /// Add a field column (measurement values)
     pub fn add_field<T: Into<String>>(mut self, name: T, data_type: ColumnDataType) -> Self {
-        self.columns.push(Column {
-            name: name.into(),
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
+            data_type,
+            semantic_type: SemanticType::Field,
+            data_type_extension: None,
+            is_primary_value: false,
+            options: ColumnOptions::default(),
+        });
+        self
+    }
+
+    /// Add a field column with index or other server-side settings, e.g. a fulltext
+    /// index on a log message column
+    pub fn add_field_with_options<T: Into<String>>(
+        mut self,
+        name: T,
+        data_type: ColumnDataType,
+        options: ColumnOptions,
+    ) -> Self {
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
+            data_type,
+            semantic_type: SemanticType::Field,
+            data_type_extension: None,
+            is_primary_value: false,
+            options,
+        });
+        self
+    }
+
+    /// Add a field column holding the primary value of the table, such as the sample
+    /// value of a metric table
+    ///
+    /// The mark is metadata for tools like [`crate::helpers::metrics::metric_row`] that
+    /// need to pick the value field of a table with several fields; it is not sent to
+    /// the server. A table can have at most one primary value column, which
+    /// [`TableSchema::validate`] checks.
+    pub fn add_primary_field<T: Into<String>>(
+        mut self,
+        name: T,
+        data_type: ColumnDataType,
+    ) -> Self {
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
             data_type,
             semantic_type: SemanticType::Field,
             data_type_extension: None,
+            is_primary_value: true,
+            options: ColumnOptions::default(),
         });
         self
     }
 
+    /// Append the columns of `other` to this schema, for tables sharing a common set
+    /// of columns such as a timestamp and standard tags
+    ///
+    /// Inherited names are normalized with this schema's [`CaseMode`]. Fails if an
+    /// inherited column has the name of an existing column.
+    pub fn extend_from(mut self, other: &TableSchema) -> Result<Self> {
+        for column in &other.columns {
+            let name = self.case_mode.apply(&column.name);
+            ensure!(
+                !self.columns.iter().any(|existing| existing.name == name),
+                error::DuplicateColumnSnafu { column: name }
+            );
+            self.push_column(Column {
+                name,
+                ..column.clone()
+            });
+        }
+        Ok(self)
+    }
+
     /// Add a decimal128 field column with specific precision and scale
     pub fn add_decimal128_field<T: Into<String>>(
         mut self,
@@ -92,32 +749,354 @@ impl TableSchema {
         precision: u8,
         scale: i8,
     ) -> Self {
-        self.columns.push(Column {
-            name: name.into(),
+        self.push_column(Column {
+            name: self.case_mode.apply(&name.into()),
             data_type: ColumnDataType::Decimal128,
             semantic_type: SemanticType::Field,
             data_type_extension: Some(DataTypeExtension::Decimal128 { precision, scale }),
+            is_primary_value: false,
+            options: ColumnOptions::default(),
         });
         self
     }
 }
 
 /// Table column definition
-#[derive(Debug, Clone)]
+///
+/// With the `serde` feature, the data and semantic types are written by their
+/// protobuf names, e.g. `"TIMESTAMP_MILLISECOND"` and `"TIMESTAMP"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Column {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(with = "column_data_type_serde"))]
     pub data_type: ColumnDataType,
+    #[cfg_attr(feature = "serde", serde(with = "semantic_type_serde"))]
     pub semantic_type: SemanticType,
     /// Extended type information for data types that need additional parameters
     pub data_type_extension: Option<DataTypeExtension>,
+    /// Whether this field holds the primary value of the table, see
+    /// [`TableSchema::add_primary_field`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub is_primary_value: bool,
+    /// Index and other server-side settings, see [`TableSchema::add_field_with_options`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "ColumnOptions::is_empty")
+    )]
+    pub options: ColumnOptions,
+}
+
+impl Column {
+    /// The SQL type of this column, as used in DDL
+    fn sql_type(&self) -> Result<String> {
+        Ok(match self.data_type {
+            ColumnDataType::Boolean => "BOOLEAN".to_string(),
+            ColumnDataType::Int8 => "TINYINT".to_string(),
+            ColumnDataType::Int16 => "SMALLINT".to_string(),
+            ColumnDataType::Int32 => "INT".to_string(),
+            ColumnDataType::Int64 => "BIGINT".to_string(),
+            ColumnDataType::Uint8 => "TINYINT UNSIGNED".to_string(),
+            ColumnDataType::Uint16 => "SMALLINT UNSIGNED".to_string(),
+            ColumnDataType::Uint32 => "INT UNSIGNED".to_string(),
+            ColumnDataType::Uint64 => "BIGINT UNSIGNED".to_string(),
+            ColumnDataType::Float32 => "FLOAT".to_string(),
+            ColumnDataType::Float64 => "DOUBLE".to_string(),
+            ColumnDataType::Binary => "VARBINARY".to_string(),
+            ColumnDataType::String => "STRING".to_string(),
+            ColumnDataType::Date => "DATE".to_string(),
+            ColumnDataType::Datetime => "DATETIME".to_string(),
+            ColumnDataType::TimestampSecond => "TIMESTAMP(0)".to_string(),
+            ColumnDataType::TimestampMillisecond => "TIMESTAMP(3)".to_string(),
+            ColumnDataType::TimestampMicrosecond => "TIMESTAMP(6)".to_string(),
+            ColumnDataType::TimestampNanosecond => "TIMESTAMP(9)".to_string(),
+            ColumnDataType::TimeSecond => "TIME(0)".to_string(),
+            ColumnDataType::TimeMillisecond => "TIME(3)".to_string(),
+            ColumnDataType::TimeMicrosecond => "TIME(6)".to_string(),
+            ColumnDataType::TimeNanosecond => "TIME(9)".to_string(),
+            ColumnDataType::Decimal128 => match self.data_type_extension {
+                Some(DataTypeExtension::Decimal128 { precision, scale }) => {
+                    format!("DECIMAL({precision}, {scale})")
+                }
+                None => "DECIMAL".to_string(),
+            },
+            ColumnDataType::Json => "JSON".to_string(),
+            data_type => {
+                return error::UnsupportedDataTypeSnafu {
+                    data_type: format!("{data_type:?}"),
+                }
+                .fail()
+            }
+        })
+    }
+
+    /// Convert into the column schema of the regular insert API
+    pub fn to_api(&self) -> api::ColumnSchema {
+        let datatype_extension =
+            self.data_type_extension
+                .as_ref()
+                .map(|extension| match extension {
+                    DataTypeExtension::Decimal128 { precision, scale } => {
+                        api::ColumnDataTypeExtension {
+                            type_ext: Some(api::column_data_type_extension::TypeExt::DecimalType(
+                                api::DecimalTypeExtension {
+                                    precision: i32::from(*precision),
+                                    scale: i32::from(*scale),
+                                },
+                            )),
+                        }
+                    }
+                });
+
+        api::ColumnSchema {
+            column_name: self.name.clone(),
+            semantic_type: self.semantic_type as i32,
+            datatype: self.data_type as i32,
+            datatype_extension,
+            options: self.options.to_api(),
+        }
+    }
+
+    /// Convert from the column schema of the regular insert API, inverting [`Column::to_api`]
+    pub fn from_api(schema: &api::ColumnSchema) -> Result<Self> {
+        let invalid = |reason: String| {
+            error::InvalidColumnSchemaSnafu {
+                column: schema.column_name.clone(),
+                reason,
+            }
+            .build()
+        };
+
+        let data_type = ColumnDataType::try_from(schema.datatype)
+            .map_err(|_| invalid(format!("unknown data type {}", schema.datatype)))?;
+        if !is_supported_data_type(data_type) {
+            return Err(invalid(format!(
+                "data type {} is not supported by this client",
+                data_type.as_str_name()
+            )));
+        }
+        let semantic_type = SemanticType::try_from(schema.semantic_type)
+            .map_err(|_| invalid(format!("unknown semantic type {}", schema.semantic_type)))?;
+
+        let data_type_extension = match schema
+            .datatype_extension
+            .as_ref()
+            .and_then(|extension| extension.type_ext.as_ref())
+        {
+            None => None,
+            Some(api::column_data_type_extension::TypeExt::DecimalType(decimal)) => {
+                let precision = u8::try_from(decimal.precision)
+                    .map_err(|_| invalid(format!("decimal precision {}", decimal.precision)))?;
+                let scale = i8::try_from(decimal.scale)
+                    .map_err(|_| invalid(format!("decimal scale {}", decimal.scale)))?;
+                Some(DataTypeExtension::Decimal128 { precision, scale })
+            }
+            Some(other) => {
+                return Err(invalid(format!(
+                    "unsupported data type extension {other:?}"
+                )))
+            }
+        };
+
+        let options = ColumnOptions::from_api(schema.options.as_ref()).map_err(invalid)?;
+
+        Ok(Self {
+            name: schema.column_name.clone(),
+            data_type,
+            semantic_type,
+            data_type_extension,
+            is_primary_value: false,
+            options,
+        })
+    }
+}
+
+/// Whether values of `data_type` can be represented by [`Value`]
+fn is_supported_data_type(data_type: ColumnDataType) -> bool {
+    matches!(
+        data_type,
+        ColumnDataType::Boolean
+            | ColumnDataType::Int8
+            | ColumnDataType::Int16
+            | ColumnDataType::Int32
+            | ColumnDataType::Int64
+            | ColumnDataType::Uint8
+            | ColumnDataType::Uint16
+            | ColumnDataType::Uint32
+            | ColumnDataType::Uint64
+            | ColumnDataType::Float32
+            | ColumnDataType::Float64
+            | ColumnDataType::Binary
+            | ColumnDataType::String
+            | ColumnDataType::Date
+            | ColumnDataType::Datetime
+            | ColumnDataType::TimestampSecond
+            | ColumnDataType::TimestampMillisecond
+            | ColumnDataType::TimestampMicrosecond
+            | ColumnDataType::TimestampNanosecond
+            | ColumnDataType::TimeSecond
+            | ColumnDataType::TimeMillisecond
+            | ColumnDataType::TimeMicrosecond
+            | ColumnDataType::TimeNanosecond
+            | ColumnDataType::Decimal128
+            | ColumnDataType::Json
+    )
+}
+
+/// Plausible range for timestamp values, used to catch values written in the wrong unit.
+///
+/// The check is heuristic: a raw `i64` holding seconds written into a millisecond column
+/// lands in the first weeks of 1970, while milliseconds written into a second column land
+/// tens of thousands of years in the future. Both are rejected by a range check against the
+/// column's unit, but a genuinely old or far-future timestamp is rejected too, so widen the
+/// range if your data legitimately falls outside of it.
+///
+/// The default range is `1971-01-01T00:00:00Z` to `2100-01-01T00:00:00Z`. The lower bound is
+/// kept off the epoch itself so that seconds misread as a finer unit are still flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampRange {
+    min_seconds: i64,
+    max_seconds: i64,
+}
+
+impl Default for TimestampRange {
+    fn default() -> Self {
+        Self {
+            // 1971-01-01T00:00:00Z
+            min_seconds: 31_536_000,
+            // 2100-01-01T00:00:00Z
+            max_seconds: 4_102_444_800,
+        }
+    }
+}
+
+impl TimestampRange {
+    /// Create a range from inclusive bounds expressed in seconds since the Unix epoch
+    pub fn new(min_seconds: i64, max_seconds: i64) -> Self {
+        Self {
+            min_seconds,
+            max_seconds,
+        }
+    }
+
+    /// Get the inclusive lower bound in seconds since the Unix epoch
+    pub fn min_seconds(&self) -> i64 {
+        self.min_seconds
+    }
+
+    /// Get the inclusive upper bound in seconds since the Unix epoch
+    pub fn max_seconds(&self) -> i64 {
+        self.max_seconds
+    }
+
+    /// Get the inclusive bounds scaled to the given unit
+    pub fn bounds_in(&self, unit: TimeUnit) -> (i64, i64) {
+        let factor = units_per_second(unit);
+        (
+            self.min_seconds.saturating_mul(factor),
+            self.max_seconds.saturating_mul(factor),
+        )
+    }
+
+    /// Check a raw timestamp of the given unit against the range
+    pub fn contains(&self, value: i64, unit: TimeUnit) -> bool {
+        let (min, max) = self.bounds_in(unit);
+        (min..=max).contains(&value)
+    }
+
+    /// Check a timestamp value destined for `column`, whose unit is `unit`.
+    ///
+    /// Nulls and non-timestamp values are ignored.
+    pub fn check(&self, column: &str, unit: TimeUnit, value: &Value) -> Result<()> {
+        let raw = match value {
+            Value::TimestampSecond(v)
+            | Value::TimestampMillisecond(v)
+            | Value::TimestampMicrosecond(v)
+            | Value::TimestampNanosecond(v) => *v,
+            _ => return Ok(()),
+        };
+
+        let (min, max) = self.bounds_in(unit);
+        ensure!(
+            (min..=max).contains(&raw),
+            error::ImplausibleTimestampSnafu {
+                column,
+                value: raw,
+                unit,
+                min,
+                max,
+            }
+        );
+        Ok(())
+    }
+}
+
+/// Number of `unit` ticks in one second
+fn units_per_second(unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+/// Rescale the raw timestamp `v` from unit `from` to unit `to`
+///
+/// Returns `None` on overflow, and whether sub-unit digits were dropped otherwise.
+/// Down-conversion rounds towards negative infinity, so a timestamp never moves
+/// later than the instant it stands for.
+fn rescale_timestamp(v: i64, from: TimeUnit, to: TimeUnit) -> Option<(i64, bool)> {
+    let (from, to) = (units_per_second(from), units_per_second(to));
+    if to >= from {
+        v.checked_mul(to / from).map(|v| (v, false))
+    } else {
+        let factor = from / to;
+        Some((v.div_euclid(factor), v.rem_euclid(factor) != 0))
+    }
 }
 
 /// Represents a data row with type-safe value access
-#[derive(Debug, Clone, Default)]
+///
+/// With the `serde` feature, a row is written as the sequence of its values. Rows
+/// are equal when their values are, see [`Value`]'s `PartialEq`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Row {
     values: Vec<Value>,
 }
 
+impl<'a> IntoIterator for &'a Row {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+/// The values of selected columns of a row, e.g. the tags identifying a series,
+/// built by [`Row::key_for`]
+///
+/// Keys compare and hash by their values, following [`Value`]'s `PartialEq`, so
+/// they can be collected into a `HashSet` or used as `HashMap` keys to find rows
+/// sharing a key before an upsert.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RowKey {
+    values: Vec<Value>,
+}
+
+impl RowKey {
+    /// The values of the key, in the order of the key columns
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+}
+
 impl Row {
     /// Create a new empty row
     pub fn new() -> Self {
@@ -146,6 +1125,110 @@ impl Row {
         Self { values }
     }
 
+    /// The values of the row, in column order
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Iterate over the values of the row, in column order
+    pub fn iter(&self) -> std::slice::Iter<'_, Value> {
+        self.values.iter()
+    }
+
+    /// Iterate over the values of the row paired with the names of the columns of
+    /// `schema` they belong to
+    ///
+    /// Values are matched to columns by position; iteration stops at the end of the
+    /// shorter of the two, so check the row with [`TableSchema::validate_row`] first
+    /// if its width is not known to match.
+    pub fn iter_named<'a>(
+        &'a self,
+        schema: &'a TableSchema,
+    ) -> impl Iterator<Item = (&'a str, &'a Value)> {
+        schema
+            .columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .zip(&self.values)
+    }
+
+    /// Extract the values of `key_columns`, e.g. the tag columns of `schema`, as a
+    /// [`RowKey`] for deduplication
+    ///
+    /// Columns are looked up by name, see [`TableSchema::column_index`], and values
+    /// matched to them by position. Unknown columns and columns past the end of the
+    /// row are rejected.
+    pub fn key_for(&self, schema: &TableSchema, key_columns: &[&str]) -> Result<RowKey> {
+        let values = key_columns
+            .iter()
+            .map(|&name| {
+                let index = schema
+                    .column_index(name)
+                    .context(error::UnknownColumnSnafu { column: name })?;
+                self.values
+                    .get(index)
+                    .cloned()
+                    .context(error::InvalidColumnIndexSnafu {
+                        index,
+                        total: self.values.len(),
+                    })
+            })
+            .collect::<Result<_>>()?;
+        Ok(RowKey { values })
+    }
+
+    /// Replace every [`Value::Null`] in a column of `schema` that has a default by that
+    /// default, see [`TableSchema::with_column_default`]
+    ///
+    /// Values are matched to columns by position; non-null values are left untouched.
+    /// Use this on rows from sources that emit nulls for omitted fields.
+    pub fn fill_defaults(&mut self, schema: &TableSchema) {
+        if schema.column_defaults.is_empty() {
+            return;
+        }
+        for (column, value) in schema.columns.iter().zip(&mut self.values) {
+            if matches!(value, Value::Null) {
+                if let Some(default) = schema.column_defaults.get(&column.name) {
+                    *value = default.clone();
+                }
+            }
+        }
+    }
+
+    /// Set the value at index, padding the row with nulls if it is shorter
+    ///
+    /// Together with [`Row::set_string`] this lets a generator refill one row per
+    /// iteration instead of allocating a new one.
+    pub fn set_value(&mut self, index: usize, value: Value) {
+        if index >= self.values.len() {
+            self.values.resize(index + 1, Value::Null);
+        }
+        self.values[index] = value;
+    }
+
+    /// Set the value at index to a copy of `value`, reusing the allocation of the
+    /// string already there
+    pub fn set_string(&mut self, index: usize, value: &str) {
+        match self.values.get_mut(index) {
+            Some(Value::String(current)) => {
+                current.clear();
+                current.push_str(value);
+            }
+            _ => self.set_value(index, Value::String(value.to_string())),
+        }
+    }
+
+    /// Get a reference to the raw value at index
+    pub(crate) fn value(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    /// Report a mismatch found by a `try_get_*` accessor the way `get_*` accessors do
+    #[cold]
+    fn type_mismatch<T>(&self, error: ValueError) -> Option<T> {
+        handle_type_mismatch(error.index, error.expected, &self.values[error.index])
+    }
+
     /// Add a value to the row
     pub fn add_value(mut self, value: Value) -> Self {
         self.values.push(value);
@@ -164,12 +1247,43 @@ impl Row {
         self
     }
 
+    /// Convert into a row of the regular insert API
+    pub fn into_api(self) -> api::Row {
+        api::Row {
+            values: self.values.into_iter().map(api::Value::from).collect(),
+        }
+    }
+
+    /// Get the value at index as `T`, e.g. `row.get::<i64>(0)`
+    ///
+    /// Behaves like the `get_*` accessor for `T`, see [`FromValue`] for the mapping.
+    pub fn get<T: FromValue>(&self, index: usize) -> Option<T> {
+        T::try_from_row(self, index).unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get the value at index as `T` (returning an error on a type mismatch)
+    pub fn try_get<T: FromValue>(
+        &self,
+        index: usize,
+    ) -> std::result::Result<Option<T>, ValueError> {
+        T::try_from_row(self, index)
+    }
+
     /// Get boolean value at index (safe version with bounds checking)
     pub fn get_bool(&self, index: usize) -> Option<bool> {
-        match self.values.get(index)? {
-            Value::Boolean(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "boolean", other),
+        self.try_get_bool(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get boolean value at index (returning an error on a type mismatch)
+    pub fn try_get_bool(&self, index: usize) -> std::result::Result<Option<bool>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Boolean(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "boolean", other)),
         }
     }
 
@@ -186,10 +1300,19 @@ impl Row {
 
     /// Get i8 value at index (safe version with bounds checking)
     pub fn get_i8(&self, index: usize) -> Option<i8> {
-        match self.values.get(index)? {
-            Value::Int8(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "i8", other),
+        self.try_get_i8(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get i8 value at index (returning an error on a type mismatch)
+    pub fn try_get_i8(&self, index: usize) -> std::result::Result<Option<i8>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Int8(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "i8", other)),
         }
     }
 
@@ -206,10 +1329,19 @@ impl Row {
 
     /// Get i16 value at index (safe version with bounds checking)
     pub fn get_i16(&self, index: usize) -> Option<i16> {
-        match self.values.get(index)? {
-            Value::Int16(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "i16", other),
+        self.try_get_i16(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get i16 value at index (returning an error on a type mismatch)
+    pub fn try_get_i16(&self, index: usize) -> std::result::Result<Option<i16>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Int16(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "i16", other)),
         }
     }
 
@@ -226,10 +1358,19 @@ impl Row {
 
     /// Get i32 value at index (safe version with bounds checking)
     pub fn get_i32(&self, index: usize) -> Option<i32> {
-        match self.values.get(index)? {
-            Value::Int32(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "i32", other),
+        self.try_get_i32(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get i32 value at index (returning an error on a type mismatch)
+    pub fn try_get_i32(&self, index: usize) -> std::result::Result<Option<i32>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Int32(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "i32", other)),
         }
     }
 
@@ -246,10 +1387,19 @@ impl Row {
 
     /// Get i64 value at index (safe version with bounds checking)
     pub fn get_i64(&self, index: usize) -> Option<i64> {
-        match self.values.get(index)? {
-            Value::Int64(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "i64", other),
+        self.try_get_i64(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get i64 value at index (returning an error on a type mismatch)
+    pub fn try_get_i64(&self, index: usize) -> std::result::Result<Option<i64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Int64(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "i64", other)),
         }
     }
 
@@ -266,10 +1416,19 @@ impl Row {
 
     /// Get u8 value at index (safe version with bounds checking)
     pub fn get_u8(&self, index: usize) -> Option<u8> {
-        match self.values.get(index)? {
-            Value::Uint8(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "u8", other),
+        self.try_get_u8(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get u8 value at index (returning an error on a type mismatch)
+    pub fn try_get_u8(&self, index: usize) -> std::result::Result<Option<u8>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Uint8(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "u8", other)),
         }
     }
 
@@ -286,10 +1445,19 @@ impl Row {
 
     /// Get u16 value at index (safe version with bounds checking)
     pub fn get_u16(&self, index: usize) -> Option<u16> {
-        match self.values.get(index)? {
-            Value::Uint16(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "u16", other),
+        self.try_get_u16(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get u16 value at index (returning an error on a type mismatch)
+    pub fn try_get_u16(&self, index: usize) -> std::result::Result<Option<u16>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Uint16(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "u16", other)),
         }
     }
 
@@ -306,10 +1474,19 @@ impl Row {
 
     /// Get u32 value at index (safe version with bounds checking)
     pub fn get_u32(&self, index: usize) -> Option<u32> {
-        match self.values.get(index)? {
-            Value::Uint32(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "u32", other),
+        self.try_get_u32(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get u32 value at index (returning an error on a type mismatch)
+    pub fn try_get_u32(&self, index: usize) -> std::result::Result<Option<u32>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Uint32(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "u32", other)),
         }
     }
 
@@ -326,10 +1503,19 @@ impl Row {
 
     /// Get u64 value at index (safe version with bounds checking)
     pub fn get_u64(&self, index: usize) -> Option<u64> {
-        match self.values.get(index)? {
-            Value::Uint64(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "u64", other),
+        self.try_get_u64(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get u64 value at index (returning an error on a type mismatch)
+    pub fn try_get_u64(&self, index: usize) -> std::result::Result<Option<u64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Uint64(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "u64", other)),
         }
     }
 
@@ -346,10 +1532,19 @@ impl Row {
 
     /// Get f32 value at index (safe version with bounds checking)
     pub fn get_f32(&self, index: usize) -> Option<f32> {
-        match self.values.get(index)? {
-            Value::Float32(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "f32", other),
+        self.try_get_f32(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get f32 value at index (returning an error on a type mismatch)
+    pub fn try_get_f32(&self, index: usize) -> std::result::Result<Option<f32>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Float32(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "f32", other)),
         }
     }
 
@@ -366,10 +1561,19 @@ impl Row {
 
     /// Get f64 value at index (safe version with bounds checking)
     pub fn get_f64(&self, index: usize) -> Option<f64> {
-        match self.values.get(index)? {
-            Value::Float64(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "f64", other),
+        self.try_get_f64(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get f64 value at index (returning an error on a type mismatch)
+    pub fn try_get_f64(&self, index: usize) -> std::result::Result<Option<f64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Float64(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "f64", other)),
         }
     }
 
@@ -385,12 +1589,26 @@ impl Row {
     }
 
     /// Get binary value at index (safe version with bounds checking)
+    ///
+    /// A [`Value::BinaryChunks`] value is returned as the concatenation of its chunks.
     pub fn get_binary(&self, index: usize) -> Option<Vec<u8>> {
-        match self.values.get(index)? {
-            Value::Binary(v) => Some(v.clone()),
-            Value::String(v) => Some(v.as_bytes().to_vec()), // JSON type
-            Value::Null => None,
-            other => handle_type_mismatch(index, "binary", other),
+        self.try_get_binary(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get binary value at index (returning an error on a type mismatch)
+    ///
+    /// A [`Value::BinaryChunks`] value is returned as the concatenation of its chunks.
+    pub fn try_get_binary(&self, index: usize) -> std::result::Result<Option<Vec<u8>>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Binary(v) => Ok(Some(v.clone())),
+            Value::BinaryChunks(chunks) => Ok(Some(concat_chunks(chunks))),
+            Value::String(v) => Ok(Some(v.as_bytes().to_vec())), // JSON type
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "binary", other)),
         }
     }
 
@@ -400,6 +1618,7 @@ impl Row {
     pub unsafe fn get_binary_unchecked(&self, index: usize) -> Option<Vec<u8>> {
         match self.values.get_unchecked(index) {
             Value::Binary(v) => Some(v.clone()),
+            Value::BinaryChunks(chunks) => Some(concat_chunks(chunks)),
             Value::String(v) => Some(v.as_bytes().to_vec()), // JSON type
             Value::Null => None,
             other => handle_type_mismatch(index, "binary", other),
@@ -420,6 +1639,7 @@ impl Row {
     pub unsafe fn take_binary_unchecked(&mut self, index: usize) -> Option<Vec<u8>> {
         match std::mem::replace(self.values.get_unchecked_mut(index), Value::Null) {
             Value::Binary(v) => Some(v),
+            Value::BinaryChunks(chunks) => Some(concat_chunks(&chunks)),
             Value::String(v) => Some(v.into_bytes()), // JSON type
             Value::Null => None,
             other => handle_type_mismatch(index, "binary", &other),
@@ -428,10 +1648,19 @@ impl Row {
 
     /// Get string value at index (safe version with bounds checking)
     pub fn get_string(&self, index: usize) -> Option<String> {
-        match self.values.get(index)? {
-            Value::String(v) => Some(v.clone()),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "string", other),
+        self.try_get_string(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get string value at index (returning an error on a type mismatch)
+    pub fn try_get_string(&self, index: usize) -> std::result::Result<Option<String>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::String(v) => Ok(Some(v.clone())),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "string", other)),
         }
     }
 
@@ -465,12 +1694,73 @@ impl Row {
         }
     }
 
+    /// Get JSON value at index (safe version with bounds checking)
+    ///
+    /// Plain strings are accepted as well, for servers that return JSON as strings.
+    pub fn get_json(&self, index: usize) -> Option<String> {
+        self.try_get_json(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get JSON value at index (returning an error on a type mismatch)
+    ///
+    /// Plain strings are accepted as well, for servers that return JSON as strings.
+    pub fn try_get_json(&self, index: usize) -> std::result::Result<Option<String>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Json(v) | Value::String(v) => Ok(Some(v.clone())),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "json", other)),
+        }
+    }
+
+    /// Get JSON value at index (unsafe version without bounds checking)
+    /// # Safety
+    /// The caller must ensure that `index < self.values.len()`
+    pub unsafe fn get_json_unchecked(&self, index: usize) -> Option<String> {
+        match self.values.get_unchecked(index) {
+            Value::Json(v) | Value::String(v) => Some(v.clone()),
+            Value::Null => None,
+            other => handle_type_mismatch(index, "json", other),
+        }
+    }
+
+    /// Take JSON value at index (safe version with bounds checking)
+    pub fn take_json(&mut self, index: usize) -> Option<String> {
+        if index >= self.values.len() {
+            return None;
+        }
+        unsafe { self.take_json_unchecked(index) }
+    }
+
+    /// Take JSON value at index (unsafe version without bounds checking)
+    /// # Safety
+    /// The caller must ensure that `index < self.values.len()`
+    pub unsafe fn take_json_unchecked(&mut self, index: usize) -> Option<String> {
+        match std::mem::replace(self.values.get_unchecked_mut(index), Value::Null) {
+            Value::Json(v) | Value::String(v) => Some(v),
+            Value::Null => None,
+            other => handle_type_mismatch(index, "json", &other),
+        }
+    }
+
     /// Get date value at index (safe version with bounds checking)
     pub fn get_date(&self, index: usize) -> Option<i32> {
-        match self.values.get(index)? {
-            Value::Date(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "date", other),
+        self.try_get_date(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get date value at index (returning an error on a type mismatch)
+    pub fn try_get_date(&self, index: usize) -> std::result::Result<Option<i32>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Date(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "date", other)),
         }
     }
 
@@ -487,10 +1777,19 @@ impl Row {
 
     /// Get datetime value at index (safe version with bounds checking)
     pub fn get_datetime(&self, index: usize) -> Option<i64> {
-        match self.values.get(index)? {
-            Value::Datetime(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "datetime", other),
+        self.try_get_datetime(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get datetime value at index (returning an error on a type mismatch)
+    pub fn try_get_datetime(&self, index: usize) -> std::result::Result<Option<i64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Datetime(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "datetime", other)),
         }
     }
 
@@ -507,16 +1806,96 @@ impl Row {
 
     /// Get timestamp value at index (generic, supports all timestamp types, safe version with bounds checking)
     pub fn get_timestamp(&self, index: usize) -> Option<i64> {
+        self.try_get_timestamp(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get timestamp value at index (generic, supports all timestamp types, safe version with bounds checking)
+    pub fn try_get_timestamp(&self, index: usize) -> std::result::Result<Option<i64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::TimestampSecond(v) => Ok(Some(*v)),
+            Value::TimestampMillisecond(v) => Ok(Some(*v)),
+            Value::TimestampMicrosecond(v) => Ok(Some(*v)),
+            Value::TimestampNanosecond(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "timestamp", other)),
+        }
+    }
+
+    /// Get timestamp value at index in `unit`, scaled from whichever unit is stored
+    ///
+    /// Down-conversion drops sub-unit digits, rounding towards negative infinity;
+    /// see [`Value::rescale_timestamp`] to detect that. Values that overflow `unit`
+    /// return `None`.
+    pub fn get_timestamp_as(&self, index: usize, unit: TimeUnit) -> Option<i64> {
+        let value = self.values.get(index)?;
+        let Some((from, v)) = value.timestamp_parts() else {
+            return match value {
+                Value::Null => None,
+                other => handle_type_mismatch(index, "timestamp", other),
+            };
+        };
+        rescale_timestamp(v, from, unit).map(|(v, _)| v)
+    }
+
+    /// Get timestamp value at index as a UTC datetime, scaled from whichever unit is stored
+    ///
+    /// Every nanosecond timestamp fits into chrono's range (years 1677 to 2262); second,
+    /// millisecond and microsecond timestamps beyond chrono's range return `None`.
+    #[cfg(feature = "chrono")]
+    pub fn get_timestamp_datetime(&self, index: usize) -> Option<chrono::DateTime<chrono::Utc>> {
+        use chrono::DateTime;
+
         match self.values.get(index)? {
-            Value::TimestampSecond(v) => Some(*v),
-            Value::TimestampMillisecond(v) => Some(*v),
-            Value::TimestampMicrosecond(v) => Some(*v),
-            Value::TimestampNanosecond(v) => Some(*v),
+            Value::TimestampSecond(v) => DateTime::from_timestamp(*v, 0),
+            Value::TimestampMillisecond(v) => DateTime::from_timestamp_millis(*v),
+            Value::TimestampMicrosecond(v) => DateTime::from_timestamp_micros(*v),
+            Value::TimestampNanosecond(v) => Some(DateTime::from_timestamp_nanos(*v)),
             Value::Null => None,
             other => handle_type_mismatch(index, "timestamp", other),
         }
     }
 
+    /// Get a timestamp or datetime value at index as a UTC datetime
+    ///
+    /// Accepts every timestamp unit like [`Row::get_timestamp_datetime`], as well as
+    /// [`Value::Datetime`], which holds milliseconds since the Unix epoch.
+    #[cfg(feature = "chrono")]
+    pub fn get_datetime_utc(&self, index: usize) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.values.get(index)? {
+            Value::Datetime(v) => chrono::DateTime::from_timestamp_millis(*v),
+            _ => self.get_timestamp_datetime(index),
+        }
+    }
+
+    /// Get a date value at index as a calendar date
+    #[cfg(feature = "chrono")]
+    pub fn get_naive_date(&self, index: usize) -> Option<chrono::NaiveDate> {
+        let days = self.get_date(index)?;
+        unix_epoch_date().checked_add_signed(chrono::Duration::days(i64::from(days)))
+    }
+
+    /// Get a time value at index as a time of day, scaled from whichever unit is stored
+    ///
+    /// Values outside of a day return `None`.
+    #[cfg(feature = "chrono")]
+    pub fn get_naive_time(&self, index: usize) -> Option<chrono::NaiveTime> {
+        let nanos = match self.values.get(index)? {
+            Value::TimeSecond(v) => i64::from(*v) * 1_000_000_000,
+            Value::TimeMillisecond(v) => i64::from(*v) * 1_000_000,
+            Value::TimeMicrosecond(v) => v.checked_mul(1_000)?,
+            Value::TimeNanosecond(v) => *v,
+            Value::Null => return None,
+            other => return handle_type_mismatch(index, "time", other),
+        };
+        let seconds = u32::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+        let nanos = nanos.rem_euclid(1_000_000_000) as u32;
+        chrono::NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)
+    }
+
     /// Get timestamp value at index (generic, supports all timestamp types, unsafe version without bounds checking)
     /// # Safety
     /// The caller must ensure that `index < self.values.len()`
@@ -533,11 +1912,20 @@ impl Row {
 
     /// Get time32 value at index (safe version with bounds checking)
     pub fn get_time32(&self, index: usize) -> Option<i32> {
-        match self.values.get(index)? {
-            Value::TimeSecond(v) => Some(*v),
-            Value::TimeMillisecond(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "time32", other),
+        self.try_get_time32(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get time32 value at index (returning an error on a type mismatch)
+    pub fn try_get_time32(&self, index: usize) -> std::result::Result<Option<i32>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::TimeSecond(v) => Ok(Some(*v)),
+            Value::TimeMillisecond(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "time32", other)),
         }
     }
 
@@ -555,11 +1943,20 @@ impl Row {
 
     /// Get time64 value at index (safe version with bounds checking)
     pub fn get_time64(&self, index: usize) -> Option<i64> {
-        match self.values.get(index)? {
-            Value::TimeMicrosecond(v) => Some(*v),
-            Value::TimeNanosecond(v) => Some(*v),
-            Value::Null => None,
-            other => handle_type_mismatch(index, "time64", other),
+        self.try_get_time64(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get time64 value at index (returning an error on a type mismatch)
+    pub fn try_get_time64(&self, index: usize) -> std::result::Result<Option<i64>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::TimeMicrosecond(v) => Ok(Some(*v)),
+            Value::TimeNanosecond(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "time64", other)),
         }
     }
 
@@ -575,15 +1972,58 @@ impl Row {
         }
     }
 
-    /// Get decimal128 value at index (safe version with bounds checking)
-    pub fn get_decimal128(&self, index: usize) -> Option<i128> {
+    /// Get a time value at index as a duration, scaled from whichever unit is stored
+    ///
+    /// The reverse of [`Value::from_duration`]. Negative values return `None`.
+    pub fn get_duration(&self, index: usize) -> Option<Duration> {
         match self.values.get(index)? {
-            Value::Decimal128(v) => Some(*v),
+            Value::TimeSecond(v) => u64::try_from(*v).ok().map(Duration::from_secs),
+            Value::TimeMillisecond(v) => u64::try_from(*v).ok().map(Duration::from_millis),
+            Value::TimeMicrosecond(v) => u64::try_from(*v).ok().map(Duration::from_micros),
+            Value::TimeNanosecond(v) => u64::try_from(*v).ok().map(Duration::from_nanos),
             Value::Null => None,
-            other => handle_type_mismatch(index, "decimal128", other),
+            other => handle_type_mismatch(index, "time", other),
+        }
+    }
+
+    /// Get decimal128 value at index (safe version with bounds checking)
+    pub fn get_decimal128(&self, index: usize) -> Option<i128> {
+        self.try_get_decimal128(index)
+            .unwrap_or_else(|error| self.type_mismatch(error))
+    }
+
+    /// Get decimal128 value at index (returning an error on a type mismatch)
+    pub fn try_get_decimal128(
+        &self,
+        index: usize,
+    ) -> std::result::Result<Option<i128>, ValueError> {
+        let Some(value) = self.values.get(index) else {
+            return Ok(None);
+        };
+        match value {
+            Value::Decimal128(v) => Ok(Some(*v)),
+            Value::Null => Ok(None),
+            other => Err(ValueError::new(index, "decimal128", other)),
         }
     }
 
+    /// Get an unsigned 128-bit integer stored by [`Value::try_from_u128`] at index
+    ///
+    /// Returns `None` for nulls and for negative decimals.
+    pub fn get_u128(&self, index: usize) -> Option<u128> {
+        self.get_decimal128(index)
+            .and_then(|v| u128::try_from(v).ok())
+    }
+
+    /// Get the decimal128 value at index as a decimal string with `scale` fractional
+    /// digits, e.g. `"-1234.56"` for `Decimal128(-123456)` at scale 2
+    ///
+    /// The inverse of [`Value::decimal128_from_str`]; a negative scale appends zeros.
+    pub fn get_decimal128_as_string(&self, index: usize, scale: i8) -> Option<String> {
+        self.get_decimal128(index)
+            .map(|v| format_decimal128(v, scale))
+    }
+
     /// Get decimal128 value at index (unsafe version without bounds checking)
     /// # Safety
     /// The caller must ensure that `index < self.values.len()`
@@ -596,6 +2036,457 @@ impl Row {
     }
 }
 
+/// A row that stores only the values that are set, keyed by column index
+///
+/// Useful for wide tables where each row fills in a handful of columns. Convert it
+/// into a dense [`Row`] against the table schema before writing; unset columns are
+/// filled with [`Value::Null`].
+#[derive(Debug, Clone, Default)]
+pub struct SparseRow {
+    values: Vec<(usize, Value)>,
+}
+
+impl SparseRow {
+    /// Create a new empty sparse row
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new sparse row with pre-allocated capacity
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Get the number of values that are set
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if no value is set
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Set the value of the column at `index`, replacing any previous value
+    pub fn set(mut self, index: usize, value: Value) -> Self {
+        match self.values.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, existing)) => *existing = value,
+            None => self.values.push((index, value)),
+        }
+        self
+    }
+
+    /// Get the value of the column at `index`, if it is set
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.values
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, value)| value)
+    }
+
+    /// Get the set values as `(column index, value)` pairs, in insertion order
+    pub fn values(&self) -> &[(usize, Value)] {
+        &self.values
+    }
+
+    /// Convert into a dense row with one value per column of `schema`
+    pub fn into_dense(self, schema: &TableSchema) -> Result<Row> {
+        self.into_dense_with_width(schema.columns().len())
+    }
+
+    pub(crate) fn into_dense_with_width(self, width: usize) -> Result<Row> {
+        let mut values = vec![Value::Null; width];
+        for (index, value) in self.values {
+            ensure!(
+                index < width,
+                error::InvalidColumnIndexSnafu {
+                    index,
+                    total: width,
+                }
+            );
+            values[index] = value;
+        }
+        Ok(Row::from_values(values))
+    }
+
+    /// Create a sparse row from a dense row, dropping null values
+    pub fn from_dense(row: Row) -> Self {
+        let values = row
+            .values
+            .into_iter()
+            .enumerate()
+            .filter(|(_, value)| !matches!(value, Value::Null))
+            .collect();
+        Self { values }
+    }
+}
+
+/// Largest unscaled value of a `Decimal128` column at its maximum precision of 38 digits
+const MAX_DECIMAL128: i128 = 10_i128.pow(38) - 1;
+
+/// Get the smallest and largest value representable by a column type
+///
+/// Decimals use the maximum precision of 38 digits; time-of-day types are bounded
+/// by a single day. Returns `None` for types without a natural order or bound,
+/// such as strings, binaries and JSON.
+pub fn value_bounds(data_type: ColumnDataType) -> Option<(Value, Value)> {
+    Some(match data_type {
+        ColumnDataType::Boolean => (Value::Boolean(false), Value::Boolean(true)),
+        ColumnDataType::Int8 => (Value::Int8(i8::MIN), Value::Int8(i8::MAX)),
+        ColumnDataType::Int16 => (Value::Int16(i16::MIN), Value::Int16(i16::MAX)),
+        ColumnDataType::Int32 => (Value::Int32(i32::MIN), Value::Int32(i32::MAX)),
+        ColumnDataType::Int64 => (Value::Int64(i64::MIN), Value::Int64(i64::MAX)),
+        ColumnDataType::Uint8 => (Value::Uint8(u8::MIN), Value::Uint8(u8::MAX)),
+        ColumnDataType::Uint16 => (Value::Uint16(u16::MIN), Value::Uint16(u16::MAX)),
+        ColumnDataType::Uint32 => (Value::Uint32(u32::MIN), Value::Uint32(u32::MAX)),
+        ColumnDataType::Uint64 => (Value::Uint64(u64::MIN), Value::Uint64(u64::MAX)),
+        ColumnDataType::Float32 => (Value::Float32(f32::MIN), Value::Float32(f32::MAX)),
+        ColumnDataType::Float64 => (Value::Float64(f64::MIN), Value::Float64(f64::MAX)),
+        ColumnDataType::Date => (Value::Date(i32::MIN), Value::Date(i32::MAX)),
+        ColumnDataType::Datetime => (Value::Datetime(i64::MIN), Value::Datetime(i64::MAX)),
+        ColumnDataType::TimestampSecond => (
+            Value::TimestampSecond(i64::MIN),
+            Value::TimestampSecond(i64::MAX),
+        ),
+        ColumnDataType::TimestampMillisecond => (
+            Value::TimestampMillisecond(i64::MIN),
+            Value::TimestampMillisecond(i64::MAX),
+        ),
+        ColumnDataType::TimestampMicrosecond => (
+            Value::TimestampMicrosecond(i64::MIN),
+            Value::TimestampMicrosecond(i64::MAX),
+        ),
+        ColumnDataType::TimestampNanosecond => (
+            Value::TimestampNanosecond(i64::MIN),
+            Value::TimestampNanosecond(i64::MAX),
+        ),
+        ColumnDataType::TimeSecond => (Value::TimeSecond(0), Value::TimeSecond(86_399)),
+        ColumnDataType::TimeMillisecond => (
+            Value::TimeMillisecond(0),
+            Value::TimeMillisecond(86_399_999),
+        ),
+        ColumnDataType::TimeMicrosecond => (
+            Value::TimeMicrosecond(0),
+            Value::TimeMicrosecond(86_399_999_999),
+        ),
+        ColumnDataType::TimeNanosecond => (
+            Value::TimeNanosecond(0),
+            Value::TimeNanosecond(86_399_999_999_999),
+        ),
+        ColumnDataType::Decimal128 => (
+            Value::Decimal128(-MAX_DECIMAL128),
+            Value::Decimal128(MAX_DECIMAL128),
+        ),
+        _ => return None,
+    })
+}
+
+/// Compare two rows column by column, matching columns by name instead of position
+///
+/// Returns `true` if both schemas have the same column names and every column holds
+/// equal values in both rows. Floats compare by value, with NaN equal to NaN.
+pub fn rows_equal_by_name(
+    a: &Row,
+    a_schema: &TableSchema,
+    b: &Row,
+    b_schema: &TableSchema,
+) -> bool {
+    if a.len() != a_schema.columns.len()
+        || b.len() != b_schema.columns.len()
+        || a_schema.columns.len() != b_schema.columns.len()
+    {
+        return false;
+    }
+
+    a_schema
+        .columns
+        .iter()
+        .zip(&a.values)
+        .all(|(column, a_value)| {
+            b_schema
+                .columns
+                .iter()
+                .position(|b_column| b_column.name == column.name)
+                .is_some_and(|index| *a_value == b.values[index])
+        })
+}
+
+/// Render the unscaled decimal `v` with `scale` fractional digits
+fn format_decimal128(v: i128, scale: i8) -> String {
+    let sign = if v < 0 { "-" } else { "" };
+    let digits = v.unsigned_abs().to_string();
+    if scale <= 0 {
+        let zeros = if v == 0 {
+            0
+        } else {
+            scale.unsigned_abs() as usize
+        };
+        return format!("{sign}{digits}{}", "0".repeat(zeros));
+    }
+
+    let scale = scale as usize;
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{sign}{integer}.{fraction}")
+}
+
+/// Quote `name` as a SQL identifier
+fn quote_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// Convert `rows` holding one value per column of `columns` to API rows
+fn api_rows<'a>(columns: impl Iterator<Item = &'a Column>, rows: Vec<Row>) -> Result<api::Rows> {
+    let schema: Vec<api::ColumnSchema> = columns.map(Column::to_api).collect();
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            ensure!(
+                row.len() == schema.len(),
+                error::InvalidColumnCountSnafu {
+                    expected: schema.len(),
+                    actual: row.len(),
+                }
+            );
+            Ok(row.into_api())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(api::Rows { schema, rows })
+}
+
+/// Iterate over the values of `row` together with the columns of `schema` they belong to
+///
+/// Values and columns are paired by position. If the row and the schema differ in
+/// length, the iterator stops at the shorter of the two, silently skipping the extra
+/// values or columns; compare `row.len()` with `schema.columns().len()` first where a
+/// mismatch must be detected.
+pub fn iter_with_columns<'a>(
+    row: &'a Row,
+    schema: &'a TableSchema,
+) -> impl Iterator<Item = (&'a Column, &'a Value)> {
+    schema.columns.iter().zip(&row.values)
+}
+
+/// Turn row-major `rows` into one vector of values per column
+///
+/// Fails if a row does not have exactly `column_count` values.
+pub fn transpose_rows(rows: &[Row], column_count: usize) -> Result<Vec<Vec<Value>>> {
+    let mut columns: Vec<Vec<Value>> = (0..column_count)
+        .map(|_| Vec::with_capacity(rows.len()))
+        .collect();
+    for row in rows {
+        ensure!(
+            row.len() == column_count,
+            error::InvalidColumnCountSnafu {
+                expected: column_count,
+                actual: row.len(),
+            }
+        );
+        for (column, value) in columns.iter_mut().zip(&row.values) {
+            column.push(value.clone());
+        }
+    }
+    Ok(columns)
+}
+
+/// Resolve a column type from its name, accepting common SQL aliases
+///
+/// Matching is case-insensitive and ignores a trailing argument list, so `varchar(255)`
+/// and `DECIMAL(10, 2)` resolve as well. GreptimeDB's own names (`INT64`,
+/// `TIMESTAMP_MILLISECOND`, ...) are always accepted; the aliases are:
+///
+/// | Alias                                | Type                                 |
+/// |--------------------------------------|--------------------------------------|
+/// | `BOOL`                               | `Boolean`                            |
+/// | `TINYINT`                            | `Int8`                               |
+/// | `SMALLINT`                           | `Int16`                              |
+/// | `INT`, `INTEGER`                     | `Int32`                              |
+/// | `BIGINT`                             | `Int64`                              |
+/// | `FLOAT`, `REAL`                      | `Float32`                            |
+/// | `DOUBLE`, `DOUBLE PRECISION`         | `Float64`                            |
+/// | `VARCHAR`, `CHAR`, `TEXT`            | `String`                             |
+/// | `VARBINARY`, `BLOB`, `BYTEA`         | `Binary`                             |
+/// | `DECIMAL`, `NUMERIC`                 | `Decimal128`                         |
+/// | `TIMESTAMP`                          | `TimestampMillisecond`               |
+/// | `TIMESTAMP(0)`, `(3)`, `(6)`, `(9)`  | second, milli-, micro-, nanosecond   |
+pub fn column_data_type_from_name(name: &str) -> Option<ColumnDataType> {
+    let name = name.trim().to_ascii_uppercase();
+    let (base, args) = match name.split_once('(') {
+        Some((base, args)) => (base.trim_end(), Some(args.strip_suffix(')')?.trim())),
+        None => (name.as_str(), None),
+    };
+
+    let data_type = match base {
+        "BOOL" => ColumnDataType::Boolean,
+        "TINYINT" => ColumnDataType::Int8,
+        "SMALLINT" => ColumnDataType::Int16,
+        "INT" | "INTEGER" => ColumnDataType::Int32,
+        "BIGINT" => ColumnDataType::Int64,
+        "FLOAT" | "REAL" => ColumnDataType::Float32,
+        "DOUBLE" | "DOUBLE PRECISION" => ColumnDataType::Float64,
+        "VARCHAR" | "CHAR" | "TEXT" => ColumnDataType::String,
+        "VARBINARY" | "BLOB" | "BYTEA" => ColumnDataType::Binary,
+        "DECIMAL" | "NUMERIC" => ColumnDataType::Decimal128,
+        "TIMESTAMP" => match args {
+            None | Some("3") => ColumnDataType::TimestampMillisecond,
+            Some("0") => ColumnDataType::TimestampSecond,
+            Some("6") => ColumnDataType::TimestampMicrosecond,
+            Some("9") => ColumnDataType::TimestampNanosecond,
+            Some(_) => return None,
+        },
+        other => ColumnDataType::from_str_name(other)?,
+    };
+    Some(data_type)
+}
+
+/// A value of an unexpected type, returned by the `try_get_*` accessors of [`Row`]
+///
+/// The `get_*` accessors panic on a type mismatch in debug builds and return `None`
+/// in release builds; `try_get_*` report it as this error in every profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError {
+    index: usize,
+    expected: &'static str,
+    actual: &'static str,
+}
+
+impl ValueError {
+    fn new(index: usize, expected: &'static str, actual: &Value) -> Self {
+        Self {
+            index,
+            expected,
+            actual: actual.variant_name(),
+        }
+    }
+
+    /// Index of the mismatched value in the row
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Name of the expected type, such as `i32` or `timestamp`
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    /// Name of the [`Value`] variant found instead
+    pub fn actual(&self) -> &'static str {
+        self.actual
+    }
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Expected `{}` value at index {}, got {}",
+            self.expected, self.index, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Why [`Value::decimal128_from_str`] rejected a string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecimalErrorKind {
+    /// Not an optional sign followed by digits with at most one decimal point
+    Syntax,
+    /// Non-zero digits that the scale would round away
+    Precision,
+    /// More than the 38 digits of a GreptimeDB decimal
+    Overflow,
+}
+
+/// A string rejected by [`Value::decimal128_from_str`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecimalError {
+    text: String,
+    scale: i8,
+    kind: DecimalErrorKind,
+}
+
+impl DecimalError {
+    /// The rejected string
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The scale the string was parsed for
+    pub fn scale(&self) -> i8 {
+        self.scale
+    }
+
+    /// Why the string was rejected
+    pub fn kind(&self) -> DecimalErrorKind {
+        self.kind
+    }
+}
+
+impl std::fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.kind {
+            DecimalErrorKind::Syntax => "expected digits with an optional sign and decimal point",
+            DecimalErrorKind::Precision => "more digits than the scale keeps",
+            DecimalErrorKind::Overflow => "more than 38 digits",
+        };
+        write!(
+            f,
+            "Invalid decimal `{}` for scale {}: {}",
+            self.text, self.scale, reason
+        )
+    }
+}
+
+impl std::error::Error for DecimalError {}
+
+/// Rust types that can be read from a [`Row`] with [`Row::get`] and [`Row::try_get`]
+///
+/// Each type reads the same [`Value`] variants as its `try_get_*` accessor: `i64`
+/// reads [`Value::Int64`], `String` reads [`Value::String`], `Vec<u8>` reads binary
+/// values and `i128` reads [`Value::Decimal128`]. Timestamps, dates and JSON share
+/// their Rust type with other variants, so they are read with their explicit
+/// accessors such as [`Row::get_timestamp`].
+pub trait FromValue: Sized {
+    /// Read the value at `index`, `Ok(None)` for nulls and out of bounds indices
+    fn try_from_row(row: &Row, index: usize) -> std::result::Result<Option<Self>, ValueError>;
+}
+
+macro_rules! impl_from_value {
+    ($($ty:ty => $accessor:ident),* $(,)?) => {
+        $(
+            impl FromValue for $ty {
+                fn try_from_row(
+                    row: &Row,
+                    index: usize,
+                ) -> std::result::Result<Option<Self>, ValueError> {
+                    row.$accessor(index)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value!(
+    bool => try_get_bool,
+    i8 => try_get_i8,
+    i16 => try_get_i16,
+    i32 => try_get_i32,
+    i64 => try_get_i64,
+    u8 => try_get_u8,
+    u16 => try_get_u16,
+    u32 => try_get_u32,
+    u64 => try_get_u64,
+    f32 => try_get_f32,
+    f64 => try_get_f64,
+    i128 => try_get_decimal128,
+    String => try_get_string,
+    Vec<u8> => try_get_binary,
+);
+
 /// Handle type mismatch with debug assertion
 #[inline]
 fn handle_type_mismatch<T>(index: usize, expected: &str, actual: &Value) -> Option<T> {
@@ -605,8 +2496,25 @@ fn handle_type_mismatch<T>(index: usize, expected: &str, actual: &Value) -> Opti
     None
 }
 
+/// Join binary chunks into one contiguous buffer
+fn concat_chunks(chunks: &[Bytes]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(chunks.iter().map(Bytes::len).sum());
+    for chunk in chunks {
+        bytes.extend_from_slice(chunk);
+    }
+    bytes
+}
+
 /// Type-safe value wrapper for all GreptimeDB data types
+///
+/// With the `serde` feature, a value is written tagged with its variant, e.g.
+/// `{"type": "TimestampMillisecond", "value": 1700000000000}`, so the unit survives a
+/// round trip. In human-readable formats binary values are written as base64 and
+/// decimals as strings, keeping the full `i128` precision; [`Value::BinaryChunks`]
+/// is written like [`Value::Binary`] and read back as a single chunk.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Value {
     // Boolean
     Boolean(bool),
@@ -626,7 +2534,15 @@ pub enum Value {
     Float64(f64),
 
     // String and Binary types
+    #[cfg_attr(feature = "serde", serde(with = "binary_serde"))]
     Binary(Vec<u8>),
+    /// A binary value held as a sequence of chunks, for large blobs.
+    ///
+    /// The chunks are written one after another straight into the Arrow buffer of the
+    /// batch, so a multi-megabyte blob never needs a single allocation of its own,
+    /// and chunks sliced from a larger `Bytes` are shared rather than copied.
+    #[cfg_attr(feature = "serde", serde(with = "binary_chunks_serde"))]
+    BinaryChunks(Vec<Bytes>),
     String(String),
 
     // Date and Time types
@@ -646,6 +2562,7 @@ pub enum Value {
     TimeNanosecond(i64),
 
     // Decimal type (`precision` and `scale` are placed in the column schema)
+    #[cfg_attr(feature = "serde", serde(with = "decimal128_serde"))]
     Decimal128(i128),
 
     // JSON type (stored as string)
@@ -655,10 +2572,1270 @@ pub enum Value {
     Null,
 }
 
-#[cfg(test)]
+impl Value {
+    /// Name of the variant, without its payload
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Value::Boolean(_) => "Boolean",
+            Value::Int8(_) => "Int8",
+            Value::Int16(_) => "Int16",
+            Value::Int32(_) => "Int32",
+            Value::Int64(_) => "Int64",
+            Value::Uint8(_) => "Uint8",
+            Value::Uint16(_) => "Uint16",
+            Value::Uint32(_) => "Uint32",
+            Value::Uint64(_) => "Uint64",
+            Value::Float32(_) => "Float32",
+            Value::Float64(_) => "Float64",
+            Value::Binary(_) => "Binary",
+            Value::BinaryChunks(_) => "BinaryChunks",
+            Value::String(_) => "String",
+            Value::Date(_) => "Date",
+            Value::Datetime(_) => "Datetime",
+            Value::TimestampSecond(_) => "TimestampSecond",
+            Value::TimestampMillisecond(_) => "TimestampMillisecond",
+            Value::TimestampMicrosecond(_) => "TimestampMicrosecond",
+            Value::TimestampNanosecond(_) => "TimestampNanosecond",
+            Value::TimeSecond(_) => "TimeSecond",
+            Value::TimeMillisecond(_) => "TimeMillisecond",
+            Value::TimeMicrosecond(_) => "TimeMicrosecond",
+            Value::TimeNanosecond(_) => "TimeNanosecond",
+            Value::Decimal128(_) => "Decimal128",
+            Value::Json(_) => "Json",
+            Value::Null => "Null",
+        }
+    }
+
+    /// Decode a hex string into a binary value
+    ///
+    /// Accepts two digits per byte in either case, with or without a `0x` prefix.
+    pub fn binary_from_hex(text: &str) -> Result<Self> {
+        BinaryEncoding::Hex.decode(text).map(Value::Binary)
+    }
+
+    /// Decode a base64 string into a binary value
+    ///
+    /// Accepts the standard alphabet of RFC 4648 with padding.
+    pub fn binary_from_base64(text: &str) -> Result<Self> {
+        BinaryEncoding::Base64.decode(text).map(Value::Binary)
+    }
+
+    /// Store an unsigned 128-bit integer, such as a counter that outgrew `u64`
+    ///
+    /// GreptimeDB has no 128-bit integer type, so the value is stored as a `Decimal128`
+    /// with scale 0 and must be written to a decimal column with a scale of 0. Decimals
+    /// hold at most 38 digits, so values above `10^38 - 1` are rejected. Read it back
+    /// with [`Row::get_u128`].
+    pub fn try_from_u128(v: u128) -> Result<Self> {
+        match i128::try_from(v) {
+            Ok(v) if v <= MAX_DECIMAL128 => Ok(Value::Decimal128(v)),
+            _ => error::ValueOutOfRangeSnafu {
+                value: v.to_string(),
+                target: "Decimal128",
+            }
+            .fail(),
+        }
+    }
+
+    /// Parse a decimal string such as `"-1234.56"` into the unscaled integer of a
+    /// decimal column with `scale`, e.g. `Decimal128(-123456)` for a scale of 2
+    ///
+    /// The text is an optional sign followed by digits with at most one decimal
+    /// point; exponents and whitespace are not accepted. Fewer fractional digits
+    /// than `scale` are padded, so `"12.5"` is `1250` at scale 2. More are rejected
+    /// unless the extra ones are zeros, as they would be rounded away; the same goes
+    /// for the integer digits a negative scale drops. Values above 38 digits, the
+    /// precision limit of GreptimeDB decimals, are rejected as well; see
+    /// [`DecimalErrorKind`] for the reasons. Read the value back with
+    /// [`Row::get_decimal128_as_string`].
+    pub fn decimal128_from_str(text: &str, scale: i8) -> std::result::Result<Self, DecimalError> {
+        let invalid = |kind| DecimalError {
+            text: text.to_string(),
+            scale,
+            kind,
+        };
+
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(invalid(DecimalErrorKind::Syntax));
+        }
+        if !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid(DecimalErrorKind::Syntax));
+        }
+
+        // All digits as one integer, scaled by 10^shift to the unscaled value
+        let digits = format!("{integer}{fraction}");
+        let digits = digits.trim_start_matches('0');
+        let shift = i32::from(scale) - fraction.len() as i32;
+        let kept = if shift >= 0 {
+            digits.to_string() + &"0".repeat(shift as usize)
+        } else {
+            let dropped = shift.unsigned_abs() as usize;
+            let split = digits.len().saturating_sub(dropped);
+            if digits[split..].bytes().any(|b| b != b'0') {
+                return Err(invalid(DecimalErrorKind::Precision));
+            }
+            digits[..split].to_string()
+        };
+
+        let magnitude = if kept.is_empty() {
+            0
+        } else {
+            kept.parse::<i128>()
+                .ok()
+                .filter(|v| *v <= MAX_DECIMAL128)
+                .ok_or_else(|| invalid(DecimalErrorKind::Overflow))?
+        };
+        Ok(Value::Decimal128(if negative {
+            -magnitude
+        } else {
+            magnitude
+        }))
+    }
+
+    /// A timestamp of `value` ticks of `unit` since the Unix epoch, e.g.
+    /// `Value::TimestampMillisecond(value)` for [`TimeUnit::Millisecond`]
+    pub fn timestamp_in(unit: TimeUnit, value: i64) -> Self {
+        match unit {
+            TimeUnit::Second => Value::TimestampSecond(value),
+            TimeUnit::Millisecond => Value::TimestampMillisecond(value),
+            TimeUnit::Microsecond => Value::TimestampMicrosecond(value),
+            TimeUnit::Nanosecond => Value::TimestampNanosecond(value),
+        }
+    }
+
+    /// The unit and raw value of a timestamp, `None` for any other value
+    fn timestamp_parts(&self) -> Option<(TimeUnit, i64)> {
+        match self {
+            Value::TimestampSecond(v) => Some((TimeUnit::Second, *v)),
+            Value::TimestampMillisecond(v) => Some((TimeUnit::Millisecond, *v)),
+            Value::TimestampMicrosecond(v) => Some((TimeUnit::Microsecond, *v)),
+            Value::TimestampNanosecond(v) => Some((TimeUnit::Nanosecond, *v)),
+            _ => None,
+        }
+    }
+
+    /// Rescale a timestamp to `unit`, e.g. a nanosecond source for a millisecond
+    /// time index, returning the value of the matching variant and whether precision
+    /// was lost
+    ///
+    /// Down-conversion drops the sub-unit digits, rounding towards negative infinity
+    /// so that `TimestampNanosecond(-1)` becomes `TimestampMillisecond(-1)`; the flag
+    /// is `true` when any of them were not zero. Up-conversion is exact but fails with
+    /// `ValueOutOfRange` when the result overflows an `i64`, e.g. seconds beyond the
+    /// year 2262 as nanoseconds. `Null` is returned as it is; other values are
+    /// rejected.
+    pub fn rescale_timestamp(&self, unit: TimeUnit) -> Result<(Self, bool)> {
+        if matches!(self, Value::Null) {
+            return Ok((Value::Null, false));
+        }
+        let (from, v) =
+            self.timestamp_parts()
+                .with_context(|| error::UnsupportedDataTypeSnafu {
+                    data_type: format!("{self:?}. Only timestamps can be rescaled"),
+                })?;
+        let (v, lossy) =
+            rescale_timestamp(v, from, unit).with_context(|| error::ValueOutOfRangeSnafu {
+                value: format!("{self:?}"),
+                target: format!("timestamp in {unit:?}"),
+            })?;
+        Ok((Self::timestamp_in(unit, v), lossy))
+    }
+
+    /// Store a duration, such as a latency, in a time column of type `target`
+    ///
+    /// The duration is counted in the unit of `target` and truncated to it, so 1.5ms
+    /// becomes `TimeMillisecond(1)`. `TimeSecond` and `TimeMillisecond` hold an `i32`
+    /// and overflow past about 68 years and 24 days respectively; durations that do
+    /// not fit are rejected rather than wrapped. Pick `TimeMicrosecond` to keep
+    /// sub-millisecond precision for durations of any practical length.
+    ///
+    /// Interval types have no [`Value`] representation in this client, so only the
+    /// time types are accepted as `target`.
+    pub fn from_duration(d: Duration, target: ColumnDataType) -> Result<Self> {
+        let out_of_range = || {
+            error::ValueOutOfRangeSnafu {
+                value: format!("{d:?}"),
+                target: format!("{target:?}"),
+            }
+            .build()
+        };
+        match target {
+            ColumnDataType::TimeSecond => i32::try_from(d.as_secs())
+                .map(Value::TimeSecond)
+                .map_err(|_| out_of_range()),
+            ColumnDataType::TimeMillisecond => i32::try_from(d.as_millis())
+                .map(Value::TimeMillisecond)
+                .map_err(|_| out_of_range()),
+            ColumnDataType::TimeMicrosecond => i64::try_from(d.as_micros())
+                .map(Value::TimeMicrosecond)
+                .map_err(|_| out_of_range()),
+            ColumnDataType::TimeNanosecond => i64::try_from(d.as_nanos())
+                .map(Value::TimeNanosecond)
+                .map_err(|_| out_of_range()),
+            _ => error::UnsupportedDataTypeSnafu {
+                data_type: format!("{target:?}. Durations can only be stored in time columns"),
+            }
+            .fail(),
+        }
+    }
+
+    /// A deterministic token standing in for this value, e.g. to mask identifiers
+    ///
+    /// The token is the hex encoded SHA-256 of `salt`, the value's type and its bytes,
+    /// so equal values hash to equal tokens across rows, processes and releases, and
+    /// masked columns can still be joined on. Values of different types never share a
+    /// token, even when they hold the same number; `Binary` and `BinaryChunks` with the
+    /// same bytes do.
+    ///
+    /// This is pseudonymization, not encryption. The token cannot be decrypted, but
+    /// anyone knowing the salt can hash candidate values and compare, which recovers
+    /// low-entropy values such as small integers or known hostnames quickly. Keep the
+    /// salt secret and treat masked data as personal data where the law requires it.
+    /// Changing the salt changes every token, breaking joins with data masked before.
+    pub fn stable_masked_token(&self, salt: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update((salt.len() as u64).to_le_bytes());
+        hasher.update(salt);
+        let type_name = match self {
+            Value::BinaryChunks(_) => "Binary",
+            other => other.variant_name(),
+        };
+        hasher.update(type_name.as_bytes());
+        hasher.update([0]);
+        match self {
+            Value::Boolean(v) => hasher.update([u8::from(*v)]),
+            Value::Int8(v) => hasher.update(v.to_le_bytes()),
+            Value::Int16(v) => hasher.update(v.to_le_bytes()),
+            Value::Int32(v) | Value::Date(v) | Value::TimeSecond(v) | Value::TimeMillisecond(v) => {
+                hasher.update(v.to_le_bytes())
+            }
+            Value::Int64(v)
+            | Value::Datetime(v)
+            | Value::TimestampSecond(v)
+            | Value::TimestampMillisecond(v)
+            | Value::TimestampMicrosecond(v)
+            | Value::TimestampNanosecond(v)
+            | Value::TimeMicrosecond(v)
+            | Value::TimeNanosecond(v) => hasher.update(v.to_le_bytes()),
+            Value::Uint8(v) => hasher.update(v.to_le_bytes()),
+            Value::Uint16(v) => hasher.update(v.to_le_bytes()),
+            Value::Uint32(v) => hasher.update(v.to_le_bytes()),
+            Value::Uint64(v) => hasher.update(v.to_le_bytes()),
+            Value::Float32(v) => hasher.update(v.to_bits().to_le_bytes()),
+            Value::Float64(v) => hasher.update(v.to_bits().to_le_bytes()),
+            Value::Binary(v) => hasher.update(v),
+            Value::BinaryChunks(chunks) => chunks.iter().for_each(|chunk| hasher.update(chunk)),
+            Value::String(v) | Value::Json(v) => hasher.update(v.as_bytes()),
+            Value::Decimal128(v) => hasher.update(v.to_le_bytes()),
+            Value::Null => {}
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+// Conversions from primitive types. Integers never convert into the timestamp,
+// date or time variants, whose unit cannot be told from the integer alone.
+macro_rules! impl_from_for_value {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(v: $ty) -> Self {
+                    Value::$variant(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_value! {
+    bool => Boolean,
+    i8 => Int8,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    u8 => Uint8,
+    u16 => Uint16,
+    u32 => Uint32,
+    u64 => Uint64,
+    f32 => Float32,
+    f64 => Float64,
+    String => String,
+    Vec<u8> => Binary,
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Self {
+        Value::Binary(v.to_vec())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+/// Values are equal when they have the same variant and payload, with two exceptions
+/// that keep equality an equivalence relation, so it can back `assert_eq!` and `Eq`:
+///
+/// - floats compare by IEEE 754 value, except that any NaN equals any NaN; `0.0`
+///   and `-0.0` are equal as in IEEE 754;
+/// - [`Value::Binary`] and [`Value::BinaryChunks`] are equal when they hold the same
+///   bytes, however these are chunked.
+///
+/// Values of different variants are otherwise never equal, even where a column
+/// would accept both, e.g. `Int32(1) != Int64(1)`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        use Value::*;
+
+        match (self, other) {
+            (Boolean(a), Boolean(b)) => a == b,
+            (Int8(a), Int8(b)) => a == b,
+            (Int16(a), Int16(b)) => a == b,
+            (Int32(a), Int32(b)) => a == b,
+            (Int64(a), Int64(b)) => a == b,
+            (Uint8(a), Uint8(b)) => a == b,
+            (Uint16(a), Uint16(b)) => a == b,
+            (Uint32(a), Uint32(b)) => a == b,
+            (Uint64(a), Uint64(b)) => a == b,
+            (Float32(a), Float32(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Float64(a), Float64(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Binary(a), Binary(b)) => a == b,
+            (BinaryChunks(a), BinaryChunks(b)) => concat_chunks(a) == concat_chunks(b),
+            (BinaryChunks(a), Binary(b)) | (Binary(b), BinaryChunks(a)) => concat_chunks(a) == *b,
+            (String(a), String(b)) | (Json(a), Json(b)) => a == b,
+            (Date(a), Date(b)) => a == b,
+            (Datetime(a), Datetime(b)) => a == b,
+            (TimestampSecond(a), TimestampSecond(b))
+            | (TimestampMillisecond(a), TimestampMillisecond(b))
+            | (TimestampMicrosecond(a), TimestampMicrosecond(b))
+            | (TimestampNanosecond(a), TimestampNanosecond(b)) => a == b,
+            (TimeSecond(a), TimeSecond(b)) | (TimeMillisecond(a), TimeMillisecond(b)) => a == b,
+            (TimeMicrosecond(a), TimeMicrosecond(b)) | (TimeNanosecond(a), TimeNanosecond(b)) => {
+                a == b
+            }
+            (Decimal128(a), Decimal128(b)) => a == b,
+            (Null, Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Hashes agree with `PartialEq`: `Binary` and `BinaryChunks` with the same bytes
+/// hash alike, as do all NaNs, and `0.0` and `-0.0`.
+impl std::hash::Hash for Value {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        use Value::*;
+
+        match self {
+            BinaryChunks(_) => std::mem::discriminant(&Binary(Vec::new())).hash(state),
+            other => std::mem::discriminant(other).hash(state),
+        }
+        match self {
+            Boolean(v) => v.hash(state),
+            Int8(v) => v.hash(state),
+            Int16(v) => v.hash(state),
+            Int32(v) | Date(v) | TimeSecond(v) | TimeMillisecond(v) => v.hash(state),
+            Int64(v)
+            | Datetime(v)
+            | TimestampSecond(v)
+            | TimestampMillisecond(v)
+            | TimestampMicrosecond(v)
+            | TimestampNanosecond(v)
+            | TimeMicrosecond(v)
+            | TimeNanosecond(v) => v.hash(state),
+            Uint8(v) => v.hash(state),
+            Uint16(v) => v.hash(state),
+            Uint32(v) => v.hash(state),
+            Uint64(v) => v.hash(state),
+            Float32(v) => canonical_float_bits(f64::from(*v)).hash(state),
+            Float64(v) => canonical_float_bits(*v).hash(state),
+            Binary(v) => v.hash(state),
+            BinaryChunks(chunks) => concat_chunks(chunks).hash(state),
+            String(v) | Json(v) => v.hash(state),
+            Decimal128(v) => v.hash(state),
+            Null => {}
+        }
+    }
+}
+
+/// Bits of `v` with every NaN and both zeros mapped to one representation each
+fn canonical_float_bits(v: f64) -> u64 {
+    if v.is_nan() {
+        f64::NAN.to_bits()
+    } else if v == 0.0 {
+        0
+    } else {
+        v.to_bits()
+    }
+}
+
+/// Bytes of a binary value shown by [`Value`]'s `Display` before it is cut off
+const DISPLAY_BINARY_PREFIX: usize = 16;
+
+/// The bare value for logs, without the variant wrapper of `Debug`
+///
+/// Nulls print as `NULL`. Timestamps, dates and times print their raw integer with
+/// a unit suffix, e.g. `1700000000000ms` or `19000d`, so the unit is not lost.
+/// Binary values print as hex, cut off after 16 bytes, followed by their length,
+/// e.g. `0x0a0b0c (3 bytes)`. Decimals print their unscaled integer, as the scale
+/// lives in the column schema.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Boolean(v) => write!(f, "{v}"),
+            Value::Int8(v) => write!(f, "{v}"),
+            Value::Int16(v) => write!(f, "{v}"),
+            Value::Int32(v) => write!(f, "{v}"),
+            Value::Int64(v) => write!(f, "{v}"),
+            Value::Uint8(v) => write!(f, "{v}"),
+            Value::Uint16(v) => write!(f, "{v}"),
+            Value::Uint32(v) => write!(f, "{v}"),
+            Value::Uint64(v) => write!(f, "{v}"),
+            Value::Float32(v) => write!(f, "{v}"),
+            Value::Float64(v) => write!(f, "{v}"),
+            Value::Binary(v) => write_binary(f, v.len(), v.iter()),
+            Value::BinaryChunks(chunks) => write_binary(
+                f,
+                chunks.iter().map(Bytes::len).sum(),
+                chunks.iter().flat_map(|chunk| chunk.iter()),
+            ),
+            Value::String(v) | Value::Json(v) => f.write_str(v),
+            Value::Date(v) => write!(f, "{v}d"),
+            Value::Datetime(v) | Value::TimestampMillisecond(v) => write!(f, "{v}ms"),
+            Value::TimestampSecond(v) => write!(f, "{v}s"),
+            Value::TimestampMicrosecond(v) => write!(f, "{v}us"),
+            Value::TimestampNanosecond(v) => write!(f, "{v}ns"),
+            Value::TimeSecond(v) => write!(f, "{v}s"),
+            Value::TimeMillisecond(v) => write!(f, "{v}ms"),
+            Value::TimeMicrosecond(v) => write!(f, "{v}us"),
+            Value::TimeNanosecond(v) => write!(f, "{v}ns"),
+            Value::Decimal128(v) => write!(f, "{v}"),
+            Value::Null => f.write_str("NULL"),
+        }
+    }
+}
+
+fn write_binary<'a>(
+    f: &mut std::fmt::Formatter<'_>,
+    len: usize,
+    bytes: impl Iterator<Item = &'a u8>,
+) -> std::fmt::Result {
+    f.write_str("0x")?;
+    for byte in bytes.take(DISPLAY_BINARY_PREFIX) {
+        write!(f, "{byte:02x}")?;
+    }
+    if len > DISPLAY_BINARY_PREFIX {
+        f.write_str("...")?;
+    }
+    write!(f, " ({len} bytes)")
+}
+
+/// The Unix epoch, which is chrono's default date
+#[cfg(feature = "chrono")]
+fn unix_epoch_date() -> chrono::NaiveDate {
+    chrono::NaiveDate::default()
+}
+
+/// Conversions from chrono types, each picking the variant and unit explicitly
+#[cfg(feature = "chrono")]
+impl Value {
+    /// Create a `TimestampSecond` value, truncating sub-second precision
+    pub fn from_datetime_secs(dt: chrono::DateTime<chrono::Utc>) -> Self {
+        Value::TimestampSecond(dt.timestamp())
+    }
+
+    /// Create a `TimestampMillisecond` value, truncating sub-millisecond precision
+    pub fn from_datetime_millis(dt: chrono::DateTime<chrono::Utc>) -> Self {
+        Value::TimestampMillisecond(dt.timestamp_millis())
+    }
+
+    /// Create a `TimestampMicrosecond` value, truncating sub-microsecond precision
+    pub fn from_datetime_micros(dt: chrono::DateTime<chrono::Utc>) -> Self {
+        Value::TimestampMicrosecond(dt.timestamp_micros())
+    }
+
+    /// Create a `TimestampNanosecond` value
+    ///
+    /// Nanoseconds since the epoch only fit into an `i64` for the years 1677 to 2262;
+    /// datetimes outside of that range are rejected.
+    pub fn from_datetime_nanos(dt: chrono::DateTime<chrono::Utc>) -> Result<Self> {
+        let nanos = dt
+            .timestamp_nanos_opt()
+            .context(error::ValueOutOfRangeSnafu {
+                value: dt.to_string(),
+                target: "TimestampNanosecond",
+            })?;
+        Ok(Value::TimestampNanosecond(nanos))
+    }
+
+    /// Create a `Date` value holding the days since the Unix epoch
+    pub fn from_naive_date(date: chrono::NaiveDate) -> Self {
+        // chrono dates span about 262,000 years either way, well within `i32` days
+        Value::Date((date - unix_epoch_date()).num_days() as i32)
+    }
+
+    /// Create a `TimeNanosecond` value holding the nanoseconds since midnight
+    pub fn from_naive_time(time: chrono::NaiveTime) -> Self {
+        use chrono::Timelike;
+
+        // A leap second is represented by a nanosecond value of one second or more
+        let nanos = i64::from(time.num_seconds_from_midnight()) * 1_000_000_000
+            + i64::from(time.nanosecond());
+        Value::TimeNanosecond(nanos)
+    }
+}
+
+/// How binary values are encoded in text sources such as CSV or JSON Lines files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinaryEncoding {
+    /// The bytes of the text itself
+    #[default]
+    Raw,
+    /// Two hex digits per byte in either case, with or without a `0x` prefix
+    Hex,
+    /// The standard base64 alphabet of RFC 4648 with padding
+    Base64,
+}
+
+impl BinaryEncoding {
+    /// Decode `text` into bytes
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            BinaryEncoding::Raw => Ok(text.as_bytes().to_vec()),
+            BinaryEncoding::Hex => decode_hex(text),
+            BinaryEncoding::Base64 => BASE64_STANDARD.decode(text).map_err(|e| {
+                error::InvalidBinaryEncodingSnafu {
+                    encoding: "base64",
+                    reason: e.to_string(),
+                }
+                .build()
+            }),
+        }
+    }
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    ensure!(
+        digits.len() % 2 == 0,
+        error::InvalidBinaryEncodingSnafu {
+            encoding: "hex",
+            reason: "odd number of digits",
+        }
+    );
+
+    let hex_digit = |digit: u8| char::from(digit).to_digit(16);
+    digits
+        .as_bytes()
+        .chunks_exact(2)
+        .map(|pair| match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(high), Some(low)) => Ok((high << 4 | low) as u8),
+            _ => error::InvalidBinaryEncodingSnafu {
+                encoding: "hex",
+                reason: format!("invalid digits `{}`", String::from_utf8_lossy(pair)),
+            }
+            .fail(),
+        })
+        .collect()
+}
+
+/// Serialize a protobuf enum of the API by its protobuf name
+#[cfg(feature = "serde")]
+macro_rules! proto_enum_serde {
+    ($module:ident, $ty:ty) => {
+        mod $module {
+            use super::*;
+
+            pub fn serialize<S: Serializer>(
+                value: &$ty,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(value.as_str_name())
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<$ty, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                <$ty>::from_str_name(&name).ok_or_else(|| {
+                    serde::de::Error::custom(format!("unknown {}: {name}", stringify!($ty)))
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+proto_enum_serde!(column_data_type_serde, ColumnDataType);
+#[cfg(feature = "serde")]
+proto_enum_serde!(semantic_type_serde, SemanticType);
+
+/// Binary values as base64 in human-readable formats and as raw bytes otherwise
+#[cfg(feature = "serde")]
+mod binary_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: &[u8],
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let text = String::deserialize(deserializer)?;
+            BASE64_STANDARD
+                .decode(text)
+                .map_err(serde::de::Error::custom)
+        } else {
+            deserialize_bytes(deserializer)
+        }
+    }
+
+    /// Accept raw bytes as well as a sequence of `u8`, as formats differ in what they produce
+    fn deserialize_bytes<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Vec<u8>, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> std::result::Result<Vec<u8>, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(
+                self,
+                v: Vec<u8>,
+            ) -> std::result::Result<Vec<u8>, E> {
+                Ok(v)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Vec<u8>, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// Binary chunks written as a single binary value
+#[cfg(feature = "serde")]
+mod binary_chunks_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        chunks: &[Bytes],
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        binary_serde::serialize(&concat_chunks(chunks), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Vec<Bytes>, D::Error> {
+        binary_serde::deserialize(deserializer).map(|bytes| vec![Bytes::from(bytes)])
+    }
+}
+
+/// Decimals as strings in human-readable formats, many of which cannot hold an `i128`
+#[cfg(feature = "serde")]
+mod decimal128_serde {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &i128,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(value)
+        } else {
+            serializer.serialize_i128(*value)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<i128, D::Error> {
+        if deserializer.is_human_readable() {
+            let text = String::deserialize(deserializer)?;
+            text.parse().map_err(serde::de::Error::custom)
+        } else {
+            i128::deserialize(deserializer)
+        }
+    }
+}
+
+impl From<Value> for api::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Boolean(v) => values::bool_value(v),
+            Value::Int8(v) => values::i8_value(v),
+            Value::Int16(v) => values::i16_value(v),
+            Value::Int32(v) => values::i32_value(v),
+            Value::Int64(v) => values::i64_value(v),
+            Value::Uint8(v) => values::u8_value(v),
+            Value::Uint16(v) => values::u16_value(v),
+            Value::Uint32(v) => values::u32_value(v),
+            Value::Uint64(v) => values::u64_value(v),
+            Value::Float32(v) => values::f32_value(v),
+            Value::Float64(v) => values::f64_value(v),
+            Value::Binary(v) => values::binary_value(v),
+            Value::BinaryChunks(chunks) => values::binary_value(concat_chunks(&chunks)),
+            Value::String(v) | Value::Json(v) => values::string_value(v),
+            Value::Date(v) => values::date_value(v),
+            Value::Datetime(v) => values::datetime_value(v),
+            Value::TimestampSecond(v) => values::timestamp_second_value(v),
+            Value::TimestampMillisecond(v) => values::timestamp_millisecond_value(v),
+            Value::TimestampMicrosecond(v) => values::timestamp_microsecond_value(v),
+            Value::TimestampNanosecond(v) => values::timestamp_nanosecond_value(v),
+            Value::TimeSecond(v) => values::time_second_value(i64::from(v)),
+            Value::TimeMillisecond(v) => values::time_millisecond_value(i64::from(v)),
+            Value::TimeMicrosecond(v) => values::time_microsecond_value(v),
+            Value::TimeNanosecond(v) => values::time_nanosecond_value(v),
+            Value::Decimal128(v) => values::decimal128_value(v),
+            Value::Null => values::none_value(),
+        }
+    }
+}
+
+/// Odds of a random value being null in a nullable column, see [`random_row`]
+const RANDOM_NULL_PROBABILITY: f64 = 0.1;
+
+/// Maximum length of random strings and binaries, see [`random_row`]
+const MAX_RANDOM_LEN: usize = 32;
+
+/// A random row that passes [`TableSchema::validate_row`], for property tests of
+/// ingestion code
+///
+/// Every column gets a value of its exact type. The timestamp column is never
+/// null and lies within the default [`TimestampRange`]; other columns are null one
+/// time in ten, as only the time index is `NOT NULL`. Decimals stay within the
+/// precision of their column, floats are finite, times lie within a day, and
+/// strings are alphanumeric. Columns of types without a [`Value`] variant, such as
+/// intervals, are always null.
+pub fn random_row(schema: &TableSchema, rng: &mut impl rand::Rng) -> Row {
+    use rand::distr::Alphanumeric;
+
+    let (min_seconds, max_seconds) = TimestampRange::default().bounds_in(TimeUnit::Second);
+    let values = schema.columns.iter().map(|column| {
+        let data_type = column.data_type;
+        if column.semantic_type != SemanticType::Timestamp
+            && rng.random_bool(RANDOM_NULL_PROBABILITY)
+        {
+            return Value::Null;
+        }
+        let timestamp_unit = match data_type {
+            ColumnDataType::TimestampSecond => Some(TimeUnit::Second),
+            ColumnDataType::TimestampMillisecond => Some(TimeUnit::Millisecond),
+            ColumnDataType::TimestampMicrosecond => Some(TimeUnit::Microsecond),
+            ColumnDataType::TimestampNanosecond => Some(TimeUnit::Nanosecond),
+            _ => None,
+        };
+        if let Some(unit) = timestamp_unit {
+            let (min, max) = TimestampRange::default().bounds_in(unit);
+            return Value::timestamp_in(unit, rng.random_range(min..=max));
+        }
+        match data_type {
+            ColumnDataType::Boolean => Value::Boolean(rng.random()),
+            ColumnDataType::Int8 => Value::Int8(rng.random()),
+            ColumnDataType::Int16 => Value::Int16(rng.random()),
+            ColumnDataType::Int32 => Value::Int32(rng.random()),
+            ColumnDataType::Int64 => Value::Int64(rng.random()),
+            ColumnDataType::Uint8 => Value::Uint8(rng.random()),
+            ColumnDataType::Uint16 => Value::Uint16(rng.random()),
+            ColumnDataType::Uint32 => Value::Uint32(rng.random()),
+            ColumnDataType::Uint64 => Value::Uint64(rng.random()),
+            ColumnDataType::Float32 => Value::Float32(rng.random_range(-1e6..1e6)),
+            ColumnDataType::Float64 => Value::Float64(rng.random_range(-1e9..1e9)),
+            ColumnDataType::Binary => {
+                let len = rng.random_range(0..=MAX_RANDOM_LEN);
+                Value::Binary((0..len).map(|_| rng.random()).collect())
+            }
+            ColumnDataType::String => {
+                let len = rng.random_range(0..=MAX_RANDOM_LEN);
+                Value::String(
+                    (0..len)
+                        .map(|_| char::from(rng.sample(Alphanumeric)))
+                        .collect(),
+                )
+            }
+            ColumnDataType::Json => {
+                Value::Json(serde_json::json!({ "value": rng.random::<i32>() }).to_string())
+            }
+            ColumnDataType::Date => {
+                Value::Date(rng.random_range(min_seconds / 86_400..=max_seconds / 86_400) as i32)
+            }
+            ColumnDataType::Datetime => {
+                Value::Datetime(rng.random_range(min_seconds * 1_000..=max_seconds * 1_000))
+            }
+            ColumnDataType::TimeSecond => Value::TimeSecond(rng.random_range(0..86_400)),
+            ColumnDataType::TimeMillisecond => {
+                Value::TimeMillisecond(rng.random_range(0..86_400_000))
+            }
+            ColumnDataType::TimeMicrosecond => {
+                Value::TimeMicrosecond(rng.random_range(0..86_400_000_000))
+            }
+            ColumnDataType::TimeNanosecond => {
+                Value::TimeNanosecond(rng.random_range(0..86_400_000_000_000))
+            }
+            ColumnDataType::Decimal128 => {
+                let max = match column.data_type_extension {
+                    Some(DataTypeExtension::Decimal128 { precision, .. }) => {
+                        10_i128.pow(u32::from(precision.clamp(1, 38))) - 1
+                    }
+                    None => MAX_DECIMAL128,
+                };
+                Value::Decimal128(rng.random_range(-max..=max))
+            }
+            _ => Value::Null,
+        }
+    });
+    Row::from_values(values.collect())
+}
+
+/// Generation of arbitrary values and rows, to drive fuzzers such as `cargo fuzz`
+///
+/// Every variant of [`Value`] is generated. Strings, JSON documents and binaries
+/// are bounded to [`MAX_ARBITRARY_LEN`](arbitrary_impls::MAX_ARBITRARY_LEN) bytes
+/// or chars, decimals stay within the 38 digits of `Decimal128`, JSON values are
+/// well-formed documents, and rows hold at most
+/// [`MAX_ARBITRARY_ROW_LEN`](arbitrary_impls::MAX_ARBITRARY_ROW_LEN) values.
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary_impls {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+    use bytes::Bytes;
+
+    use super::{Row, Value, MAX_DECIMAL128};
+
+    /// Maximum length of generated strings and binaries
+    pub const MAX_ARBITRARY_LEN: usize = 64;
+    /// Maximum number of values of a generated row
+    pub const MAX_ARBITRARY_ROW_LEN: usize = 16;
+    /// Maximum number of chunks of a generated `Value::BinaryChunks`
+    const MAX_ARBITRARY_CHUNKS: usize = 4;
+
+    fn bounded_string(u: &mut Unstructured<'_>) -> Result<String> {
+        let len = u.int_in_range(0..=MAX_ARBITRARY_LEN)?;
+        (0..len).map(|_| u.arbitrary::<char>()).collect()
+    }
+
+    fn bounded_bytes(u: &mut Unstructured<'_>) -> Result<Vec<u8>> {
+        let len = u.int_in_range(0..=MAX_ARBITRARY_LEN)?;
+        Ok(u.bytes(len)?.to_vec())
+    }
+
+    impl<'a> Arbitrary<'a> for Value {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(match u.int_in_range(0..=26u8)? {
+                0 => Value::Boolean(u.arbitrary()?),
+                1 => Value::Int8(u.arbitrary()?),
+                2 => Value::Int16(u.arbitrary()?),
+                3 => Value::Int32(u.arbitrary()?),
+                4 => Value::Int64(u.arbitrary()?),
+                5 => Value::Uint8(u.arbitrary()?),
+                6 => Value::Uint16(u.arbitrary()?),
+                7 => Value::Uint32(u.arbitrary()?),
+                8 => Value::Uint64(u.arbitrary()?),
+                9 => Value::Float32(u.arbitrary()?),
+                10 => Value::Float64(u.arbitrary()?),
+                11 => Value::Binary(bounded_bytes(u)?),
+                12 => {
+                    let count = u.int_in_range(0..=MAX_ARBITRARY_CHUNKS)?;
+                    let chunks = (0..count)
+                        .map(|_| bounded_bytes(u).map(Bytes::from))
+                        .collect::<Result<_>>()?;
+                    Value::BinaryChunks(chunks)
+                }
+                13 => Value::String(bounded_string(u)?),
+                14 => Value::Date(u.arbitrary()?),
+                15 => Value::Datetime(u.arbitrary()?),
+                16 => Value::TimestampSecond(u.arbitrary()?),
+                17 => Value::TimestampMillisecond(u.arbitrary()?),
+                18 => Value::TimestampMicrosecond(u.arbitrary()?),
+                19 => Value::TimestampNanosecond(u.arbitrary()?),
+                20 => Value::TimeSecond(u.arbitrary()?),
+                21 => Value::TimeMillisecond(u.arbitrary()?),
+                22 => Value::TimeMicrosecond(u.arbitrary()?),
+                23 => Value::TimeNanosecond(u.arbitrary()?),
+                24 => Value::Decimal128(u.int_in_range(-MAX_DECIMAL128..=MAX_DECIMAL128)?),
+                25 => {
+                    let json = match u.int_in_range(0..=3u8)? {
+                        0 => serde_json::Value::Null,
+                        1 => serde_json::Value::from(u.arbitrary::<bool>()?),
+                        2 => serde_json::Value::from(u.arbitrary::<i64>()?),
+                        _ => serde_json::Value::from(bounded_string(u)?),
+                    };
+                    Value::Json(json.to_string())
+                }
+                _ => Value::Null,
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Row {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let len = u.int_in_range(0..=MAX_ARBITRARY_ROW_LEN)?;
+            let values = (0..len)
+                .map(|_| Value::arbitrary(u))
+                .collect::<Result<_>>()?;
+            Ok(Row::from_values(values))
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_row_is_well_formed() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        use super::arbitrary_impls::{MAX_ARBITRARY_LEN, MAX_ARBITRARY_ROW_LEN};
+
+        let data: Vec<u8> = (0..4096_u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&data);
+        let mut values = 0;
+        while !u.is_empty() {
+            let row = Row::arbitrary(&mut u).unwrap();
+            assert!(row.len() <= MAX_ARBITRARY_ROW_LEN);
+            for value in &row.values {
+                values += 1;
+                match value {
+                    Value::String(s) => assert!(s.chars().count() <= MAX_ARBITRARY_LEN),
+                    Value::Binary(b) => assert!(b.len() <= MAX_ARBITRARY_LEN),
+                    Value::BinaryChunks(chunks) => {
+                        assert!(chunks.iter().all(|chunk| chunk.len() <= MAX_ARBITRARY_LEN))
+                    }
+                    Value::Decimal128(v) => assert!(v.unsigned_abs() <= MAX_DECIMAL128 as u128),
+                    Value::Json(json) => {
+                        assert!(serde_json::from_str::<serde_json::Value>(json).is_ok())
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert!(values > 0);
+    }
+
+    #[test]
+    fn test_value_from_primitives() {
+        assert!(matches!(Value::from(true), Value::Boolean(true)));
+        assert!(matches!(Value::from(-8i8), Value::Int8(-8)));
+        assert!(matches!(Value::from(-16i16), Value::Int16(-16)));
+        assert!(matches!(Value::from(-32i32), Value::Int32(-32)));
+        assert!(matches!(Value::from(-64i64), Value::Int64(-64)));
+        assert!(matches!(Value::from(8u8), Value::Uint8(8)));
+        assert!(matches!(Value::from(16u16), Value::Uint16(16)));
+        assert!(matches!(Value::from(32u32), Value::Uint32(32)));
+        assert!(matches!(Value::from(64u64), Value::Uint64(64)));
+        assert!(matches!(Value::from(1.5f32), Value::Float32(v) if v == 1.5));
+        assert!(matches!(Value::from(2.5f64), Value::Float64(v) if v == 2.5));
+        assert!(matches!(Value::from("host"), Value::String(v) if v == "host"));
+        assert!(matches!(Value::from("host".to_string()), Value::String(v) if v == "host"));
+        assert!(matches!(Value::from(vec![1u8, 2]), Value::Binary(v) if v == [1, 2]));
+        assert!(matches!(Value::from(&[3u8][..]), Value::Binary(v) if v == [3]));
+
+        let row = Row::from_values(vec![42i64.into(), "web".into()]);
+        assert_eq!(row.get_i64(0), Some(42));
+        assert_eq!(row.get_string(1), Some("web".to_string()));
+    }
+
+    #[test]
+    fn test_binary_chunks_read_back_concatenated() {
+        let blob = Bytes::from(vec![7u8; 1024]);
+        let chunks = vec![
+            blob.slice(..512),
+            blob.slice(512..),
+            Bytes::from_static(b"end"),
+        ];
+        let mut row = Row::from_values(vec![Value::BinaryChunks(chunks), Value::Null]);
+
+        let mut expected = vec![7u8; 1024];
+        expected.extend_from_slice(b"end");
+        assert_eq!(row.get_binary(0), Some(expected.clone()));
+        assert_eq!(row.take_binary(0), Some(expected));
+        assert_eq!(row.get_binary(1), None);
+    }
+
+    #[test]
+    fn test_value_from_option() {
+        assert!(matches!(Value::from(Some(7i32)), Value::Int32(7)));
+        assert!(matches!(Value::from(None::<i32>), Value::Null));
+        assert!(matches!(Value::from(Some("a")), Value::String(v) if v == "a"));
+        assert!(matches!(Value::from(None::<String>), Value::Null));
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicate_and_empty_names() {
+        let schema = || {
+            TableSchema::builder()
+                .name("hosts")
+                .build()
+                .unwrap()
+                .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+                .add_tag("host_id", ColumnDataType::String)
+        };
+        assert!(schema().validate().is_ok());
+
+        let duplicate = schema().add_field("host_id", ColumnDataType::Int64);
+        assert!(matches!(
+            duplicate.validate(),
+            Err(crate::Error::DuplicateColumn { column, .. }) if column == "host_id"
+        ));
+
+        let empty = schema().add_field("", ColumnDataType::Int64);
+        assert!(matches!(
+            empty.validate(),
+            Err(crate::Error::EmptyColumnName { index: 2, .. })
+        ));
+
+        let built = TableSchema::builder()
+            .name("hosts")
+            .columns(empty.columns().to_vec())
+            .build();
+        assert!(matches!(
+            built,
+            Err(TableSchemaBuilderError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_transpose_rows() {
+        let rows: Vec<Row> = (0..3_i64)
+            .map(|i| {
+                Row::from_values(vec![
+                    Value::TimestampMillisecond(1_000 + i),
+                    Value::String(format!("host-{i}")),
+                    Value::Float64(i as f64 / 2.0),
+                    if i == 1 { Value::Null } else { Value::Int64(i) },
+                ])
+            })
+            .collect();
+
+        let columns = transpose_rows(&rows, 4).unwrap();
+        assert_eq!(columns.len(), 4);
+        assert!(columns.iter().all(|column| column.len() == 3));
+        // Read each column vector back through a row to compare its values
+        let column = |index: usize| Row::from_values(columns[index].clone());
+        let timestamps: Vec<_> = (0..3).map(|i| column(0).get_timestamp(i)).collect();
+        assert_eq!(timestamps, [Some(1_000), Some(1_001), Some(1_002)]);
+        let hosts: Vec<_> = (0..3).map(|i| column(1).get_string(i)).collect();
+        assert_eq!(
+            hosts,
+            [
+                Some("host-0".to_string()),
+                Some("host-1".to_string()),
+                Some("host-2".to_string())
+            ]
+        );
+        let values: Vec<_> = (0..3).map(|i| column(2).get_f64(i)).collect();
+        assert_eq!(values, [Some(0.0), Some(0.5), Some(1.0)]);
+        let counts: Vec<_> = (0..3).map(|i| column(3).get_i64(i)).collect();
+        assert_eq!(counts, [Some(0), None, Some(2)]);
+
+        let mut ragged = rows.clone();
+        ragged.push(Row::from_values(vec![Value::Null]));
+        assert!(matches!(
+            transpose_rows(&ragged, 4),
+            Err(crate::Error::InvalidColumnCount {
+                expected: 4,
+                actual: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_column() {
+        let schema = TableSchema::builder()
+            .name("events")
+            .build()
+            .unwrap()
+            .add_tag("host", ColumnDataType::String);
+        assert!(schema.timestamp_column().is_none());
+        assert!(matches!(
+            schema.validate_time_index(),
+            Err(crate::Error::InvalidTimeIndex { columns, .. }) if columns.is_empty()
+        ));
+
+        assert_eq!(schema.timestamp_index(), None);
+
+        let schema = schema.add_timestamp("ts", ColumnDataType::TimestampMillisecond);
+        assert_eq!(schema.timestamp_column().unwrap().name, "ts");
+        assert_eq!(schema.timestamp_index(), Some(1));
+        assert!(schema.validate_time_index().is_ok());
+
+        let schema = schema.add_timestamp("ts2", ColumnDataType::TimestampMillisecond);
+        assert!(schema.timestamp_column().is_none());
+        assert_eq!(schema.timestamp_index(), None);
+        assert!(matches!(
+            schema.validate_time_index(),
+            Err(crate::Error::InvalidTimeIndex { columns, .. }) if columns == ["ts", "ts2"]
+        ));
+    }
+
+    #[test]
+    fn test_primary_value_column() {
+        let schema = TableSchema::builder()
+            .name("http_requests_total")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("exemplar", ColumnDataType::String);
+        assert!(schema.primary_value_column().is_none());
+
+        let schema = schema.add_primary_field("value", ColumnDataType::Float64);
+        let column = schema.primary_value_column().unwrap();
+        assert_eq!(column.name, "value");
+        assert!(column.is_primary_value);
+        assert_eq!(column.semantic_type, SemanticType::Field);
+        assert_eq!(schema.primary_value_index(), Some(2));
+        assert!(!schema.columns()[1].is_primary_value);
+        assert!(schema.validate().is_ok());
+
+        let schema = schema.add_primary_field("count", ColumnDataType::Int64);
+        assert!(matches!(
+            schema.validate(),
+            Err(crate::Error::MultiplePrimaryValues { columns, .. }) if columns == ["value", "count"]
+        ));
+    }
+
+    #[test]
+    fn test_extend_from() {
+        let base = TableSchema::builder()
+            .name("base")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String);
+
+        let access_log = TableSchema::builder()
+            .name("access_log")
+            .build()
+            .unwrap()
+            .extend_from(&base)
+            .unwrap()
+            .add_field("path", ColumnDataType::String)
+            .add_field("status", ColumnDataType::Uint16);
+        let names: Vec<_> = access_log.columns().iter().map(|c| &c.name).collect();
+        assert_eq!(names, ["ts", "host", "path", "status"]);
+        assert_eq!(access_log.columns()[1].semantic_type, SemanticType::Tag);
+        assert_eq!(access_log.name(), "access_log");
+
+        let collision = TableSchema::builder()
+            .name("error_log")
+            .build()
+            .unwrap()
+            .add_field("host", ColumnDataType::String)
+            .extend_from(&base);
+        assert!(matches!(
+            collision,
+            Err(crate::Error::DuplicateColumn { column, .. }) if column == "host"
+        ));
+    }
+
+    #[test]
+    fn test_normalize_case() {
+        let columns = || {
+            vec![
+                Column {
+                    name: "ResponseTimeMs".to_string(),
+                    data_type: ColumnDataType::Int64,
+                    semantic_type: SemanticType::Field,
+                    data_type_extension: None,
+                    is_primary_value: false,
+                    options: ColumnOptions::default(),
+                },
+                Column {
+                    name: "responsetimems".to_string(),
+                    data_type: ColumnDataType::Int64,
+                    semantic_type: SemanticType::Field,
+                    data_type_extension: None,
+                    is_primary_value: false,
+                    options: ColumnOptions::default(),
+                },
+            ]
+        };
+
+        let preserved = TableSchema::builder()
+            .name("http")
+            .columns(columns())
+            .build()
+            .unwrap();
+        assert_eq!(preserved.case_mode(), CaseMode::Preserve);
+        assert_eq!(preserved.columns()[0].name, "ResponseTimeMs");
+
+        let collision = TableSchema::builder()
+            .name("http")
+            .columns(columns())
+            .normalize_case(CaseMode::Lower)
+            .build();
+        assert!(matches!(
+            collision,
+            Err(TableSchemaBuilderError::ValidationError(_))
+        ));
+
+        let upper = TableSchema::builder()
+            .name("http")
+            .normalize_case(CaseMode::Upper)
+            .build()
+            .unwrap()
+            .add_tag("Host", ColumnDataType::String);
+        assert_eq!(upper.columns()[0].name, "HOST");
+    }
+
     #[test]
     fn test_get_bool_correct_types() {
         let row = Row::from_values(vec![
@@ -712,4 +3889,1376 @@ mod tests {
             let _ = row.get_bool_unchecked(0);
         }
     }
+
+    #[test]
+    fn test_try_get_reports_mismatch_in_every_profile() {
+        let row = Row::from_values(vec![
+            Value::Int32(42),
+            Value::String("test".to_string()),
+            Value::Null,
+        ]);
+
+        assert_eq!(row.try_get_i32(0), Ok(Some(42)));
+        assert_eq!(row.try_get_i32(2), Ok(None));
+        assert_eq!(row.try_get_i32(3), Ok(None));
+
+        let error = row.try_get_i32(1).unwrap_err();
+        assert_eq!(error.index(), 1);
+        assert_eq!(error.expected(), "i32");
+        assert_eq!(error.actual(), "String");
+        assert_eq!(
+            error.to_string(),
+            "Expected `i32` value at index 1, got String"
+        );
+
+        let error = row.try_get_timestamp(0).unwrap_err();
+        assert_eq!((error.expected(), error.actual()), ("timestamp", "Int32"));
+    }
+
+    #[test]
+    fn test_generic_get() {
+        let row = Row::from_values(vec![
+            Value::Int64(42),
+            Value::String("test".to_string()),
+            Value::Null,
+            Value::Float64(1.5),
+        ]);
+
+        assert_eq!(row.get::<i64>(0), Some(42));
+        assert_eq!(row.get::<String>(1), Some("test".to_string()));
+        assert_eq!(row.get::<Vec<u8>>(1), Some(b"test".to_vec()));
+        assert_eq!(row.get::<i64>(2), None);
+        assert_eq!(row.get::<f64>(3), Some(1.5));
+        assert_eq!(row.get::<i64>(4), None);
+
+        fn sum<T: FromValue + std::iter::Sum>(row: &Row, indices: &[usize]) -> T {
+            indices.iter().filter_map(|&i| row.get::<T>(i)).sum()
+        }
+        assert_eq!(sum::<i64>(&row, &[0, 2]), 42);
+
+        let error = row.try_get::<i64>(1).unwrap_err();
+        assert_eq!((error.index(), error.expected()), (1, "i64"));
+        let error = row.try_get::<String>(0).unwrap_err();
+        assert_eq!((error.expected(), error.actual()), ("string", "Int64"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected `i32` value at index 0, got Int64(42)")]
+    fn test_generic_get_type_mismatch_debug_assert() {
+        let row = Row::from_values(vec![Value::Int64(42)]);
+        let _ = row.get::<i32>(0);
+    }
+
+    #[test]
+    fn test_get_json_correct_types() {
+        let row = Row::from_values(vec![
+            Value::Json(r#"{"a":1}"#.to_string()),
+            Value::String(r#"[1,2]"#.to_string()),
+            Value::Null,
+        ]);
+
+        // Test correct type, and JSON returned as a plain string
+        assert_eq!(row.get_json(0), Some(r#"{"a":1}"#.to_string()));
+        assert_eq!(row.get_json(1), Some(r#"[1,2]"#.to_string()));
+
+        // Test null value and out of bounds index
+        assert_eq!(row.get_json(2), None);
+        assert_eq!(row.get_json(3), None);
+    }
+
+    #[test]
+    fn test_get_json_unchecked_correct_types() {
+        let row = Row::from_values(vec![Value::Json("{}".to_string()), Value::Null]);
+
+        unsafe {
+            assert_eq!(row.get_json_unchecked(0), Some("{}".to_string()));
+            assert_eq!(row.get_json_unchecked(1), None);
+        }
+    }
+
+    #[test]
+    fn test_take_json() {
+        let mut row = Row::from_values(vec![Value::Json("{}".to_string())]);
+
+        assert_eq!(row.take_json(0), Some("{}".to_string()));
+        // The value is replaced by null once taken
+        assert_eq!(row.take_json(0), None);
+        assert_eq!(row.take_json(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected `json` value at index 0, got Int32(42)")]
+    fn test_get_json_type_mismatch_debug_assert() {
+        let row = Row::from_values(vec![Value::Int32(42)]);
+
+        // This should trigger debug_assert! in debug mode
+        let _ = row.get_json(0);
+    }
+
+    #[test]
+    fn test_timestamp_range_flags_seconds_in_millisecond_column() {
+        let range = TimestampRange::default();
+
+        // 2023-11-14T22:13:20Z written as seconds into a millisecond column
+        let seconds = Value::TimestampMillisecond(1_700_000_000);
+        let err = range
+            .check("ts", TimeUnit::Millisecond, &seconds)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::ImplausibleTimestamp {
+                value: 1_700_000_000,
+                ..
+            }
+        ));
+
+        // The same instant in the right unit passes
+        let millis = Value::TimestampMillisecond(1_700_000_000_000);
+        assert!(range.check("ts", TimeUnit::Millisecond, &millis).is_ok());
+
+        // Milliseconds written into a second column are flagged as well
+        let millis_as_seconds = Value::TimestampSecond(1_700_000_000_000);
+        assert!(range
+            .check("ts", TimeUnit::Second, &millis_as_seconds)
+            .is_err());
+
+        // Nulls are not checked
+        assert!(range.check("ts", TimeUnit::Second, &Value::Null).is_ok());
+    }
+
+    #[test]
+    fn test_timestamp_range_custom_bounds() {
+        let range = TimestampRange::new(0, 100);
+        assert!(range.contains(100_000, TimeUnit::Millisecond));
+        assert!(!range.contains(100_001, TimeUnit::Millisecond));
+        assert_eq!(range.bounds_in(TimeUnit::Nanosecond), (0, 100_000_000_000));
+    }
+
+    #[test]
+    fn test_rescale_timestamp() {
+        assert_eq!(
+            Value::timestamp_in(TimeUnit::Microsecond, 7),
+            Value::TimestampMicrosecond(7)
+        );
+
+        let nanos = Value::TimestampNanosecond(1_700_000_000_123_456_789);
+        assert_eq!(
+            nanos.rescale_timestamp(TimeUnit::Millisecond).unwrap(),
+            (Value::TimestampMillisecond(1_700_000_000_123), true)
+        );
+        let exact = Value::TimestampNanosecond(1_700_000_000_123_000_000);
+        assert_eq!(
+            exact.rescale_timestamp(TimeUnit::Millisecond).unwrap(),
+            (Value::TimestampMillisecond(1_700_000_000_123), false)
+        );
+        // Rounded towards the past, not towards zero
+        assert_eq!(
+            Value::TimestampNanosecond(-1)
+                .rescale_timestamp(TimeUnit::Millisecond)
+                .unwrap(),
+            (Value::TimestampMillisecond(-1), true)
+        );
+
+        let seconds = Value::TimestampSecond(1_700_000_000);
+        assert_eq!(
+            seconds.rescale_timestamp(TimeUnit::Microsecond).unwrap(),
+            (Value::TimestampMicrosecond(1_700_000_000_000_000), false)
+        );
+        assert_eq!(
+            seconds.rescale_timestamp(TimeUnit::Second).unwrap(),
+            (seconds.clone(), false)
+        );
+        assert!(matches!(
+            Value::TimestampSecond(i64::MAX / 1_000).rescale_timestamp(TimeUnit::Nanosecond),
+            Err(crate::Error::ValueOutOfRange { .. })
+        ));
+
+        assert_eq!(
+            Value::Null.rescale_timestamp(TimeUnit::Second).unwrap(),
+            (Value::Null, false)
+        );
+        assert!(matches!(
+            Value::Int64(1).rescale_timestamp(TimeUnit::Second),
+            Err(crate::Error::UnsupportedDataType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_timestamp_as() {
+        let row = Row::from_values(vec![
+            Value::TimestampNanosecond(1_700_000_000_123_456_789),
+            Value::TimestampSecond(1_700_000_000),
+            Value::TimestampSecond(i64::MAX),
+            Value::Null,
+        ]);
+        assert_eq!(
+            row.get_timestamp_as(0, TimeUnit::Millisecond),
+            Some(1_700_000_000_123)
+        );
+        assert_eq!(
+            row.get_timestamp_as(1, TimeUnit::Millisecond),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(row.get_timestamp_as(2, TimeUnit::Millisecond), None);
+        assert_eq!(row.get_timestamp_as(3, TimeUnit::Millisecond), None);
+        assert_eq!(row.get_timestamp_as(4, TimeUnit::Millisecond), None);
+    }
+
+    #[test]
+    fn test_value_bounds() {
+        let (min, max) = value_bounds(ColumnDataType::Int32).unwrap();
+        assert!(matches!(min, Value::Int32(i32::MIN)));
+        assert!(matches!(max, Value::Int32(i32::MAX)));
+
+        let (min, max) = value_bounds(ColumnDataType::Uint64).unwrap();
+        assert!(matches!(min, Value::Uint64(0)));
+        assert!(matches!(max, Value::Uint64(u64::MAX)));
+
+        let (min, max) = value_bounds(ColumnDataType::TimestampNanosecond).unwrap();
+        assert!(matches!(min, Value::TimestampNanosecond(i64::MIN)));
+        assert!(matches!(max, Value::TimestampNanosecond(i64::MAX)));
+
+        let (min, max) = value_bounds(ColumnDataType::Decimal128).unwrap();
+        assert!(
+            matches!(min, Value::Decimal128(v) if v == -99_999_999_999_999_999_999_999_999_999_999_999_999)
+        );
+        assert!(
+            matches!(max, Value::Decimal128(v) if v == 99_999_999_999_999_999_999_999_999_999_999_999_999)
+        );
+
+        let (min, max) = value_bounds(ColumnDataType::TimeSecond).unwrap();
+        assert!(matches!(min, Value::TimeSecond(0)));
+        assert!(matches!(max, Value::TimeSecond(86_399)));
+
+        assert!(value_bounds(ColumnDataType::String).is_none());
+        assert!(value_bounds(ColumnDataType::Json).is_none());
+    }
+
+    #[test]
+    fn test_sparse_row_round_trip() {
+        let schema = TableSchema::builder()
+            .name("wide")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String)
+            .add_field("cpu", ColumnDataType::Float64)
+            .add_field("mem", ColumnDataType::Float64)
+            .add_field("disk", ColumnDataType::Int64);
+
+        let sparse = SparseRow::new()
+            .set(0, Value::TimestampMillisecond(1_700_000_000_000))
+            .set(3, Value::Float64(0.5))
+            .set(3, Value::Float64(0.75));
+        assert_eq!(sparse.len(), 2);
+
+        let dense = sparse.into_dense(&schema).unwrap();
+        assert_eq!(dense.len(), 5);
+        assert_eq!(dense.get_timestamp(0), Some(1_700_000_000_000));
+        assert_eq!(dense.get_string(1), None);
+        assert_eq!(dense.get_f64(2), None);
+        assert_eq!(dense.get_f64(3), Some(0.75));
+        assert_eq!(dense.get_i64(4), None);
+
+        let sparse = SparseRow::from_dense(dense);
+        assert_eq!(sparse.len(), 2);
+        assert!(matches!(
+            sparse.get(0),
+            Some(Value::TimestampMillisecond(1_700_000_000_000))
+        ));
+        assert!(matches!(sparse.get(3), Some(Value::Float64(v)) if *v == 0.75));
+        assert!(sparse.get(1).is_none());
+
+        let out_of_range = SparseRow::new().set(5, Value::Int64(1));
+        assert!(matches!(
+            out_of_range.into_dense(&schema),
+            Err(crate::Error::InvalidColumnIndex {
+                index: 5,
+                total: 5,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_column_data_type_from_name_aliases() {
+        let cases = [
+            ("bool", ColumnDataType::Boolean),
+            ("BOOLEAN", ColumnDataType::Boolean),
+            ("tinyint", ColumnDataType::Int8),
+            ("SmallInt", ColumnDataType::Int16),
+            ("int", ColumnDataType::Int32),
+            ("INTEGER", ColumnDataType::Int32),
+            ("BIGINT", ColumnDataType::Int64),
+            ("float", ColumnDataType::Float32),
+            ("REAL", ColumnDataType::Float32),
+            ("double", ColumnDataType::Float64),
+            ("double precision", ColumnDataType::Float64),
+            ("VARCHAR", ColumnDataType::String),
+            ("varchar(255)", ColumnDataType::String),
+            ("char", ColumnDataType::String),
+            ("TEXT", ColumnDataType::String),
+            ("varbinary", ColumnDataType::Binary),
+            ("BLOB", ColumnDataType::Binary),
+            ("bytea", ColumnDataType::Binary),
+            ("DECIMAL(10, 2)", ColumnDataType::Decimal128),
+            ("numeric", ColumnDataType::Decimal128),
+            ("timestamp", ColumnDataType::TimestampMillisecond),
+            ("TIMESTAMP(0)", ColumnDataType::TimestampSecond),
+            ("timestamp(3)", ColumnDataType::TimestampMillisecond),
+            ("TIMESTAMP(6)", ColumnDataType::TimestampMicrosecond),
+            ("TIMESTAMP(9)", ColumnDataType::TimestampNanosecond),
+            // GreptimeDB's own names
+            ("int64", ColumnDataType::Int64),
+            ("UINT32", ColumnDataType::Uint32),
+            ("timestamp_nanosecond", ColumnDataType::TimestampNanosecond),
+            ("JSON", ColumnDataType::Json),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                column_data_type_from_name(name),
+                Some(expected),
+                "resolving {name}"
+            );
+        }
+
+        assert_eq!(column_data_type_from_name("TIMESTAMP(4)"), None);
+        assert_eq!(column_data_type_from_name("VARCHAR(255"), None);
+        assert_eq!(column_data_type_from_name("GEOMETRY"), None);
+    }
+
+    #[test]
+    fn test_row_set_value() {
+        let mut row = Row::new();
+        row.set_value(2, Value::Int64(7));
+        assert_eq!(row.len(), 3);
+        assert!(matches!(row.values[0], Value::Null));
+        assert_eq!(row.get_i64(2), Some(7));
+
+        row.set_string(0, "first");
+        let capacity = match &row.values[0] {
+            Value::String(s) => s.capacity(),
+            other => panic!("expected a string, got {other:?}"),
+        };
+        row.set_string(0, "one");
+        assert_eq!(row.get_string(0), Some("one".to_string()));
+        assert!(matches!(&row.values[0], Value::String(s) if s.capacity() == capacity));
+
+        row.set_value(0, Value::Boolean(true));
+        assert_eq!(row.get_bool(0), Some(true));
+    }
+
+    #[test]
+    fn test_fill_defaults() {
+        let schema = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("region", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64)
+            .add_field("note", ColumnDataType::String)
+            .with_column_default("region", Value::String("unknown".to_string()))
+            .unwrap()
+            .with_column_default("temperature", Value::Float64(0.0))
+            .unwrap();
+        assert!(
+            matches!(schema.column_default("temperature"), Some(Value::Float64(v)) if *v == 0.0)
+        );
+        assert!(schema.column_default("note").is_none());
+        assert!(schema
+            .clone()
+            .with_column_default("missing", Value::Null)
+            .is_err());
+
+        let mut row = Row::from_values(vec![
+            Value::TimestampMillisecond(1_000),
+            Value::Null,
+            Value::Float64(21.5),
+            Value::Null,
+        ]);
+        row.fill_defaults(&schema);
+        assert_eq!(row.get_string(1), Some("unknown".to_string()));
+        // Non-null values and columns without a default are left alone
+        assert_eq!(row.get_f64(2), Some(21.5));
+        assert!(matches!(row.values[3], Value::Null));
+        assert!(matches!(row.values[0], Value::TimestampMillisecond(1_000)));
+    }
+
+    #[test]
+    fn test_to_create_table_sql() {
+        let table = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64)
+            .add_decimal128_field("price", 10, 2);
+
+        let sql = table
+            .to_create_table_sql(&[("append_mode", "true"), ("skip_wal", "true")])
+            .unwrap();
+        assert_eq!(
+            sql,
+            "CREATE TABLE IF NOT EXISTS `sensor` (\n\
+             \x20 `ts` TIMESTAMP(3) NOT NULL,\n\
+             \x20 `device` STRING NULL,\n\
+             \x20 `temperature` DOUBLE NULL,\n\
+             \x20 `price` DECIMAL(10, 2) NULL,\n\
+             \x20 TIME INDEX (`ts`),\n\
+             \x20 PRIMARY KEY (`device`)\n\
+             )\n\
+             ENGINE=mito\n\
+             WITH(\n\
+             \x20 append_mode = 'true',\n\
+             \x20 skip_wal = 'true'\n\
+             )"
+        );
+    }
+
+    #[test]
+    fn test_fulltext_column_options() {
+        let fulltext = FulltextOptions::new()
+            .with_analyzer(FulltextAnalyzer::Chinese)
+            .with_case_sensitive(true);
+        let table = TableSchema::builder()
+            .name("logs")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field_with_options(
+                "message",
+                ColumnDataType::String,
+                ColumnOptions::new().with_fulltext(fulltext),
+            )
+            .add_field("level", ColumnDataType::String);
+
+        let api_schema = table.to_api_schema();
+        assert!(api_schema[0].options.is_none());
+        assert!(api_schema[2].options.is_none());
+        let options = &api_schema[1].options.as_ref().unwrap().options;
+        let grpc: serde_json::Value = serde_json::from_str(&options["fulltext"]).unwrap();
+        assert_eq!(
+            grpc,
+            serde_json::json!({"enable": true, "analyzer": "Chinese", "case-sensitive": true})
+        );
+
+        let column = Column::from_api(&api_schema[1]).unwrap();
+        assert_eq!(column, table.columns[1]);
+        assert_eq!(column.options.fulltext, Some(fulltext));
+
+        // A disabled index is the same as none
+        let mut disabled = api_schema[1].clone();
+        disabled.options = Some(api::ColumnOptions {
+            options: HashMap::from([("fulltext".to_string(), r#"{"enable":false}"#.to_string())]),
+        });
+        assert!(Column::from_api(&disabled).unwrap().options.is_empty());
+
+        let sql = table.to_create_table_sql(&[]).unwrap();
+        assert!(sql.contains(
+            "`message` STRING NULL FULLTEXT INDEX WITH(analyzer = 'Chinese', case_sensitive = 'true')"
+        ));
+        assert!(sql.contains("`level` STRING NULL,"));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&table).unwrap();
+            let restored: TableSchema = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.columns[1].options.fulltext, Some(fulltext));
+        }
+    }
+
+    #[test]
+    fn test_inverted_index_column_options() {
+        let table = TableSchema::builder()
+            .name("logs")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host", ColumnDataType::String)
+            .add_tag_with_options(
+                "service",
+                ColumnDataType::String,
+                ColumnOptions::new().with_inverted_index(true),
+            )
+            .add_field_with_options(
+                "trace_id",
+                ColumnDataType::String,
+                ColumnOptions::new().with_inverted_index(true),
+            );
+
+        let api_schema = table.to_api_schema();
+        // Columns without options are sent as before
+        assert!(api_schema[1].options.is_none());
+        for index in [2, 3] {
+            let options = &api_schema[index].options.as_ref().unwrap().options;
+            assert_eq!(options.len(), 1);
+            assert_eq!(options["inverted_index"], "true");
+        }
+        let restored: Vec<Column> = api_schema
+            .iter()
+            .map(|schema| Column::from_api(schema).unwrap())
+            .collect();
+        assert_eq!(restored, table.columns);
+        assert_eq!(restored[2].semantic_type, SemanticType::Tag);
+        assert!(restored[3].options.inverted_index);
+
+        let sql = table.to_create_table_sql(&[]).unwrap();
+        assert!(sql.contains("`host` STRING NULL,"));
+        assert!(sql.contains("`service` STRING NULL INVERTED INDEX,"));
+        assert!(sql.contains("`trace_id` STRING NULL INVERTED INDEX,"));
+        assert!(sql.contains("PRIMARY KEY (`host`, `service`)"));
+    }
+
+    #[test]
+    fn test_into_delete_request_keeps_key_columns() {
+        let table = TableSchema::builder()
+            .name("events")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("user_id", ColumnDataType::String)
+            .add_field("payload", ColumnDataType::String);
+
+        let request = table
+            .into_delete_request(vec![Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000),
+                Value::String("u42".to_string()),
+            ])])
+            .unwrap();
+        assert_eq!(request.deletes.len(), 1);
+        assert_eq!(request.deletes[0].table_name, "events");
+        let rows = request.deletes[0].rows.as_ref().unwrap();
+        let names: Vec<_> = rows.schema.iter().map(|c| c.column_name.as_str()).collect();
+        assert_eq!(names, ["ts", "user_id"]);
+        assert_eq!(rows.rows.len(), 1);
+
+        // A row with field values does not fit the key columns
+        let full_row = Row::from_values(vec![
+            Value::TimestampMillisecond(1_700_000_000_000),
+            Value::String("u42".to_string()),
+            Value::String("{}".to_string()),
+        ]);
+        assert!(table.into_delete_request(vec![full_row]).is_err());
+    }
+
+    #[test]
+    fn test_into_insert_request_matches_manual_construction() {
+        use crate::helpers::schema;
+
+        let table = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64)
+            .add_decimal128_field("price", 10, 2);
+
+        let rows = vec![
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_000_000),
+                Value::String("d1".to_string()),
+                Value::Float64(21.5),
+                Value::Decimal128(1999),
+            ]),
+            Row::from_values(vec![
+                Value::TimestampMillisecond(1_700_000_001_000),
+                Value::String("d2".to_string()),
+                Value::Null,
+                Value::Decimal128(-5),
+            ]),
+        ];
+        let request = table.into_insert_request(rows).unwrap();
+
+        let mut price = schema::field("price", ColumnDataType::Decimal128);
+        price.datatype_extension = Some(api::ColumnDataTypeExtension {
+            type_ext: Some(api::column_data_type_extension::TypeExt::DecimalType(
+                api::DecimalTypeExtension {
+                    precision: 10,
+                    scale: 2,
+                },
+            )),
+        });
+        let expected = api::RowInsertRequests {
+            inserts: vec![api::RowInsertRequest {
+                table_name: "sensor".to_string(),
+                rows: Some(api::Rows {
+                    schema: vec![
+                        schema::timestamp("ts", ColumnDataType::TimestampMillisecond),
+                        schema::tag("device", ColumnDataType::String),
+                        schema::field("temperature", ColumnDataType::Float64),
+                        price,
+                    ],
+                    rows: vec![
+                        api::Row {
+                            values: vec![
+                                values::timestamp_millisecond_value(1_700_000_000_000),
+                                values::string_value("d1".to_string()),
+                                values::f64_value(21.5),
+                                values::decimal128_value(1999),
+                            ],
+                        },
+                        api::Row {
+                            values: vec![
+                                values::timestamp_millisecond_value(1_700_000_001_000),
+                                values::string_value("d2".to_string()),
+                                values::none_value(),
+                                values::decimal128_value(-5),
+                            ],
+                        },
+                    ],
+                }),
+            }],
+        };
+        assert_eq!(request, expected);
+
+        let short_row = Row::from_values(vec![Value::TimestampMillisecond(0)]);
+        assert!(matches!(
+            table.into_insert_request(vec![short_row]),
+            Err(crate::Error::InvalidColumnCount {
+                expected: 4,
+                actual: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_u128_round_trip_through_decimal128() {
+        let counter = u128::from(u64::MAX) * 1000;
+        let row = Row::from_values(vec![
+            Value::try_from_u128(counter).unwrap(),
+            Value::try_from_u128(10_u128.pow(38) - 1).unwrap(),
+            Value::Null,
+        ]);
+        assert_eq!(row.get_u128(0), Some(counter));
+        assert_eq!(row.get_u128(1), Some(10_u128.pow(38) - 1));
+        assert_eq!(row.get_u128(2), None);
+
+        let row = Row::from_values(vec![Value::Decimal128(-1)]);
+        assert_eq!(row.get_u128(0), None);
+    }
+
+    #[test]
+    fn test_u128_out_of_decimal128_range() {
+        for v in [10_u128.pow(38), i128::MAX as u128 + 1, u128::MAX] {
+            assert!(matches!(
+                Value::try_from_u128(v),
+                Err(crate::Error::ValueOutOfRange { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decimal128_from_str() {
+        let cases = [
+            ("1234.56", 2, 123456),
+            ("-1234.56", 2, -123456),
+            ("+0.5", 2, 50),
+            ("-.05", 2, -5),
+            ("12", 2, 1200),
+            ("12.", 0, 12),
+            ("1.500", 1, 15),
+            ("-0.00", 2, 0),
+            ("007.10", 3, 7100),
+            ("1200", -2, 12),
+            ("99999999999999999999999999999999999999", 0, MAX_DECIMAL128),
+        ];
+        for (text, scale, expected) in cases {
+            assert_eq!(
+                Value::decimal128_from_str(text, scale).unwrap(),
+                Value::Decimal128(expected),
+                "parsing {text:?} at scale {scale}"
+            );
+        }
+
+        let kind = |text, scale| Value::decimal128_from_str(text, scale).unwrap_err().kind();
+        for (text, scale) in [
+            ("", 2),
+            ("-", 2),
+            (".", 2),
+            ("1.2.3", 2),
+            ("1e3", 0),
+            (" 1", 0),
+        ] {
+            assert_eq!(kind(text, scale), DecimalErrorKind::Syntax, "{text:?}");
+        }
+        // Digits that would be rounded away
+        assert_eq!(kind("1.234", 2), DecimalErrorKind::Precision);
+        assert_eq!(kind("1250", -2), DecimalErrorKind::Precision);
+
+        for (text, scale) in [
+            ("100000000000000000000000000000000000000", 0),
+            ("1", 38),
+            ("-170141183460469231731687303715884105728", 0),
+            ("9999999999999999999999999999999999999999999", 2),
+        ] {
+            assert_eq!(kind(text, scale), DecimalErrorKind::Overflow, "{text:?}");
+        }
+
+        let error = Value::decimal128_from_str("1.234", 2).unwrap_err();
+        assert_eq!(error.text(), "1.234");
+        assert_eq!(error.scale(), 2);
+        assert_eq!(
+            error.to_string(),
+            "Invalid decimal `1.234` for scale 2: more digits than the scale keeps"
+        );
+    }
+
+    #[test]
+    fn test_get_decimal128_as_string() {
+        let row = Row::from_values(vec![
+            Value::Decimal128(123456),
+            Value::Decimal128(-5),
+            Value::Decimal128(0),
+            Value::Decimal128(12),
+            Value::Null,
+        ]);
+        assert_eq!(row.get_decimal128_as_string(0, 2).unwrap(), "1234.56");
+        assert_eq!(row.get_decimal128_as_string(1, 2).unwrap(), "-0.05");
+        assert_eq!(row.get_decimal128_as_string(2, 2).unwrap(), "0.00");
+        assert_eq!(row.get_decimal128_as_string(3, 0).unwrap(), "12");
+        assert_eq!(row.get_decimal128_as_string(3, -2).unwrap(), "1200");
+        assert_eq!(row.get_decimal128_as_string(4, 2), None);
+
+        for text in ["-1234.56", "0.07", "42.00"] {
+            let value = Value::decimal128_from_str(text, 2).unwrap();
+            let row = Row::from_values(vec![value]);
+            assert_eq!(row.get_decimal128_as_string(0, 2).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_row_key_for() {
+        let schema = TableSchema::builder()
+            .name("cpu")
+            .build()
+            .unwrap()
+            .add_tag("host", ColumnDataType::String)
+            .add_tag("region", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("usage", ColumnDataType::Float64);
+        let row = |host: &str, ts: i64, usage: f64| {
+            Row::from_values(vec![
+                Value::String(host.to_string()),
+                Value::String("eu".to_string()),
+                Value::TimestampMillisecond(ts),
+                Value::Float64(usage),
+            ])
+        };
+        let first = row("a", 1, 0.5);
+        let second = row("a", 2, 0.7);
+        let other = row("b", 1, 0.5);
+
+        let key = first.key_for(&schema, &["host", "region"]).unwrap();
+        assert_eq!(
+            key.values(),
+            &[
+                Value::String("a".to_string()),
+                Value::String("eu".to_string())
+            ]
+        );
+        assert_eq!(key, second.key_for(&schema, &["host", "region"]).unwrap());
+        assert_ne!(key, other.key_for(&schema, &["host", "region"]).unwrap());
+
+        let keys: HashSet<RowKey> = [&first, &second, &other]
+            .iter()
+            .map(|row| row.key_for(&schema, &["host", "region"]).unwrap())
+            .collect();
+        assert_eq!(keys.len(), 2);
+
+        assert!(matches!(
+            first.key_for(&schema, &["hostname"]),
+            Err(crate::Error::UnknownColumn { .. })
+        ));
+        assert!(matches!(
+            Row::from_values(vec![Value::Null]).key_for(&schema, &["region"]),
+            Err(crate::Error::InvalidColumnIndex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_value_hash_matches_eq() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let state = RandomState::new();
+        let pairs = [
+            (
+                Value::Binary(vec![1, 2, 3]),
+                Value::BinaryChunks(vec![Bytes::from_static(&[1]), Bytes::from_static(&[2, 3])]),
+            ),
+            (Value::Float64(f64::NAN), Value::Float64(-f64::NAN)),
+            (Value::Float32(0.0), Value::Float32(-0.0)),
+        ];
+        for (a, b) in pairs {
+            assert_eq!(a, b);
+            assert_eq!(state.hash_one(&a), state.hash_one(&b));
+        }
+    }
+
+    #[test]
+    fn test_random_row_covers_every_type() {
+        use rand::SeedableRng;
+
+        // The log schema of the benchmark is checked by the benchmark's own tests;
+        // this one mixes in the types that schema lacks
+        let schema = TableSchema::builder()
+            .name("events")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("host_name", ColumnDataType::String)
+            .add_field("log_level", ColumnDataType::String)
+            .add_field_with_options(
+                "log_message",
+                ColumnDataType::String,
+                ColumnOptions::new().with_fulltext(FulltextOptions::new()),
+            )
+            .add_field("response_time_ms", ColumnDataType::Int64)
+            .add_field("sampled", ColumnDataType::Boolean)
+            .add_field("payload", ColumnDataType::Binary)
+            .add_field("attributes", ColumnDataType::Json)
+            .add_field("received", ColumnDataType::TimestampNanosecond)
+            .add_field("duration", ColumnDataType::TimeMicrosecond)
+            .add_decimal128_field("cost", 10, 2);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut nulls = 0;
+        for _ in 0..1000 {
+            let row = random_row(&schema, &mut rng);
+            schema.validate_row(&row).unwrap();
+
+            let ts = row.get_timestamp(0).expect("the time index is never null");
+            assert!(TimestampRange::default().contains(ts, TimeUnit::Millisecond));
+            if let Some(cost) = row.get_decimal128(10) {
+                assert!(cost.abs() < 10_i128.pow(10));
+            }
+            nulls += row.iter().filter(|v| matches!(v, Value::Null)).count();
+        }
+        // Nullable columns are sometimes null
+        assert!(nulls > 0);
+    }
+
+    #[test]
+    fn test_row_iter() {
+        let schema = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64);
+        let row = Row::from_values(vec![
+            Value::TimestampMillisecond(1_000),
+            Value::String("d1".to_string()),
+            Value::Null,
+        ]);
+
+        assert_eq!(row.values().len(), 3);
+        assert_eq!(row.iter().next(), Some(&Value::TimestampMillisecond(1_000)));
+        let rendered: Vec<String> = (&row).into_iter().map(Value::to_string).collect();
+        assert_eq!(rendered, ["1000ms", "d1", "NULL"]);
+
+        let named: Vec<(&str, &Value)> = row.iter_named(&schema).collect();
+        assert_eq!(
+            named,
+            [
+                ("ts", &Value::TimestampMillisecond(1_000)),
+                ("device", &Value::String("d1".to_string())),
+                ("temperature", &Value::Null),
+            ]
+        );
+
+        // A short row stops at its last value
+        let short = Row::from_values(vec![Value::TimestampMillisecond(1_000)]);
+        assert_eq!(short.iter_named(&schema).count(), 1);
+    }
+
+    #[test]
+    fn test_value_eq() {
+        assert_eq!(Value::Null, Value::Null);
+        assert_eq!(Value::Int64(7), Value::Int64(7));
+        assert_ne!(Value::Int64(7), Value::Int32(7));
+        assert_ne!(Value::Int64(7), Value::Null);
+        assert_ne!(
+            Value::TimestampMillisecond(1_000),
+            Value::TimestampSecond(1_000)
+        );
+        assert_ne!(
+            Value::String("{}".to_string()),
+            Value::Json("{}".to_string())
+        );
+
+        assert_eq!(Value::Float64(f64::NAN), Value::Float64(-f64::NAN));
+        assert_eq!(Value::Float32(f32::NAN), Value::Float32(f32::NAN));
+        assert_eq!(Value::Float64(0.0), Value::Float64(-0.0));
+        assert_ne!(Value::Float64(f64::NAN), Value::Float64(1.0));
+        assert_ne!(Value::Float64(1.0), Value::Float32(1.0));
+
+        let chunks = Value::BinaryChunks(vec![Bytes::from_static(b"ab"), Bytes::from_static(b"c")]);
+        assert_eq!(chunks, Value::Binary(b"abc".to_vec()));
+        assert_eq!(Value::Binary(b"abc".to_vec()), chunks);
+        assert_eq!(
+            chunks,
+            Value::BinaryChunks(vec![Bytes::from_static(b"a"), Bytes::from_static(b"bc")])
+        );
+
+        let row = Row::from_values(vec![
+            Value::TimestampMillisecond(1_000),
+            Value::Float64(f64::NAN),
+        ]);
+        assert_eq!(row.clone(), row);
+        assert_ne!(
+            row,
+            Row::from_values(vec![Value::TimestampMillisecond(1_000)])
+        );
+    }
+
+    #[test]
+    fn test_value_display() {
+        let cases = [
+            (Value::Int64(42), "42"),
+            (Value::Float64(0.5), "0.5"),
+            (Value::String("hello".to_string()), "hello"),
+            (Value::Boolean(true), "true"),
+            (Value::Null, "NULL"),
+            (
+                Value::TimestampMillisecond(1_700_000_000_000),
+                "1700000000000ms",
+            ),
+            (Value::TimestampNanosecond(-1), "-1ns"),
+            (Value::Date(19_000), "19000d"),
+            (Value::TimeMicrosecond(1_500), "1500us"),
+            (Value::Binary(vec![0x0a, 0x0b, 0x0c]), "0x0a0b0c (3 bytes)"),
+            (Value::Binary(Vec::new()), "0x (0 bytes)"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(value.to_string(), expected, "{value:?}");
+        }
+
+        let long = Value::BinaryChunks(vec![
+            Bytes::from(vec![0xff; 10]),
+            Bytes::from(vec![0x01; 10]),
+        ]);
+        assert_eq!(
+            long.to_string(),
+            "0xffffffffffffffffffff010101010101... (20 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_duration_round_trip() {
+        let latency = Duration::from_micros(1_500_250);
+        let value = Value::from_duration(latency, ColumnDataType::TimeMicrosecond).unwrap();
+        assert!(matches!(value, Value::TimeMicrosecond(1_500_250)));
+
+        let truncated = Value::from_duration(latency, ColumnDataType::TimeMillisecond).unwrap();
+        assert!(matches!(truncated, Value::TimeMillisecond(1_500)));
+
+        let row = Row::from_values(vec![value, truncated, Value::TimeSecond(-1), Value::Null]);
+        assert_eq!(row.get_duration(0), Some(latency));
+        assert_eq!(row.get_duration(1), Some(Duration::from_millis(1_500)));
+        assert_eq!(row.get_duration(2), None);
+        assert_eq!(row.get_duration(3), None);
+
+        assert!(matches!(
+            Value::from_duration(
+                Duration::from_secs(30 * 86_400),
+                ColumnDataType::TimeMillisecond
+            ),
+            Err(crate::Error::ValueOutOfRange { .. })
+        ));
+        assert!(matches!(
+            Value::from_duration(latency, ColumnDataType::IntervalDayTime),
+            Err(crate::Error::UnsupportedDataType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_row() {
+        let schema = TableSchema::builder()
+            .name("metrics")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_field("value", ColumnDataType::Int64);
+
+        let row = Row::from_values(vec![Value::TimestampMillisecond(0), Value::Int64(1)]);
+        assert!(schema.validate_row(&row).is_ok());
+        let row = Row::from_values(vec![Value::TimestampMillisecond(0), Value::Null]);
+        assert!(schema.validate_row(&row).is_ok());
+
+        let row = Row::from_values(vec![Value::TimestampMillisecond(0)]);
+        assert!(matches!(
+            schema.validate_row(&row),
+            Err(crate::Error::InvalidColumnCount {
+                expected: 2,
+                actual: 1,
+                ..
+            })
+        ));
+
+        let row = Row::from_values(vec![Value::TimestampMillisecond(0), Value::Int32(1)]);
+        assert!(matches!(
+            schema.validate_row(&row),
+            Err(crate::Error::InvalidColumnValue { column, expected: ColumnDataType::Int64, .. })
+                if column == "value"
+        ));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_round_trip() {
+        use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+        let dt = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+            + chrono::Duration::nanoseconds(123_456_789);
+        let date = NaiveDate::from_ymd_opt(1969, 12, 30).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(23, 59, 58, 987_654_321).unwrap();
+
+        assert!(matches!(
+            Value::from_datetime_secs(dt),
+            Value::TimestampSecond(1_704_164_645)
+        ));
+        assert!(matches!(
+            Value::from_datetime_millis(dt),
+            Value::TimestampMillisecond(1_704_164_645_123)
+        ));
+        assert!(matches!(
+            Value::from_datetime_micros(dt),
+            Value::TimestampMicrosecond(1_704_164_645_123_456)
+        ));
+        assert!(matches!(
+            Value::from_datetime_nanos(dt),
+            Ok(Value::TimestampNanosecond(1_704_164_645_123_456_789))
+        ));
+        let far_future = Utc.with_ymd_and_hms(2300, 1, 1, 0, 0, 0).unwrap();
+        assert!(Value::from_datetime_nanos(far_future).is_err());
+        assert!(matches!(Value::from_naive_date(date), Value::Date(-2)));
+        assert!(matches!(
+            Value::from_naive_time(time),
+            Value::TimeNanosecond(86_398_987_654_321)
+        ));
+
+        let row = Row::from_values(vec![
+            Value::from_datetime_millis(dt),
+            Value::Datetime(1_704_164_645_123),
+            Value::from_naive_date(date),
+            Value::from_naive_time(time),
+            Value::TimeMillisecond(1_500),
+            Value::Null,
+        ]);
+        let millis = dt - chrono::Duration::nanoseconds(456_789);
+        assert_eq!(row.get_datetime_utc(0), Some(millis));
+        assert_eq!(row.get_datetime_utc(1), Some(millis));
+        assert_eq!(row.get_datetime_utc(5), None);
+        assert_eq!(row.get_naive_date(2), Some(date));
+        assert_eq!(row.get_naive_time(3), Some(time));
+        assert_eq!(
+            row.get_naive_time(4),
+            NaiveTime::from_hms_milli_opt(0, 0, 1, 500)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_get_timestamp_datetime() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let expected = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let row = Row::from_values(vec![
+            Value::TimestampSecond(1_704_164_645),
+            Value::TimestampMillisecond(1_704_164_645_123),
+            Value::TimestampMicrosecond(1_704_164_645_123_456),
+            Value::TimestampNanosecond(1_704_164_645_123_456_789),
+            Value::TimestampNanosecond(i64::MIN),
+            Value::TimestampSecond(i64::MAX),
+            Value::Null,
+        ]);
+
+        assert_eq!(row.get_timestamp_datetime(0), Some(expected));
+        assert_eq!(
+            row.get_timestamp_datetime(1),
+            Some(expected + chrono::Duration::milliseconds(123))
+        );
+        assert_eq!(
+            row.get_timestamp_datetime(2),
+            Some(expected + chrono::Duration::microseconds(123_456))
+        );
+        assert_eq!(
+            row.get_timestamp_datetime(3),
+            Some(expected + chrono::Duration::nanoseconds(123_456_789))
+        );
+        assert_eq!(
+            row.get_timestamp_datetime(4),
+            Some(DateTime::from_timestamp_nanos(i64::MIN))
+        );
+        assert_eq!(row.get_timestamp_datetime(5), None);
+        assert_eq!(row.get_timestamp_datetime(6), None);
+    }
+
+    #[test]
+    fn test_binary_from_hex() {
+        for text in ["deadBEEF", "0xdeadbeef", "0XDEADBEEF"] {
+            let row = Row::from_values(vec![Value::binary_from_hex(text).unwrap()]);
+            assert_eq!(row.get_binary(0), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        }
+        let row = Row::from_values(vec![Value::binary_from_hex("").unwrap()]);
+        assert_eq!(row.get_binary(0), Some(vec![]));
+
+        let row = Row::from_values(vec![Value::binary_from_hex("0x").unwrap()]);
+        assert_eq!(row.get_binary(0), Some(vec![]));
+
+        for text in ["abc", "0xzz", "de ad"] {
+            assert!(matches!(
+                Value::binary_from_hex(text),
+                Err(crate::Error::InvalidBinaryEncoding { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_binary_from_base64() {
+        let row = Row::from_values(vec![Value::binary_from_base64("3q2+7w==").unwrap()]);
+        assert_eq!(row.get_binary(0), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+
+        for text in ["3q2+7w", "3q2+7w=!", "not base64"] {
+            assert!(matches!(
+                Value::binary_from_base64(text),
+                Err(crate::Error::InvalidBinaryEncoding { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_iter_with_columns() {
+        let schema = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64);
+        let row = Row::from_values(vec![
+            Value::TimestampMillisecond(1_000),
+            Value::String("d1".to_string()),
+            Value::Float64(21.5),
+        ]);
+
+        let pairs: Vec<_> = iter_with_columns(&row, &schema).collect();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].0.name, "ts");
+        assert!(matches!(pairs[0].1, Value::TimestampMillisecond(1_000)));
+        assert_eq!(pairs[1].0.name, "device");
+        assert!(matches!(pairs[1].1, Value::String(s) if s == "d1"));
+        assert_eq!(pairs[2].0.name, "temperature");
+        assert!(matches!(pairs[2].1, Value::Float64(v) if *v == 21.5));
+
+        // A short row stops the iteration at its last value
+        let short = Row::from_values(vec![Value::TimestampMillisecond(1_000)]);
+        let names: Vec<_> = iter_with_columns(&short, &schema)
+            .map(|(column, _)| column.name.as_str())
+            .collect();
+        assert_eq!(names, ["ts"]);
+    }
+
+    #[test]
+    fn test_rows_equal_by_name() {
+        let a_schema = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64);
+        let b_schema = TableSchema::builder()
+            .name("sensor")
+            .build()
+            .unwrap()
+            .add_tag("device", ColumnDataType::String)
+            .add_field("temperature", ColumnDataType::Float64)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond);
+
+        let a = Row::from_values(vec![
+            Value::TimestampMillisecond(1_700_000_000_000),
+            Value::String("d1".to_string()),
+            Value::Float64(f64::NAN),
+        ]);
+        let b = Row::from_values(vec![
+            Value::String("d1".to_string()),
+            Value::Float64(f64::NAN),
+            Value::TimestampMillisecond(1_700_000_000_000),
+        ]);
+        assert!(rows_equal_by_name(&a, &a_schema, &b, &b_schema));
+        assert!(rows_equal_by_name(&b, &b_schema, &a, &a_schema));
+
+        let c = Row::from_values(vec![
+            Value::String("d1".to_string()),
+            Value::Float64(21.5),
+            Value::TimestampMillisecond(1_700_000_000_000),
+        ]);
+        assert!(!rows_equal_by_name(&a, &a_schema, &c, &b_schema));
+
+        // Same position-wise data but different column names
+        let renamed = b_schema
+            .clone()
+            .add_field("humidity", ColumnDataType::Float64);
+        let d = Row::from_values(vec![
+            Value::String("d1".to_string()),
+            Value::Float64(f64::NAN),
+            Value::TimestampMillisecond(1_700_000_000_000),
+            Value::Null,
+        ]);
+        assert!(!rows_equal_by_name(&a, &a_schema, &d, &renamed));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_row_round_trip() {
+        let row = Row::from_values(vec![
+            Value::Boolean(true),
+            Value::Int64(-42),
+            Value::Uint8(7),
+            Value::Float64(1.5),
+            Value::String("host-1".to_string()),
+            Value::Binary(vec![1, 2, 3]),
+            Value::TimestampNanosecond(1_700_000_000_000_000_001),
+            Value::Decimal128(i128::MAX),
+            Value::Json(r#"{"k":1}"#.to_string()),
+            Value::Null,
+        ]);
+
+        let json = serde_json::to_string(&row).unwrap();
+        assert!(json.contains(r#"{"type":"Binary","value":"AQID"}"#));
+        assert!(json.contains(&format!(
+            r#"{{"type":"Decimal128","value":"{}"}}"#,
+            i128::MAX
+        )));
+
+        let decoded: Row = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, row);
+        // The unit is part of the tag
+        assert!(matches!(
+            decoded.value(6),
+            Some(Value::TimestampNanosecond(1_700_000_000_000_000_001))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_table_schema_round_trip() {
+        let schema = TableSchema::builder()
+            .name("prices")
+            .build()
+            .unwrap()
+            .add_tag("symbol", ColumnDataType::String)
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_decimal128_field("price", 38, 10);
+
+        let json = serde_json::to_string(&schema).unwrap();
+        assert!(json.contains(r#""data_type":"TIMESTAMP_MILLISECOND""#));
+
+        let decoded: TableSchema = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.name(), "prices");
+        assert_eq!(decoded.columns().len(), 3);
+        let price = &decoded.columns()[2];
+        assert_eq!(price.data_type, ColumnDataType::Decimal128);
+        assert_eq!(price.semantic_type, SemanticType::Field);
+        assert_eq!(
+            price.data_type_extension,
+            Some(DataTypeExtension::Decimal128 {
+                precision: 38,
+                scale: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_stable_masked_token() {
+        let salt = b"pepper";
+        let token = Value::String("alice@example.com".to_string()).stable_masked_token(salt);
+        assert_eq!(token.len(), 64);
+        assert_eq!(
+            token,
+            Value::from("alice@example.com").stable_masked_token(salt)
+        );
+
+        assert_ne!(
+            token,
+            Value::from("bob@example.com").stable_masked_token(salt)
+        );
+        assert_ne!(
+            token,
+            Value::from("alice@example.com").stable_masked_token(b"salt")
+        );
+        assert_ne!(
+            Value::Int32(1).stable_masked_token(salt),
+            Value::Int64(1).stable_masked_token(salt)
+        );
+        assert_eq!(
+            Value::Binary(b"abcd".to_vec()).stable_masked_token(salt),
+            Value::BinaryChunks(vec![Bytes::from_static(b"ab"), Bytes::from_static(b"cd")])
+                .stable_masked_token(salt)
+        );
+    }
+
+    #[test]
+    fn test_column_lookup_by_name() {
+        let schema = TableSchema::builder()
+            .name("access_log")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampMillisecond)
+            .add_tag("Host", ColumnDataType::String)
+            .add_field("latency", ColumnDataType::Float64);
+
+        assert_eq!(schema.column_index("ts"), Some(0));
+        assert_eq!(schema.column_index("latency"), Some(2));
+        let host = schema.column("Host").unwrap();
+        assert_eq!(host.semantic_type, SemanticType::Tag);
+        assert_eq!(host.data_type, ColumnDataType::String);
+
+        assert_eq!(schema.column_index("missing"), None);
+        assert!(schema.column("missing").is_none());
+        // Lookups are case-sensitive unless the schema normalizes case
+        assert_eq!(schema.column_index("host"), None);
+
+        // Adding a column after a lookup resets the index
+        let schema = schema.add_field("status", ColumnDataType::Int32);
+        assert_eq!(schema.column_index("status"), Some(3));
+
+        let lower = TableSchema::builder()
+            .name("access_log")
+            .normalize_case(CaseMode::Lower)
+            .build()
+            .unwrap()
+            .add_tag("Host", ColumnDataType::String);
+        assert_eq!(lower.column_index("HOST"), Some(0));
+        assert_eq!(lower.column("host").unwrap().name, "host");
+    }
+
+    #[test]
+    fn test_api_schema_round_trip() {
+        let schema = TableSchema::builder()
+            .name("prices")
+            .build()
+            .unwrap()
+            .add_timestamp("ts", ColumnDataType::TimestampNanosecond)
+            .add_tag("symbol", ColumnDataType::String)
+            .add_field("volume", ColumnDataType::Uint64)
+            .add_decimal128_field("price", 18, 4);
+
+        let decoded = TableSchema::from_api_schema("prices", &schema.to_api_schema()).unwrap();
+        assert_eq!(decoded, schema);
+
+        let mut columns = schema.to_api_schema();
+        columns[2].datatype = -1;
+        let result = TableSchema::from_api_schema("prices", &columns);
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidColumnSchema { column, .. }) if column == "volume"
+        ));
+    }
 }